@@ -0,0 +1,353 @@
+#![allow(clippy::similar_names)]
+
+use std::str::FromStr;
+
+use serde_json::json;
+
+use crate::bandwidth_utils::{TrafficMode, TrafficStatistics};
+use crate::earfcn_utils::get_lte_frequencies;
+use crate::modem_utils::{
+    BatteryStatus, ChannelInfo, DeviceInformation, LteSignalInfo, ModemError, ModemErrorKind,
+    ModemInfoParser, ModemStatus, NetworkMode, PlmnStatus, SignalInfo, VendorError,
+    WcdmaSignalInfo,
+};
+use crate::network_utils::{new_session_agent, post_url_json_with_agent};
+use crate::utils::copy_string_to_array;
+
+const WEBAPI_QUERY: &str = "/jrd/webapi";
+
+/// Static key that web UI of all LinkZone devices sends with requests
+const VERIFICATION_KEY: &str = "KSDHSDFOGQ5WERYTUIQWERTYUISDFG1HJZXCVCXBN2GDSMNDHKVKFsVBNf";
+
+/// Convert 'NetworkType' of network info to `NetworkMode` enum
+fn get_mode_by_network_type(network_type: i64) -> NetworkMode {
+    match network_type {
+        1 | 2 => NetworkMode::Gsm,
+        3..=7 => NetworkMode::Wcdma,
+        8 | 9 => NetworkMode::Lte,
+        _ => NetworkMode::Unknown,
+    }
+}
+
+/// Parse numeric parameter that may be reported either as number or as string
+fn get_alcatel_i64(val: &serde_json::Value) -> Option<i64> {
+    val.as_i64()
+        .or_else(|| val.as_str().and_then(|val| val.trim().parse::<i64>().ok()))
+}
+
+/// Get required numeric field of network info
+fn required_alcatel_i64(json: &serde_json::Value, name: &str) -> Result<i64, ModemError> {
+    get_alcatel_i64(&json[name]).ok_or_else(|| {
+        eprintln!("Network info doesn't have required field '{name}'");
+        ModemError::data_parsing(name).with_endpoint(WEBAPI_QUERY)
+    })
+}
+
+/*
+ * Utils for Alcatel
+ */
+
+pub struct AlcatelParser {
+    agent: ureq::Agent,
+}
+
+impl AlcatelParser {
+    pub fn new() -> Self {
+        Self {
+            agent: new_session_agent(),
+        }
+    }
+
+    /// Call JSON-RPC method of web API. Returns 'result' of the response
+    fn call_method(
+        agent: &ureq::Agent,
+        host: &str,
+        method: &str,
+    ) -> Result<serde_json::Value, ModemError> {
+        let request = json!({"jsonrpc": "2.0", "method": method, "params": {}, "id": "1"});
+        let mut response = post_url_json_with_agent(
+            agent,
+            host,
+            WEBAPI_QUERY,
+            &[("_TclRequestVerificationKey", VERIFICATION_KEY)],
+            &request,
+        )
+        .map_err(|e| e.context(&format!("Method '{method}' failed")))?;
+
+        let error = &response["error"];
+        if !error.is_null() {
+            eprintln!("Method '{method}' error: {error}");
+            return Err(ModemError::new(
+                ModemErrorKind::Access,
+                &format!("Method '{method}' failed"),
+            )
+            .with_endpoint(WEBAPI_QUERY)
+            .with_vendor_error(VendorError {
+                code: get_alcatel_i64(&error["code"]).map(|code| code.to_string()),
+                message: error["message"]
+                    .as_str()
+                    .map(std::string::ToString::to_string),
+                explanation: None,
+            }));
+        }
+
+        Ok(response["result"].take())
+    }
+
+    fn parse_battery_status_json(json: &serde_json::Value) -> Option<BatteryStatus> {
+        let battery_percent = get_alcatel_i64(&json["bat_cap"])?;
+
+        let battery_status_str = match get_alcatel_i64(&json["chg_state"]) {
+            Some(0) => "Charging",
+            Some(1) => "Charged",
+            _ => "No Charge",
+        };
+
+        let mut battery_status = ['\0'; 20];
+        copy_string_to_array!(battery_status, battery_status_str);
+
+        Some(BatteryStatus {
+            percent: battery_percent,
+            status: battery_status,
+        })
+    }
+
+    fn parse_traffic_statistics_json(json: &serde_json::Value) -> Option<TrafficStatistics> {
+        // Current rate in bytes per second
+        match (
+            get_alcatel_i64(&json["DlRate"]),
+            get_alcatel_i64(&json["UlRate"]),
+        ) {
+            (None, None) => None,
+            (dl, ul) => Some(TrafficStatistics {
+                dl: dl.unwrap_or(0) * 8,
+                ul: ul.unwrap_or(0) * 8,
+            }),
+        }
+    }
+
+    fn parse_device_info_json(json: &serde_json::Value) -> DeviceInformation {
+        let model_str = json["DeviceName"].as_str().unwrap_or_default();
+        DeviceInformation::from("Alcatel", model_str)
+    }
+
+    /// Check if host has LinkZone web API
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let agent = new_session_agent();
+        AlcatelParser::call_method(&agent, host, "GetSystemStatus").ok()?;
+
+        let system_info = AlcatelParser::call_method(&agent, host, "GetSystemInfo")
+            .unwrap_or(serde_json::Value::Null);
+        Some(AlcatelParser::parse_device_info_json(&system_info))
+    }
+
+    fn parse_network_info_json(json: &serde_json::Value) -> Result<ModemStatus, ModemError> {
+        let network_type = required_alcatel_i64(json, "NetworkType")?;
+        let mode = get_mode_by_network_type(network_type);
+
+        let rssi = get_alcatel_i64(&json["RSSI"]).unwrap_or(0);
+
+        let plmn_str = json["PLMN"].as_str().unwrap_or_default();
+        let plmn = PlmnStatus::from_str(plmn_str).expect("Unable to convert PLMN from string");
+
+        let mut band = ['\0'; 20];
+        if let Some(band_number) = get_alcatel_i64(&json["Band"]) {
+            let band_str = if mode == NetworkMode::Lte {
+                format!("LTE B{band_number}")
+            } else {
+                format!("B{band_number}")
+            };
+            copy_string_to_array!(band, band_str);
+        }
+
+        let cell_id = get_alcatel_i64(&json["CellId"]).unwrap_or(0);
+
+        let signal_info = match mode {
+            NetworkMode::Wcdma => {
+                let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
+
+                let (nb, cc) = (id / 10, id % 10);
+
+                SignalInfo::Wcdma(WcdmaSignalInfo {
+                    rscp: required_alcatel_i64(json, "RSCP")?,
+                    ecio: required_alcatel_i64(json, "ECIO")?,
+                    nb,
+                    cc,
+                    rnc,
+                    psc: 0,
+                })
+            }
+            NetworkMode::Lte => {
+                let (enb, id) = (cell_id >> 8, cell_id & 0xFF);
+
+                SignalInfo::Lte(LteSignalInfo {
+                    rsrq: required_alcatel_i64(json, "RSRQ")?,
+                    rsrp: required_alcatel_i64(json, "RSRP")?,
+                    sinr: required_alcatel_i64(json, "SINR")?,
+                    ca_count: 0,
+                    enb: get_alcatel_i64(&json["eNBID"]).unwrap_or(enb),
+                    id,
+                    pci: get_alcatel_i64(&json["PCI"]).unwrap_or(-1),
+                })
+            }
+            _ => SignalInfo::None,
+        };
+
+        let channel_info = get_alcatel_i64(&json["DL_channel"])
+            .filter(|_| mode == NetworkMode::Lte)
+            .map(|earfcn| {
+                let (dl_frequency, ul_frequency) = match get_lte_frequencies(earfcn) {
+                    Some((dl, ul)) => (Some(dl), ul),
+                    None => (None, None),
+                };
+                ChannelInfo {
+                    earfcn: Some(earfcn),
+                    dl_frequency,
+                    ul_frequency,
+                    dl_bandwidth: None,
+                    ul_bandwidth: None,
+                }
+            });
+
+        Ok(ModemStatus {
+            mode,
+            plmn,
+            rssi,
+            cell_id,
+            signal_info,
+            band,
+            carriers: Vec::new(),
+            channel_info,
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_info: DeviceInformation::from("Alcatel", ""),
+            battery_status: None,
+            device_temp: None,
+            traffic_statistics: None,
+            traffic_mode: TrafficMode::Absolute,
+        })
+    }
+}
+
+impl ModemInfoParser for AlcatelParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        let agent = &self.agent;
+
+        let network_info = AlcatelParser::call_method(agent, host, "GetNetworkInfo")
+            .map_err(|e| e.context(&format!("Cannot get network info from host {host}")))?;
+        let mut modem_status = AlcatelParser::parse_network_info_json(&network_info)?;
+
+        // Optional info. Routers without battery don't support battery state
+        if let Ok(battery_state) = AlcatelParser::call_method(agent, host, "GetBatteryState") {
+            modem_status.battery_status = AlcatelParser::parse_battery_status_json(&battery_state);
+        }
+        if let Ok(connection_state) = AlcatelParser::call_method(agent, host, "GetConnectionState")
+        {
+            modem_status.traffic_statistics =
+                AlcatelParser::parse_traffic_statistics_json(&connection_state);
+        }
+        if let Ok(system_info) = AlcatelParser::call_method(agent, host, "GetSystemInfo") {
+            modem_status.device_info = AlcatelParser::parse_device_info_json(&system_info);
+        }
+
+        Ok(modem_status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Result of 'GetNetworkInfo' of MW40V in LTE mode. Some firmwares report numbers
+    /// as strings
+    const NETWORK_INFO_JSON: &str = r#"{
+        "PLMN": "20801", "NetworkType": 8, "NetworkName": "Orange F", "SpnName": "Orange F",
+        "Roaming": 1, "DomesticRoaming": 1, "SignalStrength": 3, "mcc": "208", "mnc": "01",
+        "SINR": "11", "RSRP": "-96", "RSSI": "-67", "RSRQ": "-10", "ECIO": "", "RSCP": "",
+        "Band": "3", "DL_channel": 1300, "UL_channel": 19300, "CellId": "27436305",
+        "eNBID": "107173", "PCI": "293"
+    }"#;
+
+    /// Result of 'GetNetworkInfo' in WCDMA mode
+    const WCDMA_NETWORK_INFO_JSON: &str = r#"{
+        "PLMN": "20801", "NetworkType": 5, "RSSI": -73, "RSCP": -89, "ECIO": -7,
+        "Band": 1, "CellId": 13375493
+    }"#;
+
+    fn parse_json(json: &str) -> Result<ModemStatus, ModemError> {
+        AlcatelParser::parse_network_info_json(&serde_json::from_str(json).unwrap())
+    }
+
+    #[test]
+    fn lte_network_info() {
+        let modem_status = parse_json(NETWORK_INFO_JSON).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE");
+        assert_eq!(modem_status.get_plmn().trim_end_matches('\0'), "20801");
+        assert_eq!(modem_status.get_band(), "LTE B3");
+        assert_eq!(modem_status.rssi, -67);
+        assert_eq!(
+            modem_status.get_frequencies().as_deref(),
+            Some("1815.0/1720.0 MHz")
+        );
+
+        let SignalInfo::Lte(lte_info) = modem_status.signal_info else {
+            panic!("Signal info is not LTE");
+        };
+        assert_eq!(lte_info.rsrp, -96);
+        assert_eq!(lte_info.rsrq, -10);
+        assert_eq!(lte_info.sinr, 11);
+        assert_eq!(lte_info.enb, 107_173);
+        assert_eq!(lte_info.id, 17);
+        assert_eq!(lte_info.pci, 293);
+    }
+
+    #[test]
+    fn wcdma_network_info() {
+        let modem_status = parse_json(WCDMA_NETWORK_INFO_JSON).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "WCDMA");
+        assert_eq!(modem_status.get_band(), "B1");
+        assert!(modem_status.channel_info.is_none());
+
+        let SignalInfo::Wcdma(wcdma_info) = modem_status.signal_info else {
+            panic!("Signal info is not WCDMA");
+        };
+        assert_eq!(wcdma_info.rscp, -89);
+        assert_eq!(wcdma_info.ecio, -7);
+        assert_eq!(wcdma_info.rnc, 204);
+        assert_eq!(wcdma_info.nb, 614);
+        assert_eq!(wcdma_info.cc, 9);
+    }
+
+    #[test]
+    fn missing_signal_value() {
+        let json = NETWORK_INFO_JSON.replace(r#""RSRP": "-96""#, r#""RSRP": """#);
+        let Err(e) = parse_json(&json) else {
+            panic!("Empty RSRP is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+
+    #[test]
+    fn battery_and_connection_state() {
+        let battery_state = serde_json::json!({"chg_state": 0, "bat_cap": 64, "bat_level": 3});
+        let battery_status = AlcatelParser::parse_battery_status_json(&battery_state).unwrap();
+        assert_eq!(battery_status.percent, 64);
+        assert_eq!(
+            battery_status
+                .status
+                .iter()
+                .collect::<String>()
+                .trim_matches('\0'),
+            "Charging"
+        );
+
+        let connection_state =
+            serde_json::json!({"ConnectionStatus": 2, "DlRate": 1200, "UlRate": 300});
+        let traffic_statistics =
+            AlcatelParser::parse_traffic_statistics_json(&connection_state).unwrap();
+        assert_eq!(traffic_statistics.dl, 9600);
+        assert_eq!(traffic_statistics.ul, 2400);
+    }
+}
@@ -0,0 +1,1068 @@
+#![allow(clippy::similar_names)]
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use netgear_telnet::GstatusInfo;
+
+use crate::bandwidth_utils::TrafficMode;
+use crate::earfcn_utils::get_lte_frequencies;
+use crate::modem_utils::{
+    CarrierRole, ChannelInfo, ComponentCarrier, DeviceInformation, DeviceTemperature,
+    LteSignalInfo, ModemError, ModemErrorKind, ModemInfoParser, ModemStatus, NetworkMode,
+    NrSignalInfo, PlmnStatus, SignalInfo, WcdmaSignalInfo,
+};
+use crate::utils::{copy_string_to_array, parse_band_number, parse_bandwidth_mhz};
+
+const SERIAL_BAUD_RATE: u32 = 115_200;
+
+/// Timeout of a single read from the port
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Maximal time of waiting for final result code of a command
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(3);
+
+const CESQ_COMMAND: &str = "AT+CESQ";
+const QENG_COMMAND: &str = "AT+QENG=\"servingcell\"";
+const GSTATUS_COMMAND: &str = "AT!GSTATUS?";
+const HCSQ_COMMAND: &str = "AT^HCSQ?";
+
+/// LTE channel bandwidths in MHz by index that Quectel modems report
+const QUECTEL_LTE_BANDWIDTHS: [f64; 6] = [1.4, 3.0, 5.0, 10.0, 15.0, 20.0];
+
+/// Set of vendor-specific commands for the serving cell
+#[derive(Clone, Copy, PartialEq)]
+pub enum AtDialect {
+    /// Only standard 3GPP commands
+    Generic,
+    /// `AT+QENG="servingcell"`
+    Quectel,
+    /// `AT!GSTATUS?`
+    Sierra,
+    /// `AT^HCSQ?`
+    Huawei,
+}
+
+impl AtDialect {
+    /// Name of manufacturer as reported by `AT+CGMI`
+    fn get_manufacturer_name(self) -> Option<&'static str> {
+        match self {
+            AtDialect::Generic => None,
+            AtDialect::Quectel => Some("quectel"),
+            AtDialect::Sierra => Some("sierra"),
+            AtDialect::Huawei => Some("huawei"),
+        }
+    }
+}
+
+/*
+ * Connection to AT port
+ */
+
+trait AtStream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> AtStream for T {}
+
+/// Serial ports are paths of devices ('/dev/ttyUSB2', pseudo-terminals) or Windows COM ports
+/// ('COM3'). Host names like 'comms.lan:23' are TCP addresses
+pub fn is_serial_device(address: &str) -> bool {
+    address.starts_with('/')
+        || address
+            .to_uppercase()
+            .strip_prefix("COM")
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Command line with AT commands over serial port or raw TCP socket
+pub struct AtConnection {
+    stream: Box<dyn AtStream>,
+    buffer: String,
+}
+
+impl AtConnection {
+    /// Open serial device or TCP socket in form of 'host:port'
+    pub fn open(address: &str) -> Result<Self, ModemError> {
+        let stream: Box<dyn AtStream> = if is_serial_device(address) {
+            let port = serialport::new(address, SERIAL_BAUD_RATE)
+                .timeout(READ_TIMEOUT)
+                .open()
+                .map_err(|e| {
+                    eprintln!("Cannot open serial port {address}: {e}");
+                    ModemError::port_connection(&format!("Cannot open serial port {address}: {e}"))
+                })?;
+            Box::new(port)
+        } else {
+            let socket_address = address
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .ok_or_else(|| {
+                    ModemError::port_connection(&format!(
+                        "Address {address} is not a serial port or 'host:port'"
+                    ))
+                })?;
+            let stream = TcpStream::connect_timeout(&socket_address, COMMAND_TIMEOUT)
+                .and_then(|stream| {
+                    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+                    Ok(stream)
+                })
+                .map_err(|e| {
+                    eprintln!("Cannot connect to {address}: {e}");
+                    ModemError::port_connection(&format!("Cannot connect to {address}: {e}"))
+                })?;
+            Box::new(stream)
+        };
+
+        let mut connection = Self {
+            stream,
+            buffer: String::new(),
+        };
+        // Disable echo of commands
+        connection.command("ATE0")?;
+        Ok(connection)
+    }
+
+    /// Send command and get lines of response before final 'OK'
+    pub fn command(&mut self, command: &str) -> Result<Vec<String>, ModemError> {
+        // Drop unsolicited messages
+        self.buffer.clear();
+
+        self.stream
+            .write_all(format!("{command}\r").as_bytes())
+            .and_then(|()| self.stream.flush())
+            .map_err(|e| {
+                ModemError::port_connection(&format!("Cannot send command: {e}"))
+                    .with_endpoint(command)
+            })?;
+
+        let deadline = Instant::now() + COMMAND_TIMEOUT;
+        let mut lines = Vec::new();
+        let mut read_buffer = [0u8; 256];
+        loop {
+            while let Some(pos) = self.buffer.find(['\r', '\n']) {
+                let line = self.buffer[..pos].trim().to_string();
+                self.buffer.drain(..=pos);
+
+                if line.is_empty() || line == command {
+                    continue;
+                }
+                if line == "OK" {
+                    return Ok(lines);
+                }
+                if line == "ERROR"
+                    || line.starts_with("+CME ERROR")
+                    || line.starts_with("+CMS ERROR")
+                {
+                    return Err(ModemError::access(&format!("Command failed: {line}"))
+                        .with_endpoint(command));
+                }
+                lines.push(line);
+            }
+
+            if Instant::now() > deadline {
+                return Err(
+                    ModemError::port_connection("No response from modem").with_endpoint(command)
+                );
+            }
+
+            match self.stream.read(&mut read_buffer) {
+                Ok(0) => {
+                    return Err(
+                        ModemError::port_connection("Connection closed").with_endpoint(command)
+                    );
+                }
+                Ok(n) => self
+                    .buffer
+                    .push_str(&String::from_utf8_lossy(&read_buffer[..n])),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => {
+                    return Err(ModemError::port_connection(&format!("Read error: {e}"))
+                        .with_endpoint(command));
+                }
+            }
+        }
+    }
+}
+
+/*
+ * Parsing of responses
+ */
+
+/// Values of the response line with prefix (e.g. '+CESQ:') with quotes removed
+fn get_response_values(lines: &[String], prefix: &str) -> Option<Vec<String>> {
+    lines
+        .iter()
+        .find_map(|line| line.strip_prefix(prefix))
+        .map(split_response_values)
+}
+
+fn split_response_values(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|val| val.trim().trim_matches('"').to_string())
+        .collect()
+}
+
+fn get_i64(values: &[String], index: usize) -> Option<i64> {
+    values.get(index).and_then(|val| val.parse::<i64>().ok())
+}
+
+fn get_hex_i64(values: &[String], index: usize) -> Option<i64> {
+    values
+        .get(index)
+        .and_then(|val| i64::from_str_radix(val, 16).ok())
+}
+
+/// Error of the response that doesn't report required signal parameter
+fn missing_value(command: &str, name: &str) -> ModemError {
+    eprintln!("Response of {command} doesn't have required value '{name}'");
+    ModemError::data_parsing(name).with_endpoint(command)
+}
+
+/// Scale encoded value of signal parameter. Returns `None` for 'unknown' value 255
+fn decode_signal(values: &[String], index: usize, offset: f64, step: f64) -> Option<i64> {
+    get_i64(values, index)
+        .filter(|val| *val != 255)
+        .map(|val| (offset + val as f64 * step).round() as i64)
+}
+
+fn get_lte_channel_info(earfcn: i64, bandwidth: Option<f64>) -> ChannelInfo {
+    let (dl_frequency, ul_frequency) = match get_lte_frequencies(earfcn) {
+        Some((dl, ul)) => (Some(dl), ul),
+        None => (None, None),
+    };
+    ChannelInfo {
+        earfcn: Some(earfcn),
+        dl_frequency,
+        ul_frequency,
+        dl_bandwidth: bandwidth,
+        ul_bandwidth: bandwidth,
+    }
+}
+
+/// Parameters of the serving cell reported by dialect-specific command
+struct ServingCell {
+    mode: NetworkMode,
+    rssi: i64,
+    cell_id: i64,
+    signal_info: SignalInfo,
+    band: String,
+    channel_info: Option<ChannelInfo>,
+    carriers: Vec<ComponentCarrier>,
+    tac: Option<i64>,
+    antenna_rsrp: Vec<i64>,
+    device_temp: Option<DeviceTemperature>,
+}
+
+impl ServingCell {
+    fn new(mode: NetworkMode, signal_info: SignalInfo) -> Self {
+        Self {
+            mode,
+            rssi: 0,
+            cell_id: 0,
+            signal_info,
+            band: String::new(),
+            channel_info: None,
+            carriers: Vec::new(),
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_temp: None,
+        }
+    }
+}
+
+/// Convert access technology of `AT+COPS?` to `NetworkMode` enum
+fn get_mode_by_access_technology(act: i64) -> NetworkMode {
+    match act {
+        0 | 1 | 3 => NetworkMode::Gsm,
+        2 | 4..=6 => NetworkMode::Wcdma,
+        // E-UTRAN, including E-UTRA connected to 5G core (10)
+        7 | 9 | 10 => NetworkMode::Lte,
+        11 | 12 => NetworkMode::Nr,
+        13 => NetworkMode::NrNsa,
+        _ => NetworkMode::Unknown,
+    }
+}
+
+/// Parse operator in numeric format and access technology of `+COPS: 0,2,"25001",7`
+fn parse_cops(lines: &[String]) -> (String, NetworkMode) {
+    let Some(values) = get_response_values(lines, "+COPS:") else {
+        return (String::new(), NetworkMode::Unknown);
+    };
+    let plmn = values.get(2).cloned().unwrap_or_default();
+    let mode = get_i64(&values, 3).map_or(NetworkMode::Unknown, get_mode_by_access_technology);
+    (plmn, mode)
+}
+
+fn parse_cesq(lines: &[String], mode: NetworkMode) -> Result<ServingCell, ModemError> {
+    // +CESQ: <rxlev>,<ber>,<rscp>,<ecno>,<rsrq>,<rsrp>
+    let values = get_response_values(lines, "+CESQ:").unwrap_or_default();
+    let get_signal = |index: usize, offset: f64, step: f64, name: &str| {
+        decode_signal(&values, index, offset, step).ok_or_else(|| missing_value(CESQ_COMMAND, name))
+    };
+
+    let serving_cell = match mode {
+        NetworkMode::Lte | NetworkMode::NrNsa => ServingCell::new(
+            NetworkMode::Lte,
+            SignalInfo::Lte(LteSignalInfo {
+                rsrq: get_signal(4, -20.0, 0.5, "rsrq")?,
+                rsrp: get_signal(5, -140.0, 1.0, "rsrp")?,
+                // Not reported by the standard command
+                sinr: 0,
+                ca_count: 0,
+                enb: 0,
+                id: 0,
+                pci: -1,
+            }),
+        ),
+        NetworkMode::Wcdma => ServingCell::new(
+            mode,
+            SignalInfo::Wcdma(WcdmaSignalInfo {
+                rscp: get_signal(2, -120.0, 1.0, "rscp")?,
+                ecio: get_signal(3, -24.0, 0.5, "ecno")?,
+                nb: 0,
+                cc: 0,
+                rnc: 0,
+                psc: 0,
+            }),
+        ),
+        NetworkMode::Gsm => {
+            let mut serving_cell = ServingCell::new(mode, SignalInfo::None);
+            serving_cell.rssi = get_signal(0, -110.0, 1.0, "rxlev")?;
+            serving_cell
+        }
+        // Not registered
+        _ => ServingCell::new(mode, SignalInfo::None),
+    };
+    Ok(serving_cell)
+}
+
+/// Get required value of `AT+QENG` response
+fn get_qeng_i64(values: &[String], index: usize, name: &str) -> Result<i64, ModemError> {
+    get_i64(values, index).ok_or_else(|| missing_value(QENG_COMMAND, name))
+}
+
+fn get_qeng_cell_id(values: &[String], index: usize) -> Result<i64, ModemError> {
+    get_hex_i64(values, index).ok_or_else(|| missing_value(QENG_COMMAND, "cellID"))
+}
+
+fn parse_qeng_lte(values: &[String]) -> Result<ServingCell, ModemError> {
+    // "LTE",<is_tdd>,<MCC>,<MNC>,<cellID>,<PCID>,<earfcn>,<freq_band_ind>,
+    // <UL_bandwidth>,<DL_bandwidth>,<TAC>,<RSRP>,<RSRQ>,<RSSI>,<SINR>,...
+    let cell_id = get_qeng_cell_id(values, 4)?;
+    let band_number = get_i64(values, 7);
+    let bandwidth = get_i64(values, 9)
+        .and_then(|val| usize::try_from(val).ok())
+        .and_then(|val| QUECTEL_LTE_BANDWIDTHS.get(val).copied());
+
+    let mut serving_cell = ServingCell::new(
+        NetworkMode::Lte,
+        SignalInfo::Lte(LteSignalInfo {
+            rsrq: get_qeng_i64(values, 12, "RSRQ")?,
+            rsrp: get_qeng_i64(values, 11, "RSRP")?,
+            // Reported in 1/5th of dB starting at -20 dB
+            sinr: decode_signal(values, 14, -20.0, 0.2)
+                .ok_or_else(|| missing_value(QENG_COMMAND, "SINR"))?,
+            ca_count: 0,
+            enb: cell_id >> 8,
+            id: cell_id & 0xFF,
+            pci: get_i64(values, 5).unwrap_or(-1),
+        }),
+    );
+    serving_cell.rssi = get_i64(values, 13).unwrap_or(0);
+    serving_cell.cell_id = cell_id;
+    if let Some(band_number) = band_number {
+        serving_cell.band = format!("LTE B{band_number}");
+    }
+    serving_cell.channel_info =
+        get_i64(values, 6).map(|earfcn| get_lte_channel_info(earfcn, bandwidth));
+    Ok(serving_cell)
+}
+
+fn parse_qeng_wcdma(values: &[String]) -> Result<ServingCell, ModemError> {
+    // "WCDMA",<MCC>,<MNC>,<LAC>,<cellID>,<uarfcn>,<PSC>,<RAC>,<RSCP>,<ecio>,...
+    let cell_id = get_qeng_cell_id(values, 4)?;
+    let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
+    let (nb, cc) = (id / 10, id % 10);
+
+    let mut serving_cell = ServingCell::new(
+        NetworkMode::Wcdma,
+        SignalInfo::Wcdma(WcdmaSignalInfo {
+            rscp: get_qeng_i64(values, 8, "RSCP")?,
+            ecio: get_qeng_i64(values, 9, "ecio")?,
+            nb,
+            cc,
+            rnc,
+            psc: get_i64(values, 6).unwrap_or(0),
+        }),
+    );
+    serving_cell.cell_id = cell_id;
+    Ok(serving_cell)
+}
+
+fn parse_qeng_nr_nsa(values: &[String]) -> Result<NrSignalInfo, ModemError> {
+    // "NR5G-NSA",<MCC>,<MNC>,<PCID>,<RSRP>,<SINR>,<RSRQ>,<ARFCN>,<band>,...
+    Ok(NrSignalInfo {
+        rsrq: get_qeng_i64(values, 6, "NR RSRQ")?,
+        rsrp: get_qeng_i64(values, 4, "NR RSRP")?,
+        sinr: get_qeng_i64(values, 5, "NR SINR")?,
+        band: get_i64(values, 8).unwrap_or(0),
+        arfcn: get_i64(values, 7).unwrap_or(0),
+        gnb: 0,
+        id: 0,
+        pci: get_i64(values, 3).unwrap_or(-1),
+    })
+}
+
+fn parse_qeng_nr_sa(values: &[String]) -> Result<ServingCell, ModemError> {
+    // "NR5G-SA",<duplex_mode>,<MCC>,<MNC>,<cellID>,<PCID>,<TAC>,<ARFCN>,<band>,
+    // <NR_DL_bandwidth>,<RSRP>,<RSRQ>,<SINR>,...
+    let cell_id = get_qeng_cell_id(values, 4)?;
+    let (gnb, id) = NrSignalInfo::split_nci(cell_id);
+    let band = get_i64(values, 8).unwrap_or(0);
+
+    let mut serving_cell = ServingCell::new(
+        NetworkMode::Nr,
+        SignalInfo::Nr(NrSignalInfo {
+            rsrq: get_qeng_i64(values, 11, "RSRQ")?,
+            rsrp: get_qeng_i64(values, 10, "RSRP")?,
+            sinr: get_qeng_i64(values, 12, "SINR")?,
+            band,
+            arfcn: get_i64(values, 7).unwrap_or(0),
+            gnb,
+            id,
+            pci: get_i64(values, 5).unwrap_or(-1),
+        }),
+    );
+    serving_cell.cell_id = cell_id;
+    serving_cell.band = format!("NR n{band}");
+    Ok(serving_cell)
+}
+
+/// Parse response of `AT+QENG="servingcell"`. In EN-DC mode LTE anchor and NR leg are
+/// reported in separate lines. Returns `None` if there is no serving cell (e.g. 'SEARCH' state)
+fn parse_qeng_servingcell(lines: &[String]) -> Result<Option<ServingCell>, ModemError> {
+    let mut serving_cell = None;
+    let mut nr_nsa = None;
+
+    for line in lines {
+        let Some(values) = line.strip_prefix("+QENG:").map(split_response_values) else {
+            continue;
+        };
+        let values = if values.first().is_some_and(|val| val == "servingcell") {
+            values.get(2..).unwrap_or_default()
+        } else {
+            &values[..]
+        };
+
+        match values.first().map(String::as_str) {
+            Some("LTE") => serving_cell = Some(parse_qeng_lte(values)?),
+            Some("WCDMA") => serving_cell = Some(parse_qeng_wcdma(values)?),
+            Some("NR5G-SA") => serving_cell = Some(parse_qeng_nr_sa(values)?),
+            Some("NR5G-NSA") => nr_nsa = Some(parse_qeng_nr_nsa(values)?),
+            _ => {}
+        }
+    }
+
+    let Some(mut serving_cell) = serving_cell else {
+        return Ok(None);
+    };
+    if let SignalInfo::Lte(lte_signal_info) = serving_cell.signal_info
+        && let Some(nr_signal_info) = nr_nsa
+    {
+        serving_cell.mode = NetworkMode::NrNsa;
+        serving_cell.signal_info = SignalInfo::NrNsa(lte_signal_info, nr_signal_info);
+    }
+    Ok(Some(serving_cell))
+}
+
+/// Values of `AT!GSTATUS?` are reported with decimals, e.g. '-10.9'
+fn round_gstatus_value(value: Option<f64>) -> Option<i64> {
+    value.map(|value| value.round() as i64)
+}
+
+/// Channel of the primary carrier reported by `AT!GSTATUS?`
+pub fn get_gstatus_channel_info(gstatus_info: &GstatusInfo) -> Option<ChannelInfo> {
+    let bandwidth = parse_bandwidth_mhz(&gstatus_info.lte_bandwidth);
+    gstatus_info
+        .lte_rx_channel
+        .map(|earfcn| get_lte_channel_info(earfcn, bandwidth))
+}
+
+/// RSRP of the primary carrier by antenna ('PCC RxM', 'PCC RxD')
+pub fn get_gstatus_antenna_rsrp(gstatus_info: &GstatusInfo) -> Vec<i64> {
+    gstatus_info
+        .rx_chains
+        .iter()
+        .filter(|rx_chain| rx_chain.name.starts_with("PCC Rx"))
+        .filter_map(|rx_chain| round_gstatus_value(rx_chain.rsrp))
+        .collect()
+}
+
+/// Primary and active secondary carriers. Empty without carrier aggregation
+fn get_gstatus_carriers(gstatus_info: &GstatusInfo) -> Vec<ComponentCarrier> {
+    let secondary_carriers = gstatus_info
+        .secondary_cells
+        .iter()
+        .filter(|cell| cell.state == "ACTIVE")
+        .filter_map(|cell| {
+            Some(ComponentCarrier {
+                role: CarrierRole::Secondary,
+                band: parse_band_number(&cell.band)?,
+                earfcn: cell.rx_channel,
+                bandwidth: parse_bandwidth_mhz(&cell.bandwidth),
+                pci: None,
+                rsrp: None,
+                rsrq: None,
+                sinr: None,
+            })
+        })
+        .collect::<Vec<ComponentCarrier>>();
+
+    let Some(band) = parse_band_number(&gstatus_info.lte_band) else {
+        return Vec::new();
+    };
+    if secondary_carriers.is_empty() {
+        return Vec::new();
+    }
+
+    let mut carriers = vec![ComponentCarrier {
+        role: CarrierRole::Primary,
+        band,
+        earfcn: gstatus_info.lte_rx_channel,
+        bandwidth: parse_bandwidth_mhz(&gstatus_info.lte_bandwidth),
+        pci: None,
+        rsrp: None,
+        rsrq: None,
+        sinr: None,
+    }];
+    carriers.extend(secondary_carriers);
+    carriers
+}
+
+/// Parse response of `AT!GSTATUS?` of Sierra Wireless modems in LTE mode
+/// Returns `None` in other modes
+fn parse_gstatus(lines: &[String]) -> Result<Option<ServingCell>, ModemError> {
+    let gstatus_info = GstatusInfo::parse(lines);
+
+    if gstatus_info.system_mode != "LTE" {
+        return Ok(None);
+    }
+    let required_value = |value: Option<f64>, name: &str| {
+        round_gstatus_value(value).ok_or_else(|| missing_value(GSTATUS_COMMAND, name))
+    };
+
+    let main_rx_chain = gstatus_info
+        .rx_chains
+        .iter()
+        .find(|rx_chain| rx_chain.name == "PCC RxM");
+    let cell_id = gstatus_info.cell_id.unwrap_or(0);
+    let carriers = get_gstatus_carriers(&gstatus_info);
+    let ca_count = i64::try_from(carriers.len().saturating_sub(1)).unwrap_or(0);
+
+    let mut serving_cell = ServingCell::new(
+        NetworkMode::Lte,
+        SignalInfo::Lte(LteSignalInfo {
+            rsrq: required_value(gstatus_info.rsrq, "RSRQ (dB)")?,
+            rsrp: required_value(main_rx_chain.and_then(|c| c.rsrp), "PCC RxM RSRP")?,
+            sinr: required_value(gstatus_info.sinr, "SINR (dB)")?,
+            ca_count,
+            enb: cell_id >> 8,
+            id: cell_id & 0xFF,
+            pci: -1,
+        }),
+    );
+    serving_cell.rssi = required_value(main_rx_chain.and_then(|c| c.rssi), "PCC RxM RSSI")?;
+    serving_cell.cell_id = cell_id;
+    if let Some(band_number) = parse_band_number(&gstatus_info.lte_band) {
+        serving_cell.band = format!("LTE B{band_number}");
+    }
+    serving_cell.channel_info = get_gstatus_channel_info(&gstatus_info);
+    serving_cell.carriers = carriers;
+    serving_cell.tac = gstatus_info.tac;
+    serving_cell.antenna_rsrp = get_gstatus_antenna_rsrp(&gstatus_info);
+    serving_cell.device_temp = gstatus_info
+        .temperature
+        .map(|device_temp| DeviceTemperature {
+            device_temp,
+            battery_temp: None,
+        });
+    Ok(Some(serving_cell))
+}
+
+/// Parse response of `AT^HCSQ?` of Huawei modems, e.g. `^HCSQ: "LTE",52,47,161,25`
+/// Returns `None` without service
+fn parse_hcsq(lines: &[String]) -> Result<Option<ServingCell>, ModemError> {
+    let Some(values) = get_response_values(lines, "^HCSQ:") else {
+        return Ok(None);
+    };
+    let get_signal = |index: usize, offset: f64, step: f64, name: &str| {
+        decode_signal(&values, index, offset, step).ok_or_else(|| missing_value(HCSQ_COMMAND, name))
+    };
+
+    let serving_cell = match values.first().map(String::as_str) {
+        Some("LTE") => {
+            let mut serving_cell = ServingCell::new(
+                NetworkMode::Lte,
+                SignalInfo::Lte(LteSignalInfo {
+                    rsrq: get_signal(4, -19.5, 0.5, "rsrq")?,
+                    rsrp: get_signal(2, -140.0, 1.0, "rsrp")?,
+                    sinr: get_signal(3, -20.0, 0.2, "sinr")?,
+                    ca_count: 0,
+                    enb: 0,
+                    id: 0,
+                    pci: -1,
+                }),
+            );
+            serving_cell.rssi = get_signal(1, -120.0, 1.0, "rssi")?;
+            serving_cell
+        }
+        Some("WCDMA") => {
+            let mut serving_cell = ServingCell::new(
+                NetworkMode::Wcdma,
+                SignalInfo::Wcdma(WcdmaSignalInfo {
+                    rscp: get_signal(2, -120.0, 1.0, "rscp")?,
+                    ecio: get_signal(3, -32.0, 0.5, "ecio")?,
+                    nb: 0,
+                    cc: 0,
+                    rnc: 0,
+                    psc: 0,
+                }),
+            );
+            serving_cell.rssi = get_signal(1, -120.0, 1.0, "rssi")?;
+            serving_cell
+        }
+        Some("GSM") => {
+            let mut serving_cell = ServingCell::new(NetworkMode::Gsm, SignalInfo::None);
+            serving_cell.rssi = get_signal(1, -120.0, 1.0, "rssi")?;
+            serving_cell
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(serving_cell))
+}
+
+/*
+ * Utils for modems with AT port
+ */
+
+pub struct AtParser {
+    dialect: AtDialect,
+    /// Opened port and its address
+    connection: Option<(String, AtConnection)>,
+    device_info: DeviceInformation,
+}
+
+impl AtParser {
+    pub fn new(dialect: AtDialect) -> Self {
+        Self {
+            dialect,
+            connection: None,
+            device_info: DeviceInformation::from("", ""),
+        }
+    }
+
+    fn get_device_info(connection: &mut AtConnection) -> Result<DeviceInformation, ModemError> {
+        let manufacturer = connection.command("AT+CGMI")?;
+        let model = connection.command("AT+CGMM").unwrap_or_default();
+
+        let manufacturer_str = manufacturer.first().map_or("", String::as_str);
+        let manufacturer_str = manufacturer_str
+            .strip_prefix("Manufacturer:")
+            .unwrap_or(manufacturer_str)
+            .trim();
+        let model_str = model.first().map_or("", String::as_str);
+        let model_str = model_str.strip_prefix("Model:").unwrap_or(model_str).trim();
+        Ok(DeviceInformation::from(manufacturer_str, model_str))
+    }
+
+    /// Probe AT port and select dialect by manufacturer of the modem
+    pub fn detect_dialect(address: &str) -> Option<(AtDialect, DeviceInformation)> {
+        let mut connection = AtConnection::open(address).ok()?;
+        let device_info = AtParser::get_device_info(&mut connection).ok()?;
+
+        let (manufacturer, _) = device_info.get_manufacturer_and_model();
+        let manufacturer = manufacturer.to_lowercase();
+        let dialect = [AtDialect::Quectel, AtDialect::Sierra, AtDialect::Huawei]
+            .into_iter()
+            .find(|dialect| {
+                dialect
+                    .get_manufacturer_name()
+                    .is_some_and(|name| manufacturer.contains(name))
+            })
+            .unwrap_or(AtDialect::Generic);
+        Some((dialect, device_info))
+    }
+
+    fn connect(&mut self, address: &str) -> Result<&mut AtConnection, ModemError> {
+        if self
+            .connection
+            .as_ref()
+            .is_none_or(|(connection_address, _)| connection_address != address)
+        {
+            let mut connection = AtConnection::open(address)?;
+            self.device_info = AtParser::get_device_info(&mut connection)?;
+            // Report operator in numeric format
+            connection.command("AT+COPS=3,2")?;
+            self.connection = Some((address.to_string(), connection));
+        }
+
+        let (_, connection) = self.connection.as_mut().expect("Connection is opened");
+        Ok(connection)
+    }
+
+    fn query_serving_cell(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        let dialect = self.dialect;
+        let connection = self.connect(host)?;
+
+        let (plmn_str, mode) = parse_cops(&connection.command("AT+COPS?")?);
+
+        let serving_cell = match dialect {
+            AtDialect::Generic => None,
+            AtDialect::Quectel => parse_qeng_servingcell(&connection.command(QENG_COMMAND)?)?,
+            AtDialect::Sierra => parse_gstatus(&connection.command(GSTATUS_COMMAND)?)?,
+            AtDialect::Huawei => parse_hcsq(&connection.command(HCSQ_COMMAND)?)?,
+        };
+        // Standard command is used when vendor command doesn't report current mode
+        let serving_cell = match serving_cell {
+            Some(serving_cell) => serving_cell,
+            None => parse_cesq(&connection.command(CESQ_COMMAND)?, mode)?,
+        };
+
+        let plmn = PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string");
+
+        let mut band = ['\0'; 20];
+        copy_string_to_array!(band, serving_cell.band);
+
+        Ok(ModemStatus {
+            mode: serving_cell.mode,
+            plmn,
+            rssi: serving_cell.rssi,
+            cell_id: serving_cell.cell_id,
+            signal_info: serving_cell.signal_info,
+            band,
+            carriers: serving_cell.carriers,
+            channel_info: serving_cell.channel_info,
+            tac: serving_cell.tac,
+            antenna_rsrp: serving_cell.antenna_rsrp,
+            device_info: self.device_info,
+            battery_status: None,
+            device_temp: serving_cell.device_temp,
+            traffic_statistics: None,
+            traffic_mode: TrafficMode::Absolute,
+        })
+    }
+}
+
+impl ModemInfoParser for AtParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        let modem_status = self.query_serving_cell(host);
+        if let Err(e) = &modem_status
+            && e.kind == ModemErrorKind::PortConnection
+        {
+            // Reopen port on next poll
+            self.connection = None;
+        }
+        modem_status.map_err(|e| e.context(&format!("Cannot get modem status from {host}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(response: &str) -> Vec<String> {
+        response.lines().map(ToString::to_string).collect()
+    }
+
+    fn get_lte_signal_info(serving_cell: &ServingCell) -> LteSignalInfo {
+        match serving_cell.signal_info {
+            SignalInfo::Lte(lte_signal_info) | SignalInfo::NrNsa(lte_signal_info, _) => {
+                lte_signal_info
+            }
+            _ => panic!("Serving cell is not LTE"),
+        }
+    }
+
+    #[test]
+    fn serial_device_addresses() {
+        assert!(is_serial_device("/dev/ttyUSB2"));
+        assert!(is_serial_device("COM3"));
+        assert!(is_serial_device("com12"));
+        assert!(!is_serial_device("computer:5510"));
+        assert!(!is_serial_device("comms.lan:23"));
+        assert!(!is_serial_device("COM"));
+        assert!(!is_serial_device("192.168.1.1:5510"));
+    }
+
+    #[test]
+    fn cops_access_technology() {
+        let (plmn, mode) = parse_cops(&lines("+COPS: 0,2,\"26203\",7"));
+        assert_eq!(plmn, "26203");
+        assert!(mode == NetworkMode::Lte);
+
+        // E-UTRA connected to 5G core is LTE
+        let (_, mode) = parse_cops(&lines("+COPS: 0,2,\"26203\",10"));
+        assert!(mode == NetworkMode::Lte);
+
+        let (_, mode) = parse_cops(&lines("+COPS: 0,2,\"26203\",13"));
+        assert!(mode == NetworkMode::NrNsa);
+    }
+
+    #[test]
+    fn cesq_lte() {
+        let serving_cell =
+            parse_cesq(&lines("+CESQ: 99,99,255,255,20,45"), NetworkMode::Lte).unwrap();
+        let lte_signal_info = get_lte_signal_info(&serving_cell);
+        assert_eq!(lte_signal_info.rsrq, -10);
+        assert_eq!(lte_signal_info.rsrp, -95);
+    }
+
+    #[test]
+    fn cesq_not_reported() {
+        let result = parse_cesq(&lines("+CESQ: 99,99,255,255,255,255"), NetworkMode::Lte);
+        let Err(e) = result else {
+            panic!("Unknown RSRP is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+
+    #[test]
+    fn qeng_lte() {
+        let response = "+QENG: \"servingcell\",\"NOCONN\",\"LTE\",\"FDD\",262,03,2FAA03,187,1300,3,5,5,E9E5,-95,-11,-66,130,9,-,38";
+        let serving_cell = parse_qeng_servingcell(&lines(response)).unwrap().unwrap();
+
+        assert!(serving_cell.mode == NetworkMode::Lte);
+        assert_eq!(serving_cell.cell_id, 0x002F_AA03);
+        assert_eq!(serving_cell.rssi, -66);
+        assert_eq!(serving_cell.band, "LTE B3");
+
+        let lte_signal_info = get_lte_signal_info(&serving_cell);
+        assert_eq!(lte_signal_info.rsrp, -95);
+        assert_eq!(lte_signal_info.rsrq, -11);
+        assert_eq!(lte_signal_info.sinr, 6);
+        assert_eq!(lte_signal_info.pci, 187);
+        assert_eq!(lte_signal_info.enb, 0x2FAA);
+        assert_eq!(lte_signal_info.id, 3);
+
+        let channel_info = serving_cell.channel_info.unwrap();
+        assert_eq!(channel_info.earfcn, Some(1300));
+        assert_eq!(channel_info.dl_bandwidth, Some(20.0));
+    }
+
+    #[test]
+    fn qeng_en_dc() {
+        let response = "+QENG: \"servingcell\",\"NOCONN\"
++QENG: \"LTE\",\"FDD\",262,03,2FAA03,187,1300,3,5,5,E9E5,-95,-11,-66,130,9,-,38
++QENG: \"NR5G-NSA\",262,03,500,-90,20,-11,627264,78,100,1";
+        let serving_cell = parse_qeng_servingcell(&lines(response)).unwrap().unwrap();
+
+        assert!(serving_cell.mode == NetworkMode::NrNsa);
+        let SignalInfo::NrNsa(_, nr_signal_info) = serving_cell.signal_info else {
+            panic!("NR leg is not parsed");
+        };
+        assert_eq!(nr_signal_info.rsrp, -90);
+        assert_eq!(nr_signal_info.sinr, 20);
+        assert_eq!(nr_signal_info.rsrq, -11);
+        assert_eq!(nr_signal_info.arfcn, 627_264);
+        assert_eq!(nr_signal_info.band, 78);
+        assert_eq!(nr_signal_info.pci, 500);
+    }
+
+    #[test]
+    fn qeng_missing_rsrp() {
+        let response = "+QENG: \"servingcell\",\"NOCONN\",\"LTE\",\"FDD\",262,03,2FAA03,187,1300,3,5,5,E9E5,-,-,-,-,-,-,-";
+        assert!(parse_qeng_servingcell(&lines(response)).is_err());
+    }
+
+    #[test]
+    fn qeng_search() {
+        let response = "+QENG: \"servingcell\",\"SEARCH\"";
+        assert!(parse_qeng_servingcell(&lines(response)).unwrap().is_none());
+    }
+
+    #[test]
+    fn hcsq_lte() {
+        let serving_cell = parse_hcsq(&lines("^HCSQ: \"LTE\",52,47,161,25"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(serving_cell.rssi, -68);
+
+        let lte_signal_info = get_lte_signal_info(&serving_cell);
+        assert_eq!(lte_signal_info.rsrp, -93);
+        assert_eq!(lte_signal_info.sinr, 12);
+        assert_eq!(lte_signal_info.rsrq, -7);
+    }
+
+    #[test]
+    fn hcsq_not_reported() {
+        assert!(parse_hcsq(&lines("^HCSQ: \"LTE\",52,255,255,255")).is_err());
+        assert!(
+            parse_hcsq(&lines("^HCSQ: \"NOSERVICE\""))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    const GSTATUS_RESPONSE: &str = "!GSTATUS: 
+Current Time:  4402\t\tTemperature: 38
+Bootup Time:   0\t\tMode:        ONLINE         
+System mode:   LTE        \tPS state:    Attached     
+LTE band:      B3     \t\tLTE bw:      20 MHz  
+LTE Rx chan:   1300\t\tLTE Tx chan:   19300
+LTE CA state:  ACTIVE \tLTE Scell band:B7    
+LTE Scell bw:  20 MHz  \t\tLTE Scell chan:3100
+EMM state:     Registered     \tNormal Service 
+RRC state:     RRC Connected  
+IMS reg state: No Srv  \t\t
+
+PCC RxM RSSI:  -69\t\tRSRP (dBm):  -98
+PCC RxD RSSI:  -72\t\tRSRP (dBm):  -101
+SCC RxM RSSI:  -73\t\tRSRP (dBm):  -103
+SCC RxD RSSI:  -77\t\tRSRP (dBm):  -107
+Tx Power:      --\t\tTAC:         01F2 (498)
+RSRQ (dB):     -10.9\t\tCell ID:     0012AB34 (1223476)
+SINR (dB):     10.2";
+
+    #[test]
+    fn gstatus_lte() {
+        let serving_cell = parse_gstatus(&lines(GSTATUS_RESPONSE)).unwrap().unwrap();
+
+        assert_eq!(serving_cell.cell_id, 0x0012_AB34);
+        assert_eq!(serving_cell.rssi, -69);
+        assert_eq!(serving_cell.band, "LTE B3");
+        assert_eq!(serving_cell.tac, Some(0x1F2));
+        assert_eq!(serving_cell.antenna_rsrp, vec![-98, -101]);
+        assert_eq!(serving_cell.carriers.len(), 2);
+        assert_eq!(serving_cell.carriers[1].band, 7);
+        assert_eq!(serving_cell.carriers[1].earfcn, Some(3100));
+
+        let lte_signal_info = get_lte_signal_info(&serving_cell);
+        assert_eq!(lte_signal_info.rsrp, -98);
+        assert_eq!(lte_signal_info.rsrq, -11);
+        assert_eq!(lte_signal_info.sinr, 10);
+        assert_eq!(lte_signal_info.ca_count, 1);
+    }
+
+    #[test]
+    fn gstatus_missing_sinr() {
+        let response = GSTATUS_RESPONSE.replace("SINR (dB):     10.2", "");
+        assert!(parse_gstatus(&lines(&response)).is_err());
+    }
+
+    /// Stand-in of modem port that answers commands with captured responses
+    struct ScriptedPort {
+        responses: Vec<(&'static str, &'static str)>,
+        input: String,
+        output: Vec<u8>,
+    }
+
+    impl Read for ScriptedPort {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.output.is_empty() {
+                return Err(ErrorKind::TimedOut.into());
+            }
+            let n = buf.len().min(self.output.len());
+            buf[..n].copy_from_slice(&self.output[..n]);
+            self.output.drain(..n);
+            Ok(n)
+        }
+    }
+
+    impl Write for ScriptedPort {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.input.push_str(&String::from_utf8_lossy(buf));
+            while let Some(pos) = self.input.find('\r') {
+                let command: String = self.input.drain(..=pos).collect();
+                let response = self
+                    .responses
+                    .iter()
+                    .find(|(c, _)| *c == command.trim())
+                    .map_or("\r\nERROR\r\n".to_string(), |(_, r)| {
+                        format!("\r\n{}\r\n\r\nOK\r\n", r.replace('\n', "\r\n"))
+                    });
+                self.output.extend_from_slice(response.as_bytes());
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn get_scripted_parser(
+        dialect: AtDialect,
+        responses: Vec<(&'static str, &'static str)>,
+    ) -> AtParser {
+        let port = ScriptedPort {
+            responses,
+            input: String::new(),
+            output: Vec::new(),
+        };
+        let mut parser = AtParser::new(dialect);
+        parser.connection = Some((
+            "scripted".to_string(),
+            AtConnection {
+                stream: Box::new(port),
+                buffer: String::new(),
+            },
+        ));
+        parser
+    }
+
+    #[test]
+    fn quectel_dialect() {
+        let mut parser = get_scripted_parser(
+            AtDialect::Quectel,
+            vec![
+                ("AT+COPS?", "+COPS: 0,2,\"26203\",7"),
+                (
+                    QENG_COMMAND,
+                    "+QENG: \"servingcell\",\"NOCONN\",\"LTE\",\"FDD\",262,03,2FAA03,187,1300,3,5,5,E9E5,-95,-11,-66,130,9,-,38",
+                ),
+            ],
+        );
+        let modem_status = parser.get_info("scripted").unwrap();
+        assert!(modem_status.mode == NetworkMode::Lte);
+        assert_eq!(modem_status.get_plmn().trim_end_matches('\0'), "26203");
+        assert_eq!(modem_status.rssi, -66);
+    }
+
+    #[test]
+    fn vendor_command_fallback() {
+        // Vendor command reports no service, standard command is used instead
+        let mut parser = get_scripted_parser(
+            AtDialect::Huawei,
+            vec![
+                ("AT+COPS?", "+COPS: 0,2,\"26203\",7"),
+                (HCSQ_COMMAND, "^HCSQ: \"NOSERVICE\""),
+                (CESQ_COMMAND, "+CESQ: 99,99,255,255,20,45"),
+            ],
+        );
+        let modem_status = parser.get_info("scripted").unwrap();
+        let SignalInfo::Lte(lte_signal_info) = modem_status.signal_info else {
+            panic!("Serving cell is not LTE");
+        };
+        assert_eq!(lte_signal_info.rsrp, -95);
+    }
+
+    #[test]
+    fn command_error() {
+        let mut parser = get_scripted_parser(
+            AtDialect::Quectel,
+            vec![("AT+COPS?", "+COPS: 0,2,\"26203\",7")],
+        );
+        let Err(e) = parser.get_info("scripted") else {
+            panic!("Failed command is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::Access);
+        // Port is kept open after error of the command
+        assert!(parser.connection.is_some());
+    }
+}
@@ -0,0 +1,143 @@
+/*
+ * Utils for LTE channel numbers (3GPP TS 36.101, Table 5.7.3-1)
+ */
+
+/// Parameters of LTE band. Supplemental downlink bands have no uplink, TDD bands
+/// use the same frequencies for downlink and uplink
+struct LteBand {
+    band: i64,
+    /// Lowest downlink frequency in MHz
+    f_dl_low: f64,
+    /// Range of downlink EARFCN
+    n_dl: (i64, i64),
+    /// Lowest uplink frequency in MHz
+    f_ul_low: Option<f64>,
+}
+
+const fn fdd(band: i64, f_dl_low: f64, n_dl: (i64, i64), f_ul_low: f64) -> LteBand {
+    LteBand {
+        band,
+        f_dl_low,
+        n_dl,
+        f_ul_low: Some(f_ul_low),
+    }
+}
+
+const fn sdl(band: i64, f_dl_low: f64, n_dl: (i64, i64)) -> LteBand {
+    LteBand {
+        band,
+        f_dl_low,
+        n_dl,
+        f_ul_low: None,
+    }
+}
+
+const fn tdd(band: i64, f_low: f64, n: (i64, i64)) -> LteBand {
+    fdd(band, f_low, n, f_low)
+}
+
+const LTE_BANDS: [LteBand; 29] = [
+    fdd(1, 2110.0, (0, 599), 1920.0),
+    fdd(2, 1930.0, (600, 1199), 1850.0),
+    fdd(3, 1805.0, (1200, 1949), 1710.0),
+    fdd(4, 2110.0, (1950, 2399), 1710.0),
+    fdd(5, 869.0, (2400, 2649), 824.0),
+    fdd(7, 2620.0, (2750, 3449), 2500.0),
+    fdd(8, 925.0, (3450, 3799), 880.0),
+    fdd(12, 729.0, (5010, 5179), 699.0),
+    fdd(13, 746.0, (5180, 5279), 777.0),
+    fdd(14, 758.0, (5280, 5379), 788.0),
+    fdd(17, 734.0, (5730, 5849), 704.0),
+    fdd(18, 860.0, (5850, 5999), 815.0),
+    fdd(19, 875.0, (6000, 6149), 830.0),
+    fdd(20, 791.0, (6150, 6449), 832.0),
+    fdd(25, 1930.0, (8040, 8689), 1850.0),
+    fdd(26, 859.0, (8690, 9039), 814.0),
+    fdd(28, 758.0, (9210, 9659), 703.0),
+    sdl(29, 717.0, (9660, 9769)),
+    fdd(30, 2350.0, (9770, 9869), 2305.0),
+    sdl(32, 1452.0, (9920, 10_359)),
+    tdd(38, 2570.0, (37_750, 38_249)),
+    tdd(39, 1880.0, (38_250, 38_649)),
+    tdd(40, 2300.0, (38_650, 39_649)),
+    tdd(41, 2496.0, (39_650, 41_589)),
+    tdd(42, 3400.0, (41_590, 43_589)),
+    tdd(43, 3600.0, (43_590, 45_589)),
+    tdd(48, 3550.0, (55_240, 56_739)),
+    fdd(66, 2110.0, (66_436, 67_335), 1710.0),
+    fdd(71, 617.0, (68_586, 68_935), 663.0),
+];
+
+fn find_band_by_earfcn(earfcn: i64) -> Option<&'static LteBand> {
+    LTE_BANDS
+        .iter()
+        .find(|band| earfcn >= band.n_dl.0 && earfcn <= band.n_dl.1)
+}
+
+/// Get number of LTE band by downlink EARFCN
+pub fn get_lte_band(earfcn: i64) -> Option<i64> {
+    find_band_by_earfcn(earfcn).map(|band| band.band)
+}
+
+/// Get downlink and uplink frequencies in MHz by downlink EARFCN.
+/// Uplink channel has the same offset from the start of the band as downlink channel
+#[allow(clippy::cast_precision_loss)]
+pub fn get_lte_frequencies(earfcn: i64) -> Option<(f64, Option<f64>)> {
+    let band = find_band_by_earfcn(earfcn)?;
+
+    let offset = 0.1 * (earfcn - band.n_dl.0) as f64;
+
+    Some((
+        band.f_dl_low + offset,
+        band.f_ul_low.map(|f_ul_low| f_ul_low + offset),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frequencies rounded to 100 kHz raster of EARFCN
+    fn get_rounded_frequencies(earfcn: i64) -> Option<(f64, Option<f64>)> {
+        let round = |f: f64| (f * 10.0).round() / 10.0;
+        get_lte_frequencies(earfcn).map(|(dl, ul)| (round(dl), ul.map(round)))
+    }
+
+    #[test]
+    fn band_boundaries() {
+        assert_eq!(get_lte_band(0), Some(1));
+        assert_eq!(get_lte_band(599), Some(1));
+        assert_eq!(get_lte_band(600), Some(2));
+        assert_eq!(get_lte_band(1300), Some(3));
+        assert_eq!(get_lte_band(6300), Some(20));
+        assert_eq!(get_lte_band(9820), Some(30));
+        assert_eq!(get_lte_band(39_649), Some(40));
+        assert_eq!(get_lte_band(39_650), Some(41));
+        assert_eq!(get_lte_band(68_935), Some(71));
+
+        // Gaps between bands of the table
+        assert_eq!(get_lte_band(-1), None);
+        assert_eq!(get_lte_band(2700), None);
+        assert_eq!(get_lte_band(68_936), None);
+    }
+
+    #[test]
+    fn frequencies() {
+        assert_eq!(get_rounded_frequencies(0), Some((2110.0, Some(1920.0))));
+        assert_eq!(get_rounded_frequencies(599), Some((2169.9, Some(1979.9))));
+        assert_eq!(get_rounded_frequencies(1300), Some((1815.0, Some(1720.0))));
+        assert_eq!(get_rounded_frequencies(6300), Some((806.0, Some(847.0))));
+        assert_eq!(get_rounded_frequencies(9820), Some((2355.0, Some(2310.0))));
+
+        // Supplemental downlink band
+        assert_eq!(get_rounded_frequencies(9700), Some((721.0, None)));
+
+        // TDD band uses the same frequency for both directions
+        assert_eq!(
+            get_rounded_frequencies(39_650),
+            Some((2496.0, Some(2496.0)))
+        );
+
+        assert_eq!(get_rounded_frequencies(2700), None);
+    }
+}
@@ -0,0 +1,402 @@
+#![allow(clippy::similar_names)]
+
+use std::str::FromStr;
+
+use crate::bandwidth_utils::{TrafficMode, TrafficStatistics};
+use crate::earfcn_utils::get_lte_frequencies;
+use crate::modem_utils::{
+    ChannelInfo, Credentials, DeviceInformation, LteSignalInfo, ModemError, ModemInfoParser,
+    ModemStatus, NetworkMode, PlmnStatus, SignalInfo,
+};
+use crate::network_utils::{
+    get_url_xml_with_agent, new_session_agent, post_url_xml_with_digest_auth,
+};
+use crate::utils::{copy_string_to_array, parse_band_number};
+
+/// Port of TR-064 interface if address doesn't have one
+const TR064_PORT: u16 = 49000;
+
+const DESCRIPTION_QUERY: &str = "/tr64desc.xml";
+
+/// Service with LTE info. Requires authentication
+const WAN_COMMON_CONTROL: &str = "/upnp/control/wancommonifconfig1";
+const WAN_COMMON_SERVICE: &str = "urn:dslforum-org:service:WANCommonInterfaceConfig:1";
+
+/// IGD service with current throughput. Available without authentication
+const IGD_WAN_COMMON_CONTROL: &str = "/igdupnp/control/WANCommonIFC1";
+const IGD_WAN_COMMON_SERVICE: &str = "urn:schemas-upnp-org:service:WANCommonInterfaceConfig:1";
+
+/// Normalize name of SOAP response argument, e.g. 'NewX_AVM-DE_LTE_RSRP' -> 'rsrp'
+fn normalize_argument_name(name: &str) -> String {
+    let name = name.to_lowercase();
+    let name = name.strip_prefix("new").unwrap_or(&name);
+    let name = name.strip_prefix("x_avm-de_").unwrap_or(name);
+    let name = name.strip_prefix("lte_").unwrap_or(name);
+    name.replace('_', "")
+}
+
+/// Arguments of SOAP response with normalized names
+struct SoapArguments(Vec<(String, String)>);
+
+impl SoapArguments {
+    fn get(&self, names: &[&str]) -> Option<&str> {
+        names.iter().find_map(|name| {
+            self.0
+                .iter()
+                .find(|(arg, _)| arg == name)
+                .map(|(_, value)| value.as_str())
+        })
+    }
+
+    /// Get numeric value that may have unit, e.g. '-95 dBm'
+    fn get_i64(&self, names: &[&str]) -> Option<i64> {
+        self.get(names)
+            .and_then(|val| val.split_whitespace().next())
+            .and_then(|val| val.parse::<f64>().ok())
+            .map(|val| val.round() as i64)
+    }
+
+    /// Get numeric value of LTE info that must be reported
+    fn required_i64(&self, names: &[&str]) -> Result<i64, ModemError> {
+        self.get_i64(names).ok_or_else(|| {
+            eprintln!("LTE info doesn't have required argument '{}'", names[0]);
+            ModemError::data_parsing(names[0]).with_endpoint(WAN_COMMON_CONTROL)
+        })
+    }
+}
+
+/*
+ * Utils for FRITZ!Box
+ */
+
+pub struct FritzBoxParser {
+    credentials: Credentials,
+    agent: ureq::Agent,
+}
+
+impl FritzBoxParser {
+    pub fn new(credentials: &Credentials) -> Self {
+        Self {
+            credentials: credentials.clone(),
+            agent: new_session_agent(),
+        }
+    }
+
+    /// Address of TR-064 interface
+    fn get_tr064_host(host: &str) -> String {
+        if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{host}:{TR064_PORT}")
+        }
+    }
+
+    /// Call SOAP action without arguments. Returns arguments of the response
+    fn call_action(
+        agent: &ureq::Agent,
+        host: &str,
+        control: &str,
+        service: &str,
+        action: &str,
+        credentials: &Credentials,
+    ) -> Result<SoapArguments, ModemError> {
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+            <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+            s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+            <s:Body><u:{action} xmlns:u=\"{service}\"></u:{action}></s:Body></s:Envelope>"
+        );
+        let soap_action = format!("\"{service}#{action}\"");
+
+        let xml = post_url_xml_with_digest_auth(
+            agent,
+            host,
+            control,
+            &[("SOAPAction", &soap_action)],
+            &body,
+            credentials,
+        )
+        .map_err(|e| e.context(&format!("Action '{action}' failed")))?;
+
+        FritzBoxParser::parse_soap_response(&xml, action).ok_or_else(|| {
+            eprintln!("SOAP response doesn't have '{action}Response'");
+            ModemError::data_parsing(&format!("{action}Response")).with_endpoint(control)
+        })
+    }
+
+    /// Get arguments of response: Envelope -> Body -> {action}Response -> arguments
+    fn parse_soap_response(xml: &xmltree::Element, action: &str) -> Option<SoapArguments> {
+        let response = xml
+            .get_child("Body")?
+            .get_child(format!("{action}Response").as_str())?;
+
+        let arguments = response
+            .children
+            .iter()
+            .filter_map(xmltree::XMLNode::as_element)
+            .map(|arg| {
+                let value = arg.get_text().unwrap_or_default().trim().to_string();
+                (normalize_argument_name(&arg.name), value)
+            })
+            .collect();
+        Some(SoapArguments(arguments))
+    }
+
+    fn parse_description_xml(xml: &xmltree::Element) -> Option<DeviceInformation> {
+        let device = xml.get_child("device")?;
+        let manufacturer = device.get_child("manufacturer")?.get_text()?;
+        let model = device
+            .get_child("modelName")
+            .and_then(xmltree::Element::get_text)
+            .unwrap_or_default();
+        Some(DeviceInformation::from(&manufacturer, &model))
+    }
+
+    /// Check if host has TR-064 description of AVM device
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let agent = new_session_agent();
+        let host = FritzBoxParser::get_tr064_host(host);
+        let xml = get_url_xml_with_agent(&agent, &host, DESCRIPTION_QUERY).ok()?;
+
+        FritzBoxParser::parse_description_xml(&xml).filter(|device_info| {
+            let (manufacturer, _) = device_info.get_manufacturer_and_model();
+            manufacturer.contains("AVM")
+        })
+    }
+
+    fn parse_lte_info(arguments: &SoapArguments) -> Result<ModemStatus, ModemError> {
+        let mode = match arguments.get(&["accesstechnology", "wanaccesstype"]) {
+            Some(val) if val.contains("5G") || val.contains("NR") => NetworkMode::Nr,
+            Some(val) if val.contains("UMTS") || val.contains("3G") => NetworkMode::Wcdma,
+            _ => NetworkMode::Lte,
+        };
+
+        let plmn_str = match arguments.get(&["plmn"]) {
+            Some(plmn) => plmn.to_string(),
+            None => match (arguments.get(&["mcc"]), arguments.get(&["mnc"])) {
+                (Some(mcc), Some(mnc)) => format!("{mcc}{mnc}"),
+                _ => String::new(),
+            },
+        };
+        let plmn = PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string");
+
+        let cell_id = arguments
+            .get(&["cellid"])
+            .and_then(|val| {
+                val.parse::<i64>()
+                    .ok()
+                    .or_else(|| i64::from_str_radix(val, 16).ok())
+            })
+            .unwrap_or(0);
+
+        let band_number = arguments.get(&["band"]).and_then(parse_band_number);
+        let mut band = ['\0'; 20];
+        if let Some(band_number) = band_number {
+            copy_string_to_array!(band, format!("LTE B{band_number}"));
+        }
+
+        let signal_info = if mode == NetworkMode::Lte {
+            SignalInfo::Lte(LteSignalInfo {
+                rsrq: arguments.required_i64(&["rsrq"])?,
+                rsrp: arguments.required_i64(&["rsrp"])?,
+                sinr: arguments.required_i64(&["sinr", "snr"])?,
+                ca_count: 0,
+                enb: cell_id >> 8,
+                id: cell_id & 0xFF,
+                pci: arguments.get_i64(&["pci", "physicalcellid"]).unwrap_or(-1),
+            })
+        } else {
+            SignalInfo::None
+        };
+
+        let channel_info = arguments
+            .get_i64(&["earfcn", "dlearfcn"])
+            .filter(|_| mode == NetworkMode::Lte)
+            .map(|earfcn| {
+                let (dl_frequency, ul_frequency) = match get_lte_frequencies(earfcn) {
+                    Some((dl, ul)) => (Some(dl), ul),
+                    None => (None, None),
+                };
+                ChannelInfo {
+                    earfcn: Some(earfcn),
+                    dl_frequency,
+                    ul_frequency,
+                    dl_bandwidth: None,
+                    ul_bandwidth: None,
+                }
+            });
+
+        Ok(ModemStatus {
+            mode,
+            plmn,
+            rssi: arguments.get_i64(&["rssi"]).unwrap_or(0),
+            cell_id,
+            signal_info,
+            band,
+            carriers: Vec::new(),
+            channel_info,
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_info: DeviceInformation::from("AVM", ""),
+            battery_status: None,
+            device_temp: None,
+            traffic_statistics: None,
+            traffic_mode: TrafficMode::Absolute,
+        })
+    }
+
+    fn parse_addon_infos(arguments: &SoapArguments) -> Option<TrafficStatistics> {
+        // Current rate in bytes per second
+        Some(TrafficStatistics {
+            dl: arguments.get_i64(&["bytereceiverate"])? * 8,
+            ul: arguments.get_i64(&["bytesendrate"])? * 8,
+        })
+    }
+}
+
+impl ModemInfoParser for FritzBoxParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        let agent = &self.agent;
+        let host = FritzBoxParser::get_tr064_host(host);
+
+        let lte_info = FritzBoxParser::call_action(
+            agent,
+            &host,
+            WAN_COMMON_CONTROL,
+            WAN_COMMON_SERVICE,
+            "X_AVM-DE_GetInfoEx",
+            &self.credentials,
+        )
+        .map_err(|e| e.context(&format!("Cannot get LTE info from host {host}")))?;
+        let mut modem_status = FritzBoxParser::parse_lte_info(&lte_info)?;
+
+        // Optional info
+        if let Ok(addon_infos) = FritzBoxParser::call_action(
+            agent,
+            &host,
+            IGD_WAN_COMMON_CONTROL,
+            IGD_WAN_COMMON_SERVICE,
+            "GetAddonInfos",
+            &self.credentials,
+        ) {
+            modem_status.traffic_statistics = FritzBoxParser::parse_addon_infos(&addon_infos);
+        }
+        if let Some(device_info) = get_url_xml_with_agent(agent, &host, DESCRIPTION_QUERY)
+            .ok()
+            .and_then(|xml| FritzBoxParser::parse_description_xml(&xml))
+        {
+            modem_status.device_info = device_info;
+        }
+
+        Ok(modem_status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_utils::ModemErrorKind;
+
+    /// Response of 'X_AVM-DE_GetInfoEx' of FRITZ!Box 6850 LTE
+    const GET_INFO_EX_XML: &str = r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body>
+<u:X_AVM-DE_GetInfoExResponse xmlns:u="urn:dslforum-org:service:WANCommonInterfaceConfig:1">
+<NewWANAccessType>X_AVM-DE_Mobile</NewWANAccessType>
+<NewPhysicalLinkStatus>Up</NewPhysicalLinkStatus>
+<NewX_AVM-DE_LTE_AccessTechnology>LTE</NewX_AVM-DE_LTE_AccessTechnology>
+<NewX_AVM-DE_LTE_PLMN>26203</NewX_AVM-DE_LTE_PLMN>
+<NewX_AVM-DE_LTE_Band>Band 20</NewX_AVM-DE_LTE_Band>
+<NewX_AVM-DE_LTE_EARFCN>6300</NewX_AVM-DE_LTE_EARFCN>
+<NewX_AVM-DE_LTE_Cell_ID>27436305</NewX_AVM-DE_LTE_Cell_ID>
+<NewX_AVM-DE_LTE_PCI>293</NewX_AVM-DE_LTE_PCI>
+<NewX_AVM-DE_LTE_RSRP>-98 dBm</NewX_AVM-DE_LTE_RSRP>
+<NewX_AVM-DE_LTE_RSRQ>-11.5 dB</NewX_AVM-DE_LTE_RSRQ>
+<NewX_AVM-DE_LTE_SINR>7</NewX_AVM-DE_LTE_SINR>
+<NewX_AVM-DE_LTE_RSSI>-67</NewX_AVM-DE_LTE_RSSI>
+</u:X_AVM-DE_GetInfoExResponse>
+</s:Body>
+</s:Envelope>"#;
+
+    /// Response of 'GetAddonInfos' of IGD service
+    const ADDON_INFOS_XML: &str = r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body>
+<u:GetAddonInfosResponse xmlns:u="urn:schemas-upnp-org:service:WANCommonInterfaceConfig:1">
+<NewByteSendRate>4096</NewByteSendRate>
+<NewByteReceiveRate>131072</NewByteReceiveRate>
+<NewPacketSendRate>0</NewPacketSendRate>
+<NewPacketReceiveRate>0</NewPacketReceiveRate>
+<NewTotalBytesSent>1863256</NewTotalBytesSent>
+<NewTotalBytesReceived>29817253</NewTotalBytesReceived>
+</u:GetAddonInfosResponse>
+</s:Body>
+</s:Envelope>"#;
+
+    fn parse_soap_xml(xml: &str, action: &str) -> SoapArguments {
+        let xml = xmltree::Element::parse(xml.as_bytes()).unwrap();
+        FritzBoxParser::parse_soap_response(&xml, action).unwrap()
+    }
+
+    #[test]
+    fn argument_names() {
+        assert_eq!(normalize_argument_name("NewX_AVM-DE_LTE_RSRP"), "rsrp");
+        assert_eq!(normalize_argument_name("NewX_AVM-DE_LTE_Cell_ID"), "cellid");
+        assert_eq!(normalize_argument_name("NewWANAccessType"), "wanaccesstype");
+        assert_eq!(
+            normalize_argument_name("NewByteReceiveRate"),
+            "bytereceiverate"
+        );
+    }
+
+    #[test]
+    fn lte_info() {
+        let arguments = parse_soap_xml(GET_INFO_EX_XML, "X_AVM-DE_GetInfoEx");
+        let modem_status = FritzBoxParser::parse_lte_info(&arguments).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE");
+        assert_eq!(modem_status.get_plmn().trim_end_matches('\0'), "26203");
+        assert_eq!(modem_status.get_band(), "LTE B20");
+        assert_eq!(modem_status.rssi, -67);
+        assert_eq!(modem_status.cell_id, 27_436_305);
+        assert_eq!(
+            modem_status.get_frequencies().as_deref(),
+            Some("806.0/847.0 MHz")
+        );
+
+        let SignalInfo::Lte(lte_info) = modem_status.signal_info else {
+            panic!("Signal info is not LTE");
+        };
+        assert_eq!(lte_info.rsrp, -98);
+        assert_eq!(lte_info.rsrq, -12);
+        assert_eq!(lte_info.sinr, 7);
+        assert_eq!(lte_info.pci, 293);
+    }
+
+    #[test]
+    fn missing_signal_value() {
+        let xml = GET_INFO_EX_XML.replace(
+            "<NewX_AVM-DE_LTE_SINR>7</NewX_AVM-DE_LTE_SINR>",
+            "<NewX_AVM-DE_LTE_SINR></NewX_AVM-DE_LTE_SINR>",
+        );
+        let arguments = parse_soap_xml(&xml, "X_AVM-DE_GetInfoEx");
+        let Err(e) = FritzBoxParser::parse_lte_info(&arguments) else {
+            panic!("Empty SINR is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+
+    #[test]
+    fn addon_infos() {
+        let arguments = parse_soap_xml(ADDON_INFOS_XML, "GetAddonInfos");
+        let traffic_statistics = FritzBoxParser::parse_addon_infos(&arguments).unwrap();
+        assert_eq!(traffic_statistics.dl, 1_048_576);
+        assert_eq!(traffic_statistics.ul, 32_768);
+    }
+
+    #[test]
+    fn other_action_response() {
+        let xml = xmltree::Element::parse(ADDON_INFOS_XML.as_bytes()).unwrap();
+        assert!(FritzBoxParser::parse_soap_response(&xml, "X_AVM-DE_GetInfoEx").is_none());
+    }
+}
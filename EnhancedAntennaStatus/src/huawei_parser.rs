@@ -3,14 +3,25 @@
 use std::fmt;
 use std::str::FromStr;
 
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
 use crate::bandwidth_utils::{TrafficMode, TrafficStatistics};
+use crate::earfcn_utils::{get_lte_band, get_lte_frequencies};
 use crate::modem_utils::{
-    BatteryStatus, DeviceInformation, LteSignalInfo, ModemError, ModemInfoParser, ModemStatus,
-    NetworkMode, PlmnStatus, SignalInfo, WcdmaSignalInfo,
+    BatteryStatus, CarrierRole, ChannelInfo, ComponentCarrier, Credentials, DeviceInformation,
+    LteSignalInfo, ModemError, ModemErrorKind, ModemInfoParser, ModemStatus, NetworkMode,
+    NrSignalInfo, PlmnStatus, SignalInfo, VendorError, WcdmaSignalInfo,
+};
+use crate::network_utils::{
+    get_url_xml_with_agent, get_url_xml_with_session_token, new_session_agent,
+    post_url_xml_with_session_token,
 };
-use crate::network_utils::{get_url_xml, get_url_xml_with_session_token};
 use crate::utils::{
-    copy_string_to_array, get_xml_element, get_xml_element_as, get_xml_element_as_unit,
+    copy_string_to_array, escape_xml, from_hex_string, get_xml_element, get_xml_element_as,
+    get_xml_element_as_unit, parse_band_number, parse_bandwidth_mhz, sha256_hex, to_hex_string,
     xml_contains_required_parameters,
 };
 
@@ -20,10 +31,22 @@ fn get_mode_by_id(s: &str) -> NetworkMode {
         "0" => NetworkMode::Gsm,
         "2" => NetworkMode::Wcdma,
         "7" => NetworkMode::Lte,
+        "11" => NetworkMode::Nr,
         _ => NetworkMode::Unknown,
     }
 }
 
+/// Parse frequency in MHz from value in units of 100 kHz
+fn parse_huawei_frequency(s: &str) -> Option<f64> {
+    s.trim().parse::<f64>().ok().map(|f| f / 10.0)
+}
+
+/// Parse channel number from format 'DL:634080 UL:634080' (or just '634080')
+fn parse_huawei_channel(s: &str) -> Option<i64> {
+    let dl = s.split_whitespace().next()?;
+    dl.trim_start_matches("DL:").parse::<i64>().ok()
+}
+
 /// REST API error in Huawei format
 struct HuaweiError {
     code: Option<String>,
@@ -47,6 +70,70 @@ impl fmt::Display for HuaweiError {
     }
 }
 
+impl HuaweiError {
+    /// Convert into modem error with explanation of the code
+    fn into_modem_error(self, kind: ModemErrorKind, query: &str) -> ModemError {
+        let explanation = self
+            .code
+            .as_deref()
+            .and_then(explain_huawei_error_code)
+            .map(std::string::ToString::to_string);
+        ModemError::new(kind, &self.to_string())
+            .with_endpoint(query)
+            .with_vendor_error(VendorError {
+                code: self.code,
+                message: self.message,
+                explanation,
+            })
+    }
+    /// Check if error means that login is required: no rights or invalid session token
+    fn is_login_required(&self) -> bool {
+        const LOGIN_REQUIRED_CODES: [&str; 3] = ["100003", "125002", "125003"];
+        self.code
+            .as_ref()
+            .is_some_and(|code| LOGIN_REQUIRED_CODES.contains(&code.as_str()))
+    }
+    /// Check if error means that session token is not valid anymore
+    fn is_token_error(&self) -> bool {
+        const TOKEN_ERROR_CODES: [&str; 3] = ["125001", "125002", "125003"];
+        self.code
+            .as_ref()
+            .is_some_and(|code| TOKEN_ERROR_CODES.contains(&code.as_str()))
+    }
+    /// Check if error means that user is already logged in
+    fn is_already_logged_in(&self) -> bool {
+        self.code.as_ref().is_some_and(|code| code.eq("108003"))
+    }
+    /// Check if login is rejected. Next attempts with the same credentials fail too
+    /// and lock the account (108007)
+    fn is_credentials_error(&self) -> bool {
+        const CREDENTIALS_ERROR_CODES: [&str; 4] = ["108001", "108002", "108006", "108007"];
+        self.code
+            .as_ref()
+            .is_some_and(|code| CREDENTIALS_ERROR_CODES.contains(&code.as_str()))
+    }
+}
+
+/// Human-readable meaning of common Huawei REST error codes
+fn explain_huawei_error_code(code: &str) -> Option<&'static str> {
+    match code {
+        "100002" => Some("Request is not supported by the firmware"),
+        "100003" => Some("No rights, login is required"),
+        "100004" => Some("System is busy"),
+        "100005" => Some("Format error in request"),
+        "100006" => Some("Parameter error in request"),
+        "108001" => Some("Wrong username"),
+        "108002" => Some("Wrong password"),
+        "108003" => Some("User is already logged in"),
+        "108006" => Some("Wrong username or password"),
+        "108007" => Some("Too many login attempts, try again later"),
+        "125001" => Some("Wrong verification token"),
+        "125002" => Some("Wrong session"),
+        "125003" => Some("Wrong session token"),
+        _ => None,
+    }
+}
+
 /// Check if XML contains error status
 fn check_huawei_error_xml(xml: &xmltree::Element) -> Result<(), HuaweiError> {
     if xml.name.eq("error") {
@@ -62,28 +149,254 @@ fn check_huawei_error_xml(xml: &xmltree::Element) -> Result<(), HuaweiError> {
 /// Session info for Huawei web UI: (session info, token info)
 pub type SessionInfo = (String, String);
 
+/// Session of Huawei web UI that is kept between polls
+struct HuaweiSession {
+    host: String,
+    /// HTTP agent that keeps connections and cookies
+    agent: ureq::Agent,
+    /// Older firmwares don't use session tokens
+    token: Option<SessionInfo>,
+    /// Only LTE-A firmwares have list of secondary carriers. Unsupported endpoint
+    /// is not requested again within the session
+    cell_info_supported: bool,
+}
+
+impl HuaweiSession {
+    fn new(host: &str) -> Self {
+        let agent = new_session_agent();
+        let token = HuaweiParser::get_session_token(&agent, host);
+        Self {
+            host: host.to_string(),
+            agent,
+            token,
+            cell_info_supported: true,
+        }
+    }
+    fn get_xml(&self, query: &str) -> Result<xmltree::Element, ModemError> {
+        get_url_xml_with_session_token(&self.agent, &self.host, self.token.as_ref(), query)
+    }
+}
+
+/// Encoding of password in login request ('password_type' in login state)
+#[derive(PartialEq)]
+enum PasswordType {
+    Base64,
+    Sha256,
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Encode password for login request
+fn encode_password(credentials: &Credentials, password_type: &PasswordType, token: &str) -> String {
+    match password_type {
+        PasswordType::Base64 => BASE64.encode(credentials.password.as_bytes()),
+        PasswordType::Sha256 => {
+            let password_hash = BASE64.encode(sha256_hex(&credentials.password));
+            BASE64.encode(sha256_hex(&format!(
+                "{}{password_hash}{token}",
+                credentials.username
+            )))
+        }
+    }
+}
+
+/// Calculate client proof for SCRAM authentication
+fn scram_client_proof(
+    password: &str,
+    salt: &[u8],
+    iterations: u32,
+    first_nonce: &str,
+    server_nonce: &str,
+) -> String {
+    let mut salted_password = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut salted_password);
+
+    let client_key = hmac_sha256(&salted_password, b"Client Key");
+    let stored_key = Sha256::digest(&client_key);
+
+    let auth_message = format!("{first_nonce},{server_nonce},{server_nonce}");
+    let client_signature = hmac_sha256(&stored_key, auth_message.as_bytes());
+
+    let client_proof = client_key
+        .iter()
+        .zip(client_signature)
+        .map(|(key, signature)| key ^ signature)
+        .collect::<Vec<u8>>();
+
+    to_hex_string(&client_proof)
+}
+
 /*
  * Utils for Huawei
  */
 
-pub struct HuaweiParser {}
+pub struct HuaweiParser {
+    credentials: Credentials,
+    session: Option<HuaweiSession>,
+}
 
 impl HuaweiParser {
+    pub fn new(credentials: &Credentials) -> Self {
+        Self {
+            credentials: credentials.clone(),
+            session: None,
+        }
+    }
+    /// Parse component carrier. Same element names are used for PCC in signal XML
+    /// and for SCC entries in CA info XML
+    fn parse_carrier_xml(xml: &xmltree::Element, role: CarrierRole) -> ComponentCarrier {
+        let earfcn =
+            get_xml_element(xml, "earfcn").and_then(|earfcn| parse_huawei_channel(&earfcn));
+        ComponentCarrier {
+            role,
+            band: get_xml_element(xml, "band")
+                .and_then(|band| parse_band_number(&band))
+                .or_else(|| earfcn.and_then(get_lte_band))
+                .unwrap_or(0),
+            earfcn,
+            bandwidth: get_xml_element(xml, "dlbandwidth")
+                .and_then(|bandwidth| parse_bandwidth_mhz(&bandwidth)),
+            pci: get_xml_element_as_unit::<i64>(xml, "pci"),
+            rsrp: get_xml_element_as_unit::<i64>(xml, "rsrp"),
+            rsrq: get_xml_element_as_unit::<i64>(xml, "rsrq"),
+            sinr: get_xml_element_as_unit::<i64>(xml, "sinr"),
+        }
+    }
+    fn parse_channel_info_xml(
+        xml: &xmltree::Element,
+        earfcn: Option<i64>,
+        is_lte: bool,
+    ) -> Option<ChannelInfo> {
+        let (mut dl_frequency, mut ul_frequency) = (
+            get_xml_element(xml, "ltedlfreq").and_then(|freq| parse_huawei_frequency(&freq)),
+            get_xml_element(xml, "lteulfreq").and_then(|freq| parse_huawei_frequency(&freq)),
+        );
+        if dl_frequency.is_none()
+            && is_lte
+            && let Some((dl, ul)) = earfcn.and_then(get_lte_frequencies)
+        {
+            dl_frequency = Some(dl);
+            ul_frequency = ul;
+        }
+
+        let channel_info = ChannelInfo {
+            earfcn,
+            dl_frequency,
+            ul_frequency,
+            dl_bandwidth: get_xml_element(xml, "dlbandwidth")
+                .and_then(|bandwidth| parse_bandwidth_mhz(&bandwidth)),
+            ul_bandwidth: get_xml_element(xml, "ulbandwidth")
+                .and_then(|bandwidth| parse_bandwidth_mhz(&bandwidth)),
+        };
+
+        if channel_info.earfcn.is_none()
+            && channel_info.dl_frequency.is_none()
+            && channel_info.dl_bandwidth.is_none()
+        {
+            None
+        } else {
+            Some(channel_info)
+        }
+    }
+    fn parse_ca_info_xml(xml: &xmltree::Element) -> Vec<ComponentCarrier> {
+        xml.children
+            .iter()
+            .filter_map(|node| node.as_element())
+            .filter(|element| element.name.eq("scc"))
+            .map(|element| HuaweiParser::parse_carrier_xml(element, CarrierRole::Secondary))
+            .collect()
+    }
+    fn get_secondary_carriers(session: &mut HuaweiSession) -> Option<Vec<ComponentCarrier>> {
+        const CELL_INFO_QUERY: &str = "/api/net/cell-info";
+
+        if !session.cell_info_supported {
+            return None;
+        }
+
+        match session.get_xml(CELL_INFO_QUERY) {
+            Ok(xml) if check_huawei_error_xml(&xml).is_ok() => {
+                Some(HuaweiParser::parse_ca_info_xml(&xml))
+            }
+            // Firmware reports error code or HTTP status for unknown endpoint
+            Ok(_) => {
+                eprintln!("{CELL_INFO_QUERY} is not supported");
+                session.cell_info_supported = false;
+                None
+            }
+            Err(e) if e.http_status.is_some() => {
+                eprintln!("{CELL_INFO_QUERY} is not supported");
+                session.cell_info_supported = false;
+                None
+            }
+            Err(_) => None,
+        }
+    }
+    fn parse_nr_signal_xml(xml: &xmltree::Element, nci: i64) -> Option<NrSignalInfo> {
+        // NR parameters are silently missing on LTE-only firmwares
+        let rsrp = get_xml_element_as_unit::<i64>(xml, "nrrsrp")?;
+        let rsrq = get_xml_element_as_unit::<i64>(xml, "nrrsrq")?;
+        let sinr = get_xml_element_as_unit::<i64>(xml, "nrsinr")?;
+
+        let band = get_xml_element(xml, "nrband")
+            .and_then(|band| parse_band_number(&band))
+            .unwrap_or(0);
+        let arfcn = get_xml_element(xml, "nrearfcn")
+            .and_then(|arfcn| parse_huawei_channel(&arfcn))
+            .unwrap_or(0);
+        let pci = get_xml_element_as_unit::<i64>(xml, "nrpci").unwrap_or(-1);
+
+        let nci = get_xml_element_as::<i64>(xml, "nrcellid").unwrap_or(nci);
+        let (gnb, id) = NrSignalInfo::split_nci(nci);
+
+        Some(NrSignalInfo {
+            rsrq,
+            rsrp,
+            sinr,
+            band,
+            arfcn,
+            gnb,
+            id,
+            pci,
+        })
+    }
     fn parse_signal_xml(xml: &xmltree::Element) -> Option<ModemStatus> {
         const REQUIRED_PARAMETERS: [&str; 3] = ["mode", "rssi", "cell_id"];
         if !xml_contains_required_parameters(xml, &REQUIRED_PARAMETERS) {
             return None;
         }
 
-        let mode = get_mode_by_id(get_xml_element(xml, "mode").unwrap().as_str());
+        let mut mode = get_mode_by_id(get_xml_element(xml, "mode").unwrap().as_str());
 
         let rssi = get_xml_element_as_unit::<i64>(xml, "rssi").unwrap();
 
         let plmn = PlmnStatus::from_str("00000").expect("Unable to convert PLMN from string"); // PLMN is set by a different request
-        let band = ['\0'; 20]; // TODO: Band on Huawei?
-
         let cell_id = get_xml_element_as::<i64>(xml, "cell_id").unwrap();
 
+        // Band and channel are missing on some firmwares
+        let is_lte = mode == NetworkMode::Lte;
+
+        let earfcn =
+            get_xml_element(xml, "earfcn").and_then(|earfcn| parse_huawei_channel(&earfcn));
+
+        let mut band = ['\0'; 20];
+        if let Some(band_number) = get_xml_element(xml, "band")
+            .and_then(|band| parse_band_number(&band))
+            .or_else(|| earfcn.filter(|_| is_lte).and_then(get_lte_band))
+        {
+            let band_str = if is_lte {
+                format!("LTE B{band_number}")
+            } else {
+                format!("B{band_number}")
+            };
+            copy_string_to_array!(band, band_str);
+        }
+
+        let channel_info = HuaweiParser::parse_channel_info_xml(xml, earfcn, is_lte);
+
         let signal_info: SignalInfo = match mode {
             NetworkMode::Wcdma => {
                 const WCDMA_PARAMETERS: [&str; 2] = ["rscp", "ecio"];
@@ -123,19 +436,41 @@ impl HuaweiParser {
 
                 let (enb, id) = (cell_id >> 8, cell_id & 0xFF);
 
-                SignalInfo::Lte(LteSignalInfo {
+                let lte_info = LteSignalInfo {
                     rsrq,
                     rsrp,
                     sinr,
-                    ca_count: 0,
+                    ca_count: 0, // Set from CA info
                     enb,
                     id,
-                    pci, // TODO: ca_count on Huawei?
-                })
+                    pci,
+                };
+
+                // LTE anchor with NR leg (EN-DC)
+                if let Some(nr_info) = HuaweiParser::parse_nr_signal_xml(xml, 0) {
+                    mode = NetworkMode::NrNsa;
+                    SignalInfo::NrNsa(lte_info, nr_info)
+                } else {
+                    SignalInfo::Lte(lte_info)
+                }
+            }
+            NetworkMode::Nr => {
+                if let Some(nr_info) = HuaweiParser::parse_nr_signal_xml(xml, cell_id) {
+                    SignalInfo::Nr(nr_info)
+                } else {
+                    return None;
+                }
             }
             _ => SignalInfo::None,
         };
 
+        let carriers = match mode {
+            NetworkMode::Lte | NetworkMode::NrNsa => {
+                vec![HuaweiParser::parse_carrier_xml(xml, CarrierRole::Primary)]
+            }
+            _ => Vec::new(),
+        };
+
         Some(ModemStatus {
             mode,
             plmn,
@@ -143,6 +478,10 @@ impl HuaweiParser {
             cell_id,
             signal_info,
             band,
+            carriers,
+            channel_info,
+            tac: None,
+            antenna_rsrp: Vec::new(),
             device_info: DeviceInformation::from("HUAWEI", ""),
             battery_status: None,
             device_temp: None,
@@ -160,10 +499,157 @@ impl HuaweiParser {
             None
         }
     }
-    fn get_session_token(host: &str) -> Option<SessionInfo> {
-        get_url_xml(host, "/api/webserver/SesTokInfo")
+    fn get_session_token(agent: &ureq::Agent, host: &str) -> Option<SessionInfo> {
+        get_url_xml_with_agent(agent, host, "/api/webserver/SesTokInfo")
+            .ok()
             .and_then(|xml| HuaweiParser::parse_session_token_xml(&xml))
     }
+    /// Send POST request and update session with new cookie and token from the response
+    fn post_request(
+        session: &mut HuaweiSession,
+        query: &str,
+        request: &str,
+    ) -> Result<xmltree::Element, ModemError> {
+        let body =
+            format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?><request>{request}</request>");
+
+        let response = post_url_xml_with_session_token(
+            &session.agent,
+            &session.host,
+            session.token.as_ref(),
+            query,
+            &body,
+        )?;
+
+        if let Some(session_token) = &mut session.token {
+            if let Some(session_info) = response.session_info {
+                session_token.0 = session_info;
+            }
+            if let Some(token_info) = response.token_info {
+                session_token.1 = token_info;
+            }
+        }
+
+        // Login requests that fail only because user is already logged in are successful
+        if let Err(e) = check_huawei_error_xml(&response.xml)
+            && !e.is_already_logged_in()
+        {
+            eprintln!("Request '{query}' error: {e}");
+            let kind = if e.is_credentials_error() {
+                ModemErrorKind::Authentication
+            } else {
+                ModemErrorKind::Access
+            };
+            return Err(e.into_modem_error(kind, query));
+        }
+
+        Ok(response.xml)
+    }
+    fn get_password_type(session: &HuaweiSession) -> PasswordType {
+        let password_type = session
+            .get_xml("/api/user/state-login")
+            .ok()
+            .and_then(|xml| get_xml_element(&xml, "password_type"));
+
+        if password_type.is_some_and(|password_type| password_type.eq("4")) {
+            PasswordType::Sha256
+        } else {
+            PasswordType::Base64
+        }
+    }
+    /// Login with encoded password (base64 or SHA256 'password_type 4' scheme)
+    fn login_with_password(
+        session: &mut HuaweiSession,
+        credentials: &Credentials,
+        password_type: &PasswordType,
+    ) -> Result<(), ModemError> {
+        let token_info = session
+            .token
+            .as_ref()
+            .map(|(_, token_info)| token_info.as_str())
+            .unwrap_or_default();
+        let password = encode_password(credentials, password_type, token_info);
+        let request = format!(
+            "<Username>{}</Username><Password>{password}</Password><password_type>{}</password_type>",
+            escape_xml(&credentials.username),
+            if *password_type == PasswordType::Sha256 {
+                "4"
+            } else {
+                "0"
+            }
+        );
+
+        HuaweiParser::post_request(session, "/api/user/login", &request).map(|_| ())
+    }
+    /// Login with SCRAM challenge and authentication (newer firmwares)
+    fn login_with_scram(
+        session: &mut HuaweiSession,
+        credentials: &Credentials,
+    ) -> Result<(), ModemError> {
+        let first_nonce = to_hex_string(&rand::thread_rng().r#gen::<[u8; 32]>());
+
+        let request = format!(
+            "<username>{}</username><firstnonce>{first_nonce}</firstnonce><mode>1</mode>",
+            escape_xml(&credentials.username)
+        );
+        let xml = HuaweiParser::post_request(session, "/api/user/challenge_login", &request)?;
+        if check_huawei_error_xml(&xml).is_err() {
+            // Already logged in
+            return Ok(());
+        }
+
+        let (Some(salt), Some(iterations), Some(server_nonce)) = (
+            get_xml_element(&xml, "salt").and_then(|salt| from_hex_string(&salt)),
+            get_xml_element_as::<u32>(&xml, "iterations"),
+            get_xml_element(&xml, "servernonce"),
+        ) else {
+            eprintln!("Cannot parse SCRAM challenge");
+            return Err(ModemError::data_parsing("SCRAM challenge")
+                .with_endpoint("/api/user/challenge_login"));
+        };
+
+        let client_proof = scram_client_proof(
+            &credentials.password,
+            &salt,
+            iterations,
+            &first_nonce,
+            &server_nonce,
+        );
+
+        let request = format!(
+            "<clientproof>{client_proof}</clientproof><finalnonce>{server_nonce}</finalnonce>"
+        );
+        HuaweiParser::post_request(session, "/api/user/authentication_login", &request).map(|_| ())
+    }
+    /// Login to web UI within the session
+    fn login(session: &mut HuaweiSession, credentials: &Credentials) -> Result<(), ModemError> {
+        if credentials.password.is_empty() {
+            eprintln!("Login is required but password is not set");
+            return Err(ModemError::access(
+                "Login is required but password is not set",
+            ));
+        }
+
+        let password_type = HuaweiParser::get_password_type(session);
+
+        if password_type == PasswordType::Sha256 {
+            match HuaweiParser::login_with_scram(session, credentials) {
+                Ok(()) => return Ok(()),
+                // Another attempt with the same credentials counts towards lockout
+                Err(e) if e.kind == ModemErrorKind::Authentication => return Err(e),
+                Err(_) => {}
+            }
+
+            // SCRAM is not supported, start over with new token
+            session.token = HuaweiParser::get_session_token(&session.agent, &session.host);
+            if session.token.is_none() {
+                return Err(ModemError::http_connection("Cannot get session token")
+                    .with_endpoint("/api/webserver/SesTokInfo"));
+            }
+        }
+
+        HuaweiParser::login_with_password(session, credentials, &password_type)
+    }
     fn parse_traffic_statistics_xml(xml: &xmltree::Element) -> TrafficStatistics {
         let dl = if let Some(dl) = get_xml_element_as_unit::<i64>(xml, "CurrentDownloadRate") {
             dl * 8
@@ -177,17 +663,11 @@ impl HuaweiParser {
         };
         TrafficStatistics { dl, ul }
     }
-    fn get_traffic_statistics(
-        host: &str,
-        session_token: Option<&SessionInfo>,
-    ) -> Option<TrafficStatistics> {
-        let xml = get_url_xml_with_session_token(
-            host,
-            session_token,
-            "/api/monitoring/traffic-statistics",
-        );
+    fn get_traffic_statistics(session: &HuaweiSession) -> Option<TrafficStatistics> {
+        let xml = session.get_xml("/api/monitoring/traffic-statistics");
 
-        xml.map(|xml| HuaweiParser::parse_traffic_statistics_xml(&xml))
+        xml.ok()
+            .map(|xml| HuaweiParser::parse_traffic_statistics_xml(&xml))
     }
     fn parse_battery_status_xml(xml: &xmltree::Element) -> Option<BatteryStatus> {
         let battery_percent = get_xml_element_as_unit::<i64>(xml, "BatteryPercent")?;
@@ -212,13 +692,10 @@ impl HuaweiParser {
             status: battery_status,
         })
     }
-    fn get_battery_status(
-        host: &str,
-        session_token: Option<&SessionInfo>,
-    ) -> Option<BatteryStatus> {
-        let xml = get_url_xml_with_session_token(host, session_token, "/api/monitoring/status");
+    fn get_battery_status(session: &HuaweiSession) -> Option<BatteryStatus> {
+        let xml = session.get_xml("/api/monitoring/status");
 
-        if let Some(xml) = xml {
+        if let Ok(xml) = xml {
             HuaweiParser::parse_battery_status_xml(&xml)
         } else {
             None
@@ -229,10 +706,10 @@ impl HuaweiParser {
 
         PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string")
     }
-    fn get_plmn_status(host: &str, session_token: Option<&SessionInfo>) -> Option<PlmnStatus> {
-        let xml = get_url_xml_with_session_token(host, session_token, "/api/net/current-plmn");
+    fn get_plmn_status(session: &HuaweiSession) -> Option<PlmnStatus> {
+        let xml = session.get_xml("/api/net/current-plmn");
 
-        xml.map(|xml| HuaweiParser::parse_plmn_xml(&xml))
+        xml.ok().map(|xml| HuaweiParser::parse_plmn_xml(&xml))
     }
     fn parse_device_model_xml(xml: &xmltree::Element) -> String {
         if let Some(model_str) = get_xml_element(xml, "devicename") {
@@ -242,36 +719,26 @@ impl HuaweiParser {
         }
     }
     fn get_device_model_by_query(
-        host: &str,
-        session_token: Option<&SessionInfo>,
+        session: &HuaweiSession,
         query: &str,
     ) -> Result<String, ModemError> {
         let query = format!("/api/device/{query}");
-        let xml = get_url_xml_with_session_token(host, session_token, &query);
+        let xml = session.get_xml(&query)?;
 
-        if let Some(xml) = xml {
-            match check_huawei_error_xml(&xml) {
-                Err(e) => {
-                    eprintln!("Device Information Access error: {e}");
-                    Err(ModemError::Access)
-                }
-                Ok(()) => Ok(HuaweiParser::parse_device_model_xml(&xml)),
+        match check_huawei_error_xml(&xml) {
+            Err(e) => {
+                eprintln!("Device Information Access error: {e}");
+                Err(e.into_modem_error(ModemErrorKind::Access, &query))
             }
-        } else {
-            Err(ModemError::HttpConnection)
+            Ok(()) => Ok(HuaweiParser::parse_device_model_xml(&xml)),
         }
     }
-    fn get_device_information(
-        host: &str,
-        session_token: Option<&SessionInfo>,
-    ) -> Result<DeviceInformation, ModemError> {
+    fn get_device_information(session: &HuaweiSession) -> Result<DeviceInformation, ModemError> {
         let manufacturer = "HUAWEI"; // Hardcoded
         let mut model = String::new();
 
         for query in ["basic_information", "information"] {
-            if let Ok(model_str) =
-                HuaweiParser::get_device_model_by_query(host, session_token, query)
-            {
+            if let Ok(model_str) = HuaweiParser::get_device_model_by_query(session, query) {
                 if !model_str.is_empty() {
                     model = model_str;
                     break;
@@ -282,48 +749,133 @@ impl HuaweiParser {
         }
 
         if model.is_empty() {
-            Err(ModemError::Access)
+            Err(ModemError::access("Cannot get device model").with_endpoint("/api/device/"))
         } else {
             Ok(DeviceInformation::from(manufacturer, model.as_str()))
         }
     }
 }
 
+impl HuaweiParser {
+    /// Check if host has Huawei web UI. Model may be hidden until login
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let session = HuaweiSession::new(host);
+        let device_info = HuaweiParser::get_device_information(&session);
+        if session.token.is_none() && device_info.is_err() {
+            return None;
+        }
+        Some(device_info.unwrap_or_else(|_| DeviceInformation::from("HUAWEI", "")))
+    }
+}
+
 impl ModemInfoParser for HuaweiParser {
-    fn get_info(host: &str) -> Result<ModemStatus, ModemError> {
-        let session_token = HuaweiParser::get_session_token(host);
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        const SIGNAL_QUERY: &str = "/api/device/signal";
 
-        let xml =
-            get_url_xml_with_session_token(host, session_token.as_ref(), "/api/device/signal");
+        // Session is reused between polls and dropped on connection errors
+        let mut session = match self.session.take() {
+            Some(session) if session.host == host => session,
+            _ => HuaweiSession::new(host),
+        };
 
-        if let Some(xml) = xml {
-            if let Some(mut modem_status) = HuaweiParser::parse_signal_xml(&xml) {
-                if let Some(plmn) = HuaweiParser::get_plmn_status(host, session_token.as_ref()) {
-                    modem_status.plmn = plmn;
-                }
+        let mut xml = session
+            .get_xml(SIGNAL_QUERY)
+            .map_err(|e| e.context(&format!("Cannot get signal data from host {host}")))?;
 
-                modem_status.traffic_statistics =
-                    HuaweiParser::get_traffic_statistics(host, session_token.as_ref());
-                modem_status.battery_status =
-                    HuaweiParser::get_battery_status(host, session_token.as_ref());
+        // Token is no longer valid (e.g. after reboot of the modem): start new session
+        if check_huawei_error_xml(&xml).is_err_and(|e| e.is_token_error()) {
+            session = HuaweiSession::new(host);
+            xml = session.get_xml(SIGNAL_QUERY)?;
+        }
 
-                // Get model info
-                if let Ok(device_info) =
-                    HuaweiParser::get_device_information(host, session_token.as_ref())
-                {
-                    modem_status.device_info = device_info;
-                } else {
-                    /* Ignore errors from getting device data */
-                }
+        // Login when firmware doesn't give access to signal data without it
+        if session.token.is_some()
+            && check_huawei_error_xml(&xml).is_err_and(|e| e.is_login_required())
+        {
+            HuaweiParser::login(&mut session, &self.credentials)
+                .map_err(|e| e.context("Login to web UI failed"))?;
 
-                Ok(modem_status)
-            } else {
-                eprintln!("Cannot parse signal data");
-                Err(ModemError::DataParsing)
+            xml = session.get_xml(SIGNAL_QUERY)?;
+        }
+
+        let session = self.session.insert(session);
+
+        if let Err(e) = check_huawei_error_xml(&xml) {
+            eprintln!("Signal data error: {e}");
+            return Err(e.into_modem_error(ModemErrorKind::Access, SIGNAL_QUERY));
+        }
+
+        let Some(mut modem_status) = HuaweiParser::parse_signal_xml(&xml) else {
+            eprintln!("Cannot parse signal data");
+            return Err(ModemError::data_parsing("signal XML").with_endpoint(SIGNAL_QUERY));
+        };
+
+        if let Some(plmn) = HuaweiParser::get_plmn_status(session) {
+            modem_status.plmn = plmn;
+        }
+
+        // Secondary carriers are requested only when PCC is present (LTE modes)
+        if !modem_status.carriers.is_empty()
+            && let Some(sccs) = HuaweiParser::get_secondary_carriers(session)
+        {
+            if let SignalInfo::Lte(lte_info) | SignalInfo::NrNsa(lte_info, _) =
+                &mut modem_status.signal_info
+            {
+                lte_info.ca_count = i64::try_from(sccs.len()).unwrap_or_default();
             }
+            modem_status.carriers.extend(sccs);
+        }
+
+        modem_status.traffic_statistics = HuaweiParser::get_traffic_statistics(session);
+        modem_status.battery_status = HuaweiParser::get_battery_status(session);
+
+        // Get model info
+        if let Ok(device_info) = HuaweiParser::get_device_information(session) {
+            modem_status.device_info = device_info;
         } else {
-            eprintln!("Cannot get signal data from host {host}");
-            Err(ModemError::HttpConnection)
+            /* Ignore errors from getting device data */
+        }
+
+        Ok(modem_status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error_xml(code: &str) -> HuaweiError {
+        let xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><error><code>{code}</code><message></message></error>"
+        );
+        let xml = xmltree::Element::parse(xml.as_bytes()).unwrap();
+        let Err(e) = check_huawei_error_xml(&xml) else {
+            panic!("Error is not detected");
+        };
+        e
+    }
+
+    #[test]
+    fn scram_proof() {
+        // Salted password: PBKDF2-HMAC-SHA256, auth message: 'first,server,server'
+        let salt =
+            from_hex_string("4f6e0ba4a9f1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b")
+                .unwrap();
+        let first_nonce = "a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90";
+        let server_nonce = format!("{first_nonce}5d2c8e9f0a1b3c4d5e6f708192a3b4c5");
+
+        assert_eq!(
+            scram_client_proof("admin", &salt, 100, first_nonce, &server_nonce),
+            "72ef6685360595b05eb80b7aa5ce2e119f291ffd8f8fcf04b559e00ffd49a9be"
+        );
+    }
+
+    #[test]
+    fn credentials_errors() {
+        for code in ["108002", "108006", "108007"] {
+            assert!(parse_error_xml(code).is_credentials_error());
         }
+        assert!(!parse_error_xml("108003").is_credentials_error());
+        assert!(!parse_error_xml("125002").is_credentials_error());
     }
 }
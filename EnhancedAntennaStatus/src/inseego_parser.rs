@@ -0,0 +1,418 @@
+#![allow(clippy::similar_names)]
+
+use std::str::FromStr;
+
+use crate::bandwidth_utils::{SIZE_TB, TrafficMode, TrafficStatistics};
+use crate::earfcn_utils::get_lte_frequencies;
+use crate::modem_utils::{
+    BatteryStatus, ChannelInfo, Credentials, DeviceInformation, LteSignalInfo, ModemError,
+    ModemErrorKind, ModemInfoParser, ModemStatus, NetworkMode, NrSignalInfo, PlmnStatus,
+    SignalInfo, WcdmaSignalInfo,
+};
+use crate::network_utils::{
+    get_url_json_with_agent, get_url_string_with_agent, new_session_agent, post_url_form_with_agent,
+};
+use crate::utils::{copy_string_to_array, parse_band_number, sha256_hex};
+
+/// Status bar of web UI. Available without login
+const STATUS_QUERY: &str = "/srv/status";
+
+/// Cellular info of diagnostics page. Requires login
+const DIAGNOSTICS_QUERY: &str = "/apps_home/diagnostics";
+
+const LOGIN_PAGE_QUERY: &str = "/login/";
+const LOGIN_QUERY: &str = "/submitLogin/";
+
+/// Convert technology of status bar (e.g. '4G LTE', '5G', '3G') to `NetworkMode` enum
+fn get_mode_by_technology(s: &str) -> NetworkMode {
+    let s = s.to_uppercase();
+    if s.contains("5G") {
+        NetworkMode::Nr
+    } else if s.contains("LTE") || s.contains("4G") {
+        NetworkMode::Lte
+    } else if s.contains("3G") || s.contains("UMTS") || s.contains("HSPA") {
+        NetworkMode::Wcdma
+    } else if s.contains("2G") || s.contains("GSM") || s.contains("EDGE") {
+        NetworkMode::Gsm
+    } else {
+        NetworkMode::Unknown
+    }
+}
+
+/// Parse numeric parameter that may be reported either as number or as string with unit
+fn get_inseego_i64(val: &serde_json::Value) -> Option<i64> {
+    val.as_i64().or_else(|| {
+        val.as_str()
+            .and_then(|val| val.split_whitespace().next())
+            .and_then(|val| val.parse::<f64>().ok())
+            .map(|val| val.round() as i64)
+    })
+}
+
+/// Get required numeric field of cellular diagnostics
+fn required_inseego_i64(cellular: &serde_json::Value, name: &str) -> Result<i64, ModemError> {
+    get_inseego_i64(&cellular[name]).ok_or_else(|| {
+        eprintln!("Diagnostics JSON doesn't have required field '{name}'");
+        ModemError::data_parsing(name).with_endpoint(DIAGNOSTICS_QUERY)
+    })
+}
+
+/*
+ * Utils for Inseego
+ */
+
+pub struct InseegoParser {
+    credentials: Credentials,
+    /// HTTP agent with session cookie that is reused between polls
+    agent: ureq::Agent,
+}
+
+impl InseegoParser {
+    pub fn new(credentials: &Credentials) -> Self {
+        Self {
+            credentials: credentials.clone(),
+            agent: new_session_agent(),
+        }
+    }
+
+    fn get_status_json(agent: &ureq::Agent, host: &str) -> Result<serde_json::Value, ModemError> {
+        get_url_json_with_agent(agent, host, STATUS_QUERY)
+    }
+
+    /// Get secure token from script of the login page: `gSecureToken : "..."`
+    fn parse_secure_token(page: &str) -> Option<String> {
+        const TOKEN_MARKER: &str = "gSecureToken";
+        let start = page.find(TOKEN_MARKER)? + TOKEN_MARKER.len();
+        let start = start + page[start..].find('"')? + 1;
+        let len = page[start..].find('"')?;
+        Some(page[start..start + len].to_string())
+    }
+
+    /// Login to web UI. Session cookie is kept by the agent
+    fn login(agent: &ureq::Agent, host: &str, credentials: &Credentials) -> Result<(), ModemError> {
+        if credentials.password.is_empty() {
+            eprintln!("Login is required but password is not set");
+            return Err(ModemError::access(
+                "Login is required but password is not set",
+            ));
+        }
+
+        let page = get_url_string_with_agent(agent, host, LOGIN_PAGE_QUERY)?;
+
+        let Some(token) = InseegoParser::parse_secure_token(&page) else {
+            eprintln!("Cannot find secure token on login page");
+            return Err(ModemError::data_parsing("gSecureToken").with_endpoint(LOGIN_PAGE_QUERY));
+        };
+
+        let password_hash = sha256_hex(&format!("{}{token}", credentials.password));
+        post_url_form_with_agent(
+            agent,
+            host,
+            LOGIN_QUERY,
+            &[
+                ("shaPassword", password_hash.as_str()),
+                ("gSecureToken", token.as_str()),
+            ],
+        )
+        .map_err(|e| e.context("Login to web UI failed"))?;
+
+        Ok(())
+    }
+
+    /// Get diagnostics JSON. Web UI redirects to login page if session is not active
+    fn get_diagnostics_json(&self, host: &str) -> Result<serde_json::Value, ModemError> {
+        if let Ok(json) = get_url_json_with_agent(&self.agent, host, DIAGNOSTICS_QUERY)
+            && json.is_object()
+        {
+            return Ok(json);
+        }
+
+        InseegoParser::login(&self.agent, host, &self.credentials)?;
+
+        // Web UI doesn't report rejected password. Session stays inactive and diagnostics
+        // request is redirected to login page again
+        match get_url_json_with_agent(&self.agent, host, DIAGNOSTICS_QUERY) {
+            Ok(json) if json.is_object() => Ok(json),
+            Err(e)
+                if e.kind == ModemErrorKind::HttpConnection
+                    && !matches!(e.http_status, Some(401 | 403)) =>
+            {
+                Err(e.context("Diagnostics are not available after login"))
+            }
+            // Login page instead of JSON or access denied
+            _ => {
+                eprintln!("Diagnostics are not available after login, password is rejected");
+                Err(ModemError::authentication(
+                    "Password is rejected, diagnostics are not available after login",
+                )
+                .with_endpoint(LOGIN_QUERY))
+            }
+        }
+    }
+
+    fn parse_device_info_json(json: &serde_json::Value) -> DeviceInformation {
+        let model_str = json["statusData"]["statusBarDeviceName"]
+            .as_str()
+            .unwrap_or_default();
+        DeviceInformation::from("Inseego", model_str)
+    }
+
+    /// Check if host has status bar JSON of Inseego web UI
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let agent = new_session_agent();
+        let json = InseegoParser::get_status_json(&agent, host).ok()?;
+        if json["statusData"].is_object() {
+            Some(InseegoParser::parse_device_info_json(&json))
+        } else {
+            None
+        }
+    }
+
+    fn parse_battery_status_json(json: &serde_json::Value) -> Option<BatteryStatus> {
+        let status = &json["statusData"];
+        let battery_percent = get_inseego_i64(&status["statusBarBatteryPercent"])?;
+
+        let battery_status_str = status["statusBarBatteryChargingState"]
+            .as_str()
+            .unwrap_or_default();
+        let mut battery_status = ['\0'; 20];
+        copy_string_to_array!(battery_status, battery_status_str);
+
+        Some(BatteryStatus {
+            percent: battery_percent,
+            status: battery_status,
+        })
+    }
+
+    fn parse_traffic_statistics_json(json: &serde_json::Value) -> Option<TrafficStatistics> {
+        let status = &json["statusData"];
+        // Total number of bytes of the current session
+        let dl = get_inseego_i64(&status["statusBarBytesReceived"]);
+        let ul = get_inseego_i64(&status["statusBarBytesSent"]);
+        if dl.is_none() && ul.is_none() {
+            return None;
+        }
+
+        let to_bits = |bytes: Option<i64>| {
+            bytes
+                .filter(|bytes| *bytes <= SIZE_TB)
+                .map_or(0, |bytes| bytes * 8)
+        };
+        Some(TrafficStatistics {
+            dl: to_bits(dl),
+            ul: to_bits(ul),
+        })
+    }
+
+    fn parse_info_json(
+        status: &serde_json::Value,
+        diagnostics: &serde_json::Value,
+    ) -> Result<ModemStatus, ModemError> {
+        let Some(technology) = status["statusData"]["statusBarTechnology"].as_str() else {
+            eprintln!("Status JSON doesn't have required field 'statusBarTechnology'");
+            return Err(ModemError::data_parsing("statusBarTechnology").with_endpoint(STATUS_QUERY));
+        };
+        let mode = get_mode_by_technology(technology);
+
+        let cellular = &diagnostics["cellular"];
+
+        let rssi = get_inseego_i64(&cellular["rssi"]).unwrap_or(0);
+
+        let plmn_str = format!(
+            "{}{}",
+            cellular["mcc"].as_str().unwrap_or("000"),
+            cellular["mnc"].as_str().unwrap_or("00")
+        );
+        let plmn = PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string");
+
+        let band_number = cellular["band"]
+            .as_str()
+            .and_then(parse_band_number)
+            .or_else(|| cellular["band"].as_i64());
+        let mut band = ['\0'; 20];
+        if let Some(band_number) = band_number {
+            let band_str = match mode {
+                NetworkMode::Nr => format!("NR n{band_number}"),
+                _ => format!("LTE B{band_number}"),
+            };
+            copy_string_to_array!(band, band_str);
+        }
+
+        let cell_id = get_inseego_i64(&cellular["cellId"]).unwrap_or(0);
+        let pci = get_inseego_i64(&cellular["pci"]).unwrap_or(-1);
+
+        let signal_info = match mode {
+            NetworkMode::Wcdma => {
+                let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
+                let (nb, cc) = (id / 10, id % 10);
+                SignalInfo::Wcdma(WcdmaSignalInfo {
+                    rscp: required_inseego_i64(cellular, "rscp")?,
+                    ecio: required_inseego_i64(cellular, "ecio")?,
+                    nb,
+                    cc,
+                    rnc,
+                    psc: 0,
+                })
+            }
+            NetworkMode::Lte => SignalInfo::Lte(LteSignalInfo {
+                rsrq: required_inseego_i64(cellular, "rsrq")?,
+                rsrp: required_inseego_i64(cellular, "rsrp")?,
+                sinr: required_inseego_i64(cellular, "sinr")?,
+                ca_count: 0,
+                enb: cell_id >> 8,
+                id: cell_id & 0xFF,
+                pci,
+            }),
+            NetworkMode::Nr => {
+                let (gnb, id) = NrSignalInfo::split_nci(cell_id);
+                SignalInfo::Nr(NrSignalInfo {
+                    rsrq: required_inseego_i64(cellular, "rsrq")?,
+                    rsrp: required_inseego_i64(cellular, "rsrp")?,
+                    sinr: required_inseego_i64(cellular, "sinr")?,
+                    band: band_number.unwrap_or(0),
+                    arfcn: get_inseego_i64(&cellular["earfcn"]).unwrap_or(0),
+                    gnb,
+                    id,
+                    pci,
+                })
+            }
+            _ => SignalInfo::None,
+        };
+
+        let channel_info = get_inseego_i64(&cellular["earfcn"])
+            .filter(|_| mode == NetworkMode::Lte)
+            .map(|earfcn| {
+                let (dl_frequency, ul_frequency) = match get_lte_frequencies(earfcn) {
+                    Some((dl, ul)) => (Some(dl), ul),
+                    None => (None, None),
+                };
+                ChannelInfo {
+                    earfcn: Some(earfcn),
+                    dl_frequency,
+                    ul_frequency,
+                    dl_bandwidth: None,
+                    ul_bandwidth: None,
+                }
+            });
+
+        Ok(ModemStatus {
+            mode,
+            plmn,
+            rssi,
+            cell_id,
+            signal_info,
+            band,
+            carriers: Vec::new(),
+            channel_info,
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_info: InseegoParser::parse_device_info_json(status),
+            battery_status: InseegoParser::parse_battery_status_json(status),
+            device_temp: None,
+            traffic_statistics: InseegoParser::parse_traffic_statistics_json(status),
+            traffic_mode: TrafficMode::Cumulative,
+        })
+    }
+}
+
+impl ModemInfoParser for InseegoParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        let status = InseegoParser::get_status_json(&self.agent, host)
+            .map_err(|e| e.context(&format!("Cannot access status JSON from host {host}")))?;
+
+        let diagnostics = self
+            .get_diagnostics_json(host)
+            .map_err(|e| e.context(&format!("Cannot access diagnostics from host {host}")))?;
+
+        InseegoParser::parse_info_json(&status, &diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Status bar of MiFi 8000
+    const STATUS_JSON: &str = r#"{
+        "statusData": {
+            "statusBarDeviceName": "MiFi 8000", "statusBarTechnology": "4G LTE",
+            "statusBarSignalBars": "4", "statusBarNetwork": "3 UK",
+            "statusBarBatteryPercent": "82", "statusBarBatteryChargingState": "Charging",
+            "statusBarBytesReceived": "123456789", "statusBarBytesSent": "9876543"
+        }
+    }"#;
+
+    /// Cellular section of diagnostics page. Values have units
+    const DIAGNOSTICS_JSON: &str = r#"{
+        "cellular": {
+            "mcc": "234", "mnc": "20", "band": "LTE Band 20", "earfcn": "6300",
+            "cellId": "27436305", "pci": "293", "rssi": "-65 dBm", "rsrp": "-97 dBm",
+            "rsrq": "-10 dB", "sinr": "9.6 dB"
+        }
+    }"#;
+
+    /// Login page with secure token in script
+    const LOGIN_PAGE: &str = r#"<script type="text/javascript">
+        var gSecureToken = "b3c7a5e1d9f24c8e";
+        var gLoginAttempts = 5;
+    </script>"#;
+
+    fn parse_json(status: &str, diagnostics: &str) -> Result<ModemStatus, ModemError> {
+        InseegoParser::parse_info_json(
+            &serde_json::from_str(status).unwrap(),
+            &serde_json::from_str(diagnostics).unwrap(),
+        )
+    }
+
+    #[test]
+    fn lte_info() {
+        let modem_status = parse_json(STATUS_JSON, DIAGNOSTICS_JSON).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE");
+        assert_eq!(modem_status.get_plmn().trim_end_matches('\0'), "23420");
+        assert_eq!(modem_status.get_band(), "LTE B20");
+        assert_eq!(modem_status.rssi, -65);
+        assert_eq!(
+            modem_status.get_frequencies().as_deref(),
+            Some("806.0/847.0 MHz")
+        );
+
+        let SignalInfo::Lte(lte_info) = modem_status.signal_info else {
+            panic!("Signal info is not LTE");
+        };
+        assert_eq!(lte_info.rsrp, -97);
+        assert_eq!(lte_info.rsrq, -10);
+        assert_eq!(lte_info.sinr, 10);
+        assert_eq!(lte_info.pci, 293);
+
+        let (percent, status) = modem_status.get_battery_percent_and_status().unwrap();
+        assert_eq!(percent, 82);
+        assert_eq!(status, "Charging");
+
+        let traffic_statistics = modem_status.traffic_statistics.unwrap();
+        assert_eq!(traffic_statistics.dl, 987_654_312);
+        assert_eq!(traffic_statistics.ul, 79_012_344);
+
+        let (manufacturer, model) = modem_status.device_info.get_manufacturer_and_model();
+        assert_eq!(manufacturer, "Inseego");
+        assert_eq!(model, "MiFi 8000");
+    }
+
+    #[test]
+    fn missing_signal_value() {
+        let diagnostics = DIAGNOSTICS_JSON.replace(r#""rsrq": "-10 dB""#, r#""rsrq": "N/A""#);
+        let Err(e) = parse_json(STATUS_JSON, &diagnostics) else {
+            panic!("Unknown RSRQ is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+
+    #[test]
+    fn secure_token() {
+        assert_eq!(
+            InseegoParser::parse_secure_token(LOGIN_PAGE).as_deref(),
+            Some("b3c7a5e1d9f24c8e")
+        );
+        assert!(InseegoParser::parse_secure_token("<html></html>").is_none());
+    }
+}
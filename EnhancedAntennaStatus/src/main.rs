@@ -1,6 +1,7 @@
 #![allow(clippy::cast_sign_loss)]
 #![allow(clippy::too_many_lines)]
 
+mod earfcn_utils;
 mod network_utils;
 mod utils;
 
@@ -8,13 +9,25 @@ mod bandwidth_utils;
 use bandwidth_utils::{BandwidthCounter, TrafficMode, format_bandwidth};
 
 mod modem_utils;
-use modem_utils::{ModemError, ModemInfoParser, ModemStatus};
+use modem_utils::{DeviceInformation, ModemError, ModemErrorKind, ModemInfoParser, ModemStatus};
 
+mod alcatel_parser;
+mod at_parser;
+mod fritzbox_parser;
+mod huawei_parser;
+mod inseego_parser;
+mod mikrotik_parser;
+mod modemmanager_parser;
 mod netgear_parser;
-use netgear_parser::NetgearParser;
+mod openwrt_parser;
+mod teltonika_parser;
+mod tplink_parser;
+mod zte_parser;
 
-mod huawei_parser;
-use huawei_parser::HuaweiParser;
+mod parser_registry;
+use parser_registry::{
+    PARSER_BACKENDS, ParserBackend, detect_backend, get_backend, get_default_hosts,
+};
 
 mod bar_plot_widget;
 mod res;
@@ -22,27 +35,40 @@ mod res;
 mod main_window;
 use main_window::MainWindow;
 
+use chrono::{DateTime, Local};
 use fltk::{app, prelude::*};
 use fltk_theme::{WidgetTheme, ThemeType};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, SystemTime};
 
 #[derive(Clone)]
 enum Message {
-    GetInfo,
+    /// Poll modem. Polls of previous sessions are ignored by generation
+    GetInfo(u64),
     StartStopPolling,
     ReceivedInfo(Box<ModemStatus>),
     InfoOk,
     InfoError(ModemError),
+    ModemDetected(
+        u64,
+        Option<(&'static ParserBackend, Box<DeviceInformation>)>,
+    ),
+    ManufacturerChanged,
+    ShowErrorDetails,
     Quit,
 }
 
-const MANUFACTURERS: [&str; 2] = ["Netgear", "Huawei"];
+/// First entry of Manufacturer choice, the rest are backends from the registry
+const AUTO_DETECT_CHOICE: &str = "Auto";
 
-const WIDTH: i32 = 840;
-const HEIGHT: i32 = 435;
+/// Get backend selected in Manufacturer choice. None for auto detection
+fn get_selected_backend(choice_id: i32) -> Option<&'static ParserBackend> {
+    get_backend(choice_id - 1)
+}
 
-const DEFAULT_IP_ADDRESSES: [&str; 2] = ["192.168.1.1", "192.168.8.1"];
+const WIDTH: i32 = 840;
+const HEIGHT: i32 = 590;
 
 fn main() {
     let app = app::App::default();
@@ -58,19 +84,24 @@ fn main() {
     /*
      * Initial state of UI
      */
-    for m in MANUFACTURERS {
-        wnd.model_choice.add_choice(m);
+    wnd.model_choice.add_choice(AUTO_DETECT_CHOICE);
+    for backend in &PARSER_BACKENDS {
+        wnd.model_choice.add_choice(backend.name);
     }
     wnd.model_choice.set_value(0);
 
     // Fill list of standard IP addresses
-    for ip_address in DEFAULT_IP_ADDRESSES {
+    let default_hosts = get_default_hosts();
+    for ip_address in &default_hosts {
         wnd.host_input.add(ip_address);
     }
-    wnd.host_input.set_value(DEFAULT_IP_ADDRESSES[0]);
+    wnd.host_input.set_value(default_hosts[0]);
 
+    wnd.model_choice.emit(tx, Message::ManufacturerChanged);
+    tx.send(Message::ManufacturerChanged);
     wnd.connect_button.emit(tx, Message::StartStopPolling);
     wnd.close_button.emit(tx, Message::Quit);
+    wnd.error_label.emit(tx, Message::ShowErrorDetails);
 
     wnd.wnd.show();
 
@@ -79,8 +110,12 @@ fn main() {
      */
     let mut poller_timeout = Duration::from_secs(2);
     let mut run_poller = false;
+    // Incremented on every start of polling
+    let mut poll_generation: u64 = 0;
     let mut host_address = String::new();
-    let mut manufacturer_id: i32 = 0;
+    let mut parser: Option<Arc<Mutex<Box<dyn ModemInfoParser>>>> = None;
+    let mut last_error: Option<ModemError> = None;
+    let mut last_success: Option<DateTime<Local>> = None;
 
     let mut jh_getinfo: Option<std::thread::JoinHandle<()>> = None;
 
@@ -97,39 +132,115 @@ fn main() {
                         run_poller = !run_poller;
 
                         if run_poller {
-                            manufacturer_id = wnd.model_choice.value();
-
                             host_address = wnd.host_input.input().value();
 
                             let timeout = wnd.get_poll_timeout();
                             poller_timeout = Duration::from_secs(timeout);
 
-                            tx.send(Message::GetInfo);
                             wnd.start_poll();
+
+                            poll_generation += 1;
+                            // Drop stop signal that wasn't received by any poll thread
+                            while info_thread_rx.recv().is_some() {}
+
+                            if let Some(backend) = get_selected_backend(wnd.model_choice.value())
+                            {
+                                // Parser is kept between polls to reuse its session
+                                let options = wnd.get_parser_options();
+                                parser = Some(Arc::new(Mutex::new((backend.create)(&options))));
+
+                                tx.send(Message::GetInfo(poll_generation));
+                            } else {
+                                // Probe host in background, polling starts when modem is detected
+                                println!("Detecting modem at host {host_address}");
+                                wnd.set_status(&format!("Detecting modem at {host_address}..."));
+
+                                let host_address = host_address.clone();
+                                let generation = poll_generation;
+                                thread::spawn(move || {
+                                    let detected = detect_backend(&host_address).map(
+                                        |(backend, device_info)| (backend, Box::new(device_info)),
+                                    );
+                                    tx.send(Message::ModemDetected(generation, detected));
+                                });
+                            }
                         } else {
-                            info_thread_tx.send(());
                             if let Some(jh) = jh_getinfo.take() {
+                                info_thread_tx.send(());
                                 if let Err(err) = jh.join() {
                                     eprintln!("Error: Thread Join: {err:?}");
                                 }
                             }
                             wnd.stop_poll();
+                            parser = None;
+                        }
+                    }
+                    Message::ModemDetected(generation, detected) => {
+                        // Ignore result if polling was stopped or restarted during detection
+                        if !run_poller || parser.is_some() || generation != poll_generation {
+                            continue;
+                        }
+
+                        if let Some((backend, device_info)) = detected {
+                            let (manufacturer, model) = device_info.get_manufacturer_and_model();
+                            println!("Detected {} modem: {manufacturer} {model}", backend.name);
+                            wnd.set_status(&format!("Detected: {manufacturer} {model}"));
+
+                            let options = wnd.get_parser_options();
+                            parser = Some(Arc::new(Mutex::new((backend.create)(&options))));
+
+                            tx.send(Message::GetInfo(poll_generation));
+                        } else {
+                            let e = ModemError::http_connection(&format!(
+                                "No supported modem found at host {host_address}"
+                            ));
+                            wnd.set_error(Some(&e.summary()));
+                            last_error = Some(e);
+
+                            run_poller = false;
+                            wnd.stop_poll();
                         }
                     }
-                    Message::GetInfo => {
+                    Message::ManufacturerChanged => {
+                        if let Some(backend) = get_selected_backend(wnd.model_choice.value()) {
+                            wnd.set_login_fields(
+                                backend.capabilities.username,
+                                backend.capabilities.password,
+                                backend.capabilities.modem_id,
+                            );
+
+                            // Replace address only if user didn't enter a custom one
+                            let host = wnd.host_input.input().value();
+                            if let Some(backend_host) = backend.default_hosts.first()
+                                && default_hosts.contains(&host.as_str())
+                            {
+                                wnd.host_input.set_value(backend_host);
+                            }
+                        } else {
+                            // Login fields of any backend may be used
+                            wnd.set_login_fields(true, true, true);
+                        }
+                    }
+                    Message::GetInfo(generation) => {
+                        if generation != poll_generation {
+                            continue;
+                        }
+                        let Some(parser) = parser.clone() else {
+                            continue;
+                        };
                         let host_address = host_address.clone();
                         println!(
                             "Connecting to modem {} host {}",
-                            MANUFACTURERS[manufacturer_id as usize], host_address
+                            wnd.model_choice.choice().unwrap_or_default(),
+                            host_address
                         );
 
                         jh_getinfo = Some(thread::spawn(move || {
-                            let modem_info = match manufacturer_id {
-                                0 => NetgearParser::get_info(host_address.as_str()),
-                                1 => HuaweiParser::get_info(host_address.as_str()),
-                                _ => {
-                                    eprintln!("Error: Unknown modem manufacturer ID");
-                                    Err(ModemError::Unknown)
+                            let modem_info = match parser.lock() {
+                                Ok(mut parser) => parser.get_info(host_address.as_str()),
+                                Err(err) => {
+                                    eprintln!("Error: Parser Lock: {err:?}");
+                                    Err(ModemError::unknown("Parser is not available"))
                                 }
                             };
 
@@ -178,7 +289,7 @@ fn main() {
 
                             if still_running {
                                 // Run next poll
-                                tx.send(Message::GetInfo);
+                                tx.send(Message::GetInfo(generation));
                             }
                         }));
                     }
@@ -215,13 +326,39 @@ fn main() {
                     }
                     Message::InfoOk => {
                         wnd.set_error(None);
+                        last_error = None;
+                        last_success = Some(Local::now());
+                    }
+                    Message::InfoError(e) => {
+                        // Login with rejected credentials is not repeated to avoid lockout
+                        if e.kind == ModemErrorKind::Authentication && run_poller {
+                            run_poller = false;
+                            if let Some(jh) = jh_getinfo.take() {
+                                info_thread_tx.send(());
+                                if let Err(err) = jh.join() {
+                                    eprintln!("Error: Thread Join: {err:?}");
+                                }
+                            }
+                            wnd.stop_poll();
+                            parser = None;
+                        }
+
+                        wnd.set_error(Some(&e.summary()));
+                        last_error = Some(e);
+                    }
+                    Message::ShowErrorDetails => {
+                        if let Some(e) = &last_error {
+                            let last_success_str = match last_success {
+                                Some(t) => t.format("%Y-%m-%d %H:%M:%S").to_string(),
+                                None => "Never".to_string(),
+                            };
+                            fltk::dialog::message_title("Error Details");
+                            fltk::dialog::message_default(&format!(
+                                "{}\n\nLast successful poll: {last_success_str}",
+                                e.details()
+                            ));
+                        }
                     }
-                    Message::InfoError(e) => match e {
-                        ModemError::HttpConnection => wnd.set_error(Some("HTTP Error")),
-                        ModemError::Access => wnd.set_error(Some("Access Error")),
-                        ModemError::DataParsing => wnd.set_error(Some("Data Parsing Error")),
-                        ModemError::Unknown => wnd.set_error(Some("Unknown error")),
-                    },
                     Message::Quit => {
                         app.quit();
                     }
@@ -232,8 +369,8 @@ fn main() {
 
     // Stop threads
     {
-        info_thread_tx.send(());
         if let Some(jh) = jh_getinfo.take() {
+            info_thread_tx.send(());
             if let Err(err) = jh.join() {
                 eprintln!("Error: Thread Join: {err:?}");
             }
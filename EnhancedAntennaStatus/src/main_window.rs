@@ -2,11 +2,14 @@
 #![allow(clippy::many_single_char_names)]
 #![allow(clippy::similar_names)]
 
-use fltk::{button, enums, frame, group, menu, misc, output, prelude::*, window};
+use fltk::{browser, button, enums, frame, group, input, menu, misc, output, prelude::*, window};
 
 use crate::bandwidth_utils::{TrafficStatistics, format_bandwidth};
 use crate::bar_plot_widget::{BarPlotWidget, COLOR_DL, COLOR_UL, DlUlBarPlotWidget};
-use crate::modem_utils::{LteSignalInfo, ModemStatus, NetworkMode, SignalInfo, WcdmaSignalInfo};
+use crate::modem_utils::{
+    ComponentCarrier, Credentials, LteSignalInfo, ModemStatus, NetworkMode, NrSignalInfo,
+    ParserOptions, SignalInfo, WcdmaSignalInfo,
+};
 use crate::res::IconsAssets;
 use crate::utils::ValueChangeObserver;
 
@@ -23,6 +26,24 @@ const POLL_TIMEOUT_VALUES: [(u64, &str); 7] = [
     (60, "1 min"),
 ];
 
+/*
+ * Table of component carriers
+ */
+const CA_TABLE_COLUMNS: [(i32, &str); 8] = [
+    (32, "CC"),
+    (34, "Band"),
+    (44, "EARFCN"),
+    (34, "BW"),
+    (32, "PCI"),
+    (38, "RSRP"),
+    (34, "RSRQ"),
+    (32, "SINR"),
+];
+
+fn format_optional<T: std::fmt::Display>(val: Option<T>) -> String {
+    val.map_or_else(|| "-".to_string(), |val| val.to_string())
+}
+
 /*
  * Macro for UI
  */
@@ -60,22 +81,33 @@ macro_rules! add_flex_spacer {
  */
 pub struct MainWindow {
     current_pci: ValueChangeObserver<i64>,
+    current_nr_pci: ValueChangeObserver<i64>,
     current_mode: ValueChangeObserver<NetworkMode>,
     current_has_battery: ValueChangeObserver<bool>,
-    current_has_device_temp: ValueChangeObserver<bool>,
+    current_has_device_temp: ValueChangeObserver<(bool, bool)>,
     current_has_model: ValueChangeObserver<bool>,
+    current_has_carriers: ValueChangeObserver<bool>,
+    current_has_channel_info: ValueChangeObserver<bool>,
     pub wnd: window::Window,
     main_group: group::Flex,
     pub model_choice: menu::Choice,
     pub host_input: misc::InputChoice,
     pub connect_button: button::Button,
     timeout_choice: menu::Choice,
+    username_input: input::Input,
+    password_input: input::SecretInput,
+    modem_id_input: input::Input,
+    /// Login fields used by selected backend: (username, password, modem ID)
+    login_fields: (bool, bool, bool),
     pub close_button: button::Button,
     network_mode_label: output::Output,
     rssi_label: output::Output,
     plmn_label: output::Output,
     band_label: output::Output,
     cellid_label: output::Output,
+    earfcn_label: output::Output,
+    bandwidth_label: output::Output,
+    frequency_label: output::Output,
     manufacturer_label: output::Output,
     model_label: output::Output,
     battery_percent_label: output::Output,
@@ -99,19 +131,36 @@ pub struct MainWindow {
     rsrp_plot: BarPlotWidget,
     sinr_label: output::Output,
     sinr_plot: BarPlotWidget,
+    ca_table: browser::Browser,
+    nr_group: group::Flex,
+    nr_gnb_cc_label: output::Output,
+    nr_pci_label: output::Output,
+    nr_band_label: output::Output,
+    nr_arfcn_label: output::Output,
+    nr_rsrq_label: output::Output,
+    nr_rsrq_plot: BarPlotWidget,
+    nr_rsrp_label: output::Output,
+    nr_rsrp_plot: BarPlotWidget,
+    nr_sinr_label: output::Output,
+    nr_sinr_plot: BarPlotWidget,
     dl_label: output::Output,
     ul_label: output::Output,
     dlul_plot: DlUlBarPlotWidget,
-    error_label: frame::Frame,
+    pub error_label: button::Button,
+    /// Status message that is shown in footer when there is no error
+    status: String,
 }
 
 impl MainWindow {
     pub fn new(width: i32, height: i32) -> Self {
         let current_pci = ValueChangeObserver::<i64>::new();
+        let current_nr_pci = ValueChangeObserver::<i64>::new();
         let current_mode = ValueChangeObserver::<NetworkMode>::new();
         let current_has_battery = ValueChangeObserver::<bool>::new();
-        let current_has_device_temp = ValueChangeObserver::<bool>::new();
+        let current_has_device_temp = ValueChangeObserver::<(bool, bool)>::new();
         let current_has_model = ValueChangeObserver::<bool>::new();
+        let current_has_carriers = ValueChangeObserver::<bool>::new();
+        let current_has_channel_info = ValueChangeObserver::<bool>::new();
 
         let mut wnd = window::Window::default()
             .with_size(width, height)
@@ -153,6 +202,33 @@ impl MainWindow {
             (model_choice, host_input, connect_button, timeout_choice)
         };
 
+        let (username_input, password_input, modem_id_input) = {
+            let mut row = group::Flex::default_fill().row();
+
+            add_flex_spacer!(row, 95);
+
+            let mut username_input = input::Input::default().with_label("Username:");
+            username_input.set_value("admin");
+
+            row.fixed(&username_input, 115);
+
+            add_flex_spacer!(row, 115);
+
+            let password_input = input::SecretInput::default().with_label("Password:");
+
+            add_flex_spacer!(row, 95);
+
+            let modem_id_input = input::Input::default().with_label("Modem ID:");
+            row.fixed(&modem_id_input, 75);
+
+            add_flex_spacer!(row, 75);
+
+            row.end();
+            main_group.fixed(&row, 25);
+
+            (username_input, password_input, modem_id_input)
+        };
+
         let info_group_container = group::Flex::default_fill().row();
 
         /*
@@ -221,6 +297,41 @@ impl MainWindow {
             cellid_label
         };
 
+        let (earfcn_label, bandwidth_label) = {
+            let mut row = group::Flex::default_fill().row();
+            row.set_spacing(5);
+
+            add_flex_spacer!(row, 75);
+
+            let mut earfcn_label = output::Output::default().with_label("EARFCN:");
+            set_param_label!(earfcn_label);
+
+            add_flex_spacer!(row, 50);
+
+            let mut bandwidth_label = output::Output::default().with_label("BW:");
+            set_param_label!(bandwidth_label);
+
+            row.end();
+            info_group.fixed(&row, 20);
+
+            (earfcn_label, bandwidth_label)
+        };
+
+        let frequency_label = {
+            let mut row = group::Flex::default_fill().row();
+            row.set_spacing(5);
+
+            add_flex_spacer!(row, 75);
+
+            let mut frequency_label = output::Output::default().with_label("DL/UL Freq:");
+            set_param_label!(frequency_label);
+
+            row.end();
+            info_group.fixed(&row, 20);
+
+            frequency_label
+        };
+
         info_group.end();
 
         /*
@@ -297,7 +408,7 @@ impl MainWindow {
         info_group.end();
 
         info_group_container.end();
-        main_group.fixed(&info_group_container, 105);
+        main_group.fixed(&info_group_container, 145);
 
         let plot_group_container = group::Flex::default_fill().row();
 
@@ -456,8 +567,121 @@ impl MainWindow {
         sinr_plot.set_range(0, 24);
         sinr_plot.set_unit("dB");
 
+        let mut ca_table = browser::Browser::default();
+        ca_table.set_column_char('\t');
+        ca_table.set_column_widths(&CA_TABLE_COLUMNS.map(|c| c.0));
+        ca_table.set_text_size(11);
+        ca_table.set_frame(enums::FrameType::FlatBox);
+        ca_table.set_color(enums::Color::Background);
+        lte_group.fixed(&ca_table, 80);
+
         lte_group.end();
 
+        /*
+         * 5G NR signal status
+         */
+        let mut nr_group = group::Flex::default_fill().column().with_label("5G NR");
+        set_frame_style!(nr_group);
+        nr_group.set_margin(5);
+
+        add_flex_spacer!(nr_group, 10);
+
+        let (nr_gnb_cc_label, nr_pci_label) = {
+            let mut row = group::Flex::default_fill().row();
+            row.set_spacing(5);
+
+            add_flex_spacer!(row, 75);
+
+            let mut nr_gnb_cc_label = output::Output::default().with_label("gNB / Cell:");
+            set_param_label!(nr_gnb_cc_label);
+
+            add_flex_spacer!(row, 75);
+
+            let mut nr_pci_label = output::Output::default().with_label("PCI:");
+            set_param_label!(nr_pci_label);
+
+            row.end();
+            nr_group.fixed(&row, 20);
+
+            (nr_gnb_cc_label, nr_pci_label)
+        };
+
+        let (nr_band_label, nr_arfcn_label) = {
+            let mut row = group::Flex::default_fill().row();
+            row.set_spacing(5);
+
+            add_flex_spacer!(row, 75);
+
+            let mut nr_band_label = output::Output::default().with_label("NR Band:");
+            set_param_label!(nr_band_label);
+
+            add_flex_spacer!(row, 75);
+
+            let mut nr_arfcn_label = output::Output::default().with_label("NR-ARFCN:");
+            set_param_label!(nr_arfcn_label);
+
+            row.end();
+            nr_group.fixed(&row, 20);
+
+            (nr_band_label, nr_arfcn_label)
+        };
+
+        let nr_rsrq_label = {
+            let mut row = group::Flex::default_fill().row();
+
+            add_flex_spacer!(row, 75);
+
+            let mut nr_rsrq_label = output::Output::default().with_label("SS-RSRQ:");
+            set_param_label!(nr_rsrq_label);
+
+            row.end();
+            nr_group.fixed(&row, 15);
+
+            nr_rsrq_label
+        };
+
+        let mut nr_rsrq_plot = BarPlotWidget::new();
+        nr_rsrq_plot.set_range(-16, -3);
+        nr_rsrq_plot.set_unit("dB");
+
+        let nr_rsrp_label = {
+            let mut row = group::Flex::default_fill().row();
+
+            add_flex_spacer!(row, 75);
+
+            let mut nr_rsrp_label = output::Output::default().with_label("SS-RSRP:");
+            set_param_label!(nr_rsrp_label);
+
+            row.end();
+            nr_group.fixed(&row, 15);
+
+            nr_rsrp_label
+        };
+
+        let mut nr_rsrp_plot = BarPlotWidget::new();
+        nr_rsrp_plot.set_range(-130, -60);
+        nr_rsrp_plot.set_unit("dBm");
+
+        let nr_sinr_label = {
+            let mut row = group::Flex::default_fill().row();
+
+            add_flex_spacer!(row, 75);
+
+            let mut nr_sinr_label = output::Output::default().with_label("SS-SINR:");
+            set_param_label!(nr_sinr_label);
+
+            row.end();
+            nr_group.fixed(&row, 15);
+
+            nr_sinr_label
+        };
+
+        let mut nr_sinr_plot = BarPlotWidget::new();
+        nr_sinr_plot.set_range(-5, 30);
+        nr_sinr_plot.set_unit("dB");
+
+        nr_group.end();
+
         /*
          * Bandwidth
          */
@@ -500,12 +724,15 @@ impl MainWindow {
          */
         let mut footer_group = group::Flex::default_fill().row();
 
-        let mut error_label = frame::Frame::default();
+        // Error label is clickable to show details of the last error
+        let mut error_label = button::Button::default();
         error_label.set_label_color(PARAM_COLOR);
         error_label.set_color(enums::Color::Background);
         error_label.set_frame(enums::FrameType::FlatBox);
         error_label.set_label_font(enums::Font::HelveticaBold);
         error_label.set_align(enums::Align::Left | enums::Align::Inside);
+        error_label.set_down_frame(enums::FrameType::FlatBox);
+        error_label.clear_visible_focus();
 
         let close_button = button::Button::default().with_label("Close");
 
@@ -535,25 +762,36 @@ impl MainWindow {
 
         wcdma_group.hide();
         lte_group.hide();
+        nr_group.hide();
 
         Self {
             current_pci,
+            current_nr_pci,
             current_mode,
             current_has_battery,
             current_has_device_temp,
             current_has_model,
+            current_has_carriers,
+            current_has_channel_info,
             wnd,
             main_group,
             model_choice,
             host_input,
             connect_button,
             timeout_choice,
+            username_input,
+            password_input,
+            modem_id_input,
+            login_fields: (true, true, true),
             close_button,
             network_mode_label,
             rssi_label,
             plmn_label,
             band_label,
             cellid_label,
+            earfcn_label,
+            bandwidth_label,
+            frequency_label,
             manufacturer_label,
             model_label,
             battery_percent_label,
@@ -577,10 +815,23 @@ impl MainWindow {
             rsrp_plot,
             sinr_label,
             sinr_plot,
+            ca_table,
+            nr_group,
+            nr_gnb_cc_label,
+            nr_pci_label,
+            nr_band_label,
+            nr_arfcn_label,
+            nr_rsrq_label,
+            nr_rsrq_plot,
+            nr_rsrp_label,
+            nr_rsrp_plot,
+            nr_sinr_label,
+            nr_sinr_plot,
             dl_label,
             ul_label,
             dlul_plot,
             error_label,
+            status: String::new(),
         }
     }
     pub fn set_info(&mut self, info: &ModemStatus) {
@@ -600,8 +851,40 @@ impl MainWindow {
         }
 
         let (cell_id_hex, cell_id) = info.get_cell_id_hex_and_dec();
+        let tac = info
+            .tac
+            .map(|tac| format!(", TAC {tac}"))
+            .unwrap_or_default();
         self.cellid_label
-            .set_value(format!("{cell_id_hex}/{cell_id}").as_str());
+            .set_value(format!("{cell_id_hex}/{cell_id}{tac}").as_str());
+
+        // Channel
+        if self
+            .current_has_channel_info
+            .update_and_check_if_changed(info.channel_info.is_some())
+        {
+            if info.channel_info.is_some() {
+                self.earfcn_label.show();
+                self.bandwidth_label.show();
+                self.frequency_label.show();
+            } else {
+                self.earfcn_label.hide();
+                self.bandwidth_label.hide();
+                self.frequency_label.hide();
+            }
+        }
+        if let Some(channel_info) = info.channel_info {
+            self.earfcn_label.set_value(
+                &channel_info
+                    .earfcn
+                    .map(|e| e.to_string())
+                    .unwrap_or_default(),
+            );
+            self.bandwidth_label
+                .set_value(&info.get_bandwidths().unwrap_or_default());
+            self.frequency_label
+                .set_value(&info.get_frequencies().unwrap_or_default());
+        }
 
         if self.current_mode.update_and_check_if_changed(info.mode) {
             self.set_mode(info.mode);
@@ -610,9 +893,36 @@ impl MainWindow {
         match info.signal_info {
             SignalInfo::Wcdma(wcdma_info) => self.set_wcdma_info(wcdma_info),
             SignalInfo::Lte(lte_info) => self.set_lte_info(lte_info),
+            SignalInfo::Nr(nr_info) => self.set_nr_info(nr_info),
+            SignalInfo::NrNsa(lte_info, nr_info) => {
+                self.set_lte_info(lte_info);
+                self.set_nr_info(nr_info);
+            }
             SignalInfo::None => {}
         }
 
+        // RSRP by antenna
+        if let SignalInfo::Lte(lte_info) | SignalInfo::NrNsa(lte_info, _) = info.signal_info
+            && let Some(antenna_rsrp) = info.get_antenna_rsrp()
+        {
+            self.rsrp_label
+                .set_value(format!("{} dBm ({antenna_rsrp})", lte_info.rsrp).as_str());
+        }
+
+        // Carrier aggregation
+        if self
+            .current_has_carriers
+            .update_and_check_if_changed(!info.carriers.is_empty())
+        {
+            if info.carriers.is_empty() {
+                self.ca_table.hide();
+            } else {
+                self.ca_table.show();
+            }
+            self.lte_group.layout();
+        }
+        self.set_carriers(&info.carriers);
+
         // Modem model
         let (manufacturer, model) = info.device_info.get_manufacturer_and_model();
         if self
@@ -653,23 +963,29 @@ impl MainWindow {
 
         // Temperature
         let device_temp = info.device_temp;
+        let has_battery_temp = device_temp.is_some_and(|t| t.battery_temp.is_some());
         if self
             .current_has_device_temp
-            .update_and_check_if_changed(device_temp.is_some())
+            .update_and_check_if_changed((device_temp.is_some(), has_battery_temp))
         {
             if device_temp.is_some() {
                 self.device_temp_label.show();
-                self.battery_temp_label.show();
             } else {
                 self.device_temp_label.hide();
+            }
+            if has_battery_temp {
+                self.battery_temp_label.show();
+            } else {
                 self.battery_temp_label.hide();
             }
         }
         if let Some(device_temp) = device_temp {
             self.device_temp_label
                 .set_value(format!("{}°C", device_temp.device_temp).as_str());
-            self.battery_temp_label
-                .set_value(format!("{}°C", device_temp.battery_temp).as_str());
+            if let Some(battery_temp) = device_temp.battery_temp {
+                self.battery_temp_label
+                    .set_value(format!("{battery_temp}°C").as_str());
+            }
         }
 
         self.wnd.redraw();
@@ -702,10 +1018,35 @@ impl MainWindow {
         self.rsrq_plot.clear_history();
         self.sinr_plot.clear_history();
 
+        self.ca_table.clear();
+
+        // Clean NR status
+        self.nr_group.hide();
+
+        self.nr_gnb_cc_label.set_value("");
+        self.nr_pci_label.hide();
+        self.nr_band_label.set_value("");
+        self.nr_arfcn_label.set_value("");
+
+        self.nr_rsrp_label.set_value("");
+        self.nr_rsrq_label.set_value("");
+        self.nr_sinr_label.set_value("");
+
+        self.nr_rsrp_plot.clear_history();
+        self.nr_rsrq_plot.clear_history();
+        self.nr_sinr_plot.clear_history();
+
         self.dlul_plot.clear_history();
 
         // Set active mode
         match mode {
+            NetworkMode::NrNsa => {
+                self.lte_group.show();
+                self.nr_group.show();
+            }
+            NetworkMode::Nr => {
+                self.nr_group.show();
+            }
             NetworkMode::Lte => {
                 self.lte_group.show();
             }
@@ -747,9 +1088,9 @@ impl MainWindow {
             .set_value(format!("{}/{}", lte_info.enb, lte_info.id).as_str());
 
         self.rsrp_label
-            .set_value(format!("{} dB", lte_info.rsrp).as_str());
+            .set_value(format!("{} dBm", lte_info.rsrp).as_str());
         self.rsrq_label
-            .set_value(format!("{} dBm", lte_info.rsrq).as_str());
+            .set_value(format!("{} dB", lte_info.rsrq).as_str());
         self.sinr_label
             .set_value(format!("{} dB", lte_info.sinr).as_str());
 
@@ -757,6 +1098,62 @@ impl MainWindow {
         self.rsrq_plot.push_value(lte_info.rsrq);
         self.sinr_plot.push_value(lte_info.sinr);
     }
+    fn set_carriers(&mut self, carriers: &[ComponentCarrier]) {
+        self.ca_table.clear();
+
+        if carriers.is_empty() {
+            return;
+        }
+
+        let header = CA_TABLE_COLUMNS
+            .iter()
+            .map(|c| format!("@b{}", c.1))
+            .collect::<Vec<String>>()
+            .join("\t");
+        self.ca_table.add(&header);
+
+        for cc in carriers {
+            let row = [
+                cc.get_role(),
+                format!("B{}", cc.band),
+                format_optional(cc.earfcn),
+                format_optional(cc.bandwidth),
+                format_optional(cc.pci),
+                format_optional(cc.rsrp),
+                format_optional(cc.rsrq),
+                format_optional(cc.sinr),
+            ];
+            self.ca_table.add(&row.join("\t"));
+        }
+    }
+    fn set_nr_info(&mut self, nr_info: NrSignalInfo) {
+        if self.current_nr_pci.update_and_check_if_changed(nr_info.pci) {
+            if nr_info.pci == -1 {
+                self.nr_pci_label.hide();
+            } else {
+                self.nr_pci_label.show();
+                self.nr_pci_label.set_value(&nr_info.pci.to_string());
+            }
+            self.nr_group.layout();
+        }
+
+        self.nr_gnb_cc_label
+            .set_value(format!("{}/{}", nr_info.gnb, nr_info.id).as_str());
+        self.nr_band_label
+            .set_value(format!("n{}", nr_info.band).as_str());
+        self.nr_arfcn_label.set_value(&nr_info.arfcn.to_string());
+
+        self.nr_rsrp_label
+            .set_value(format!("{} dBm", nr_info.rsrp).as_str());
+        self.nr_rsrq_label
+            .set_value(format!("{} dB", nr_info.rsrq).as_str());
+        self.nr_sinr_label
+            .set_value(format!("{} dB", nr_info.sinr).as_str());
+
+        self.nr_rsrp_plot.push_value(nr_info.rsrp);
+        self.nr_rsrq_plot.push_value(nr_info.rsrq);
+        self.nr_sinr_plot.push_value(nr_info.sinr);
+    }
     pub fn set_bandwidth_data(&mut self, dlul: TrafficStatistics) {
         let dl_str = format_bandwidth(dlul.dl);
         let ul_str = format_bandwidth(dlul.ul);
@@ -767,21 +1164,74 @@ impl MainWindow {
         self.dlul_plot.push_value(dlul);
     }
     pub fn start_poll(&mut self) {
+        self.status.clear();
         self.model_choice.deactivate();
         self.host_input.deactivate();
         self.connect_button.set_label("Stop Poll");
         self.timeout_choice.deactivate();
+        self.username_input.deactivate();
+        self.password_input.deactivate();
+        self.modem_id_input.deactivate();
     }
     pub fn stop_poll(&mut self) {
         self.model_choice.activate();
         self.host_input.activate();
         self.connect_button.set_label("Start Poll");
         self.timeout_choice.activate();
+        self.update_login_fields();
     }
     pub fn set_error(&mut self, s: Option<&str>) {
         match s {
-            Some(s) => self.error_label.set_label(s),
-            None => self.error_label.set_label(""),
+            Some(s) => {
+                self.error_label.set_label(s);
+                self.error_label
+                    .set_tooltip("Click to show details of the error");
+            }
+            None => {
+                self.error_label.set_label(&self.status);
+                self.error_label.set_tooltip("");
+            }
+        }
+    }
+    pub fn set_status(&mut self, s: &str) {
+        self.status = s.to_string();
+        self.set_error(None);
+    }
+    /// Enable only login fields that are used by selected backend
+    pub fn set_login_fields(&mut self, username: bool, password: bool, modem_id: bool) {
+        self.login_fields = (username, password, modem_id);
+        self.update_login_fields();
+    }
+    fn update_login_fields(&mut self) {
+        let (username, password, modem_id) = self.login_fields;
+        if username {
+            self.username_input.activate();
+        } else {
+            self.username_input.deactivate();
+        }
+        if password {
+            self.password_input.activate();
+        } else {
+            self.password_input.deactivate();
+        }
+        if modem_id {
+            self.modem_id_input.activate();
+        } else {
+            self.modem_id_input.deactivate();
+        }
+    }
+    pub fn get_credentials(&self) -> Credentials {
+        Credentials {
+            username: self.username_input.value(),
+            password: self.password_input.value(),
+        }
+    }
+    pub fn get_parser_options(&self) -> ParserOptions {
+        ParserOptions {
+            credentials: self.get_credentials(),
+            modem_id: self.modem_id_input.value().trim().to_string(),
+            mmcli_path: std::env::var("EAS_MMCLI").unwrap_or_default(),
+            netgear_telnet: std::env::var_os("EAS_NETGEAR_TELNET").is_some(),
         }
     }
     pub fn get_poll_timeout(&self) -> u64 {
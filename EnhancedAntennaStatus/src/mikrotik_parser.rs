@@ -0,0 +1,443 @@
+#![allow(clippy::similar_names)]
+
+use std::str::FromStr;
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde_json::json;
+
+use crate::bandwidth_utils::TrafficMode;
+use crate::earfcn_utils::get_lte_frequencies;
+use crate::modem_utils::{
+    CarrierRole, ChannelInfo, ComponentCarrier, DeviceInformation, LteSignalInfo, ModemError,
+    ModemInfoParser, ModemStatus, NetworkMode, NrSignalInfo, ParserOptions, PlmnStatus, SignalInfo,
+    WcdmaSignalInfo,
+};
+use crate::network_utils::{get_url_json_with_agent, new_session_agent, post_url_json_with_agent};
+use crate::utils::{copy_string_to_array, parse_band_number, parse_bandwidth_mhz};
+
+const LTE_MONITOR_QUERY: &str = "/rest/interface/lte/monitor";
+const SYSTEM_RESOURCE_QUERY: &str = "/rest/system/resource";
+
+const DEFAULT_USERNAME: &str = "admin";
+const DEFAULT_INTERFACE: &str = "lte1";
+
+/// Parse numeric parameter. REST API reports all values as strings
+fn get_mikrotik_i64(val: &serde_json::Value) -> Option<i64> {
+    val.as_i64().or_else(|| {
+        val.as_str()
+            .and_then(|val| val.split_whitespace().next())
+            .and_then(|val| val.parse::<f64>().ok())
+            .map(|val| val.round() as i64)
+    })
+}
+
+/// Get required numeric field of LTE monitor
+fn required_mikrotik_i64(json: &serde_json::Value, name: &str) -> Result<i64, ModemError> {
+    get_mikrotik_i64(&json[name]).ok_or_else(|| {
+        eprintln!("LTE monitor doesn't have required field '{name}'");
+        ModemError::data_parsing(name).with_endpoint(LTE_MONITOR_QUERY)
+    })
+}
+
+/// Convert 'access-technology' (e.g. 'Evolved 3G (LTE)', '5G NSA', '3G HSPA+') to
+/// `NetworkMode` enum
+fn get_mode_by_access_technology(access_technology: &str) -> NetworkMode {
+    let access_technology = access_technology.to_uppercase();
+    if access_technology.contains("NSA") {
+        NetworkMode::NrNsa
+    } else if access_technology.contains("5G") || access_technology.contains("NR") {
+        NetworkMode::Nr
+    } else if access_technology.contains("LTE") {
+        NetworkMode::Lte
+    } else if ["3G", "WCDMA", "UMTS", "HSPA"]
+        .iter()
+        .any(|val| access_technology.contains(val))
+    {
+        NetworkMode::Wcdma
+    } else if ["GSM", "EDGE", "GPRS"]
+        .iter()
+        .any(|val| access_technology.contains(val))
+    {
+        NetworkMode::Gsm
+    } else {
+        NetworkMode::Unknown
+    }
+}
+
+/// Parse band description in format 'B3@20Mhz earfcn: 1300 phy-cellid: 123'
+fn parse_band_description(s: &str, role: CarrierRole) -> Option<ComponentCarrier> {
+    let mut tokens = s.split_whitespace();
+    let (band_str, bandwidth_str) = tokens.next()?.split_once('@')?;
+
+    let mut earfcn = None;
+    let mut pci = None;
+    while let Some(token) = tokens.next() {
+        match token {
+            "earfcn:" => earfcn = tokens.next().and_then(|val| val.parse::<i64>().ok()),
+            "phy-cellid:" => pci = tokens.next().and_then(|val| val.parse::<i64>().ok()),
+            _ => {}
+        }
+    }
+
+    Some(ComponentCarrier {
+        role,
+        band: parse_band_number(band_str)?,
+        earfcn,
+        bandwidth: parse_bandwidth_mhz(bandwidth_str),
+        pci,
+        rsrp: None,
+        rsrq: None,
+        sinr: None,
+    })
+}
+
+/*
+ * Utils for MikroTik
+ */
+
+pub struct MikrotikParser {
+    options: ParserOptions,
+    agent: ureq::Agent,
+}
+
+impl MikrotikParser {
+    pub fn new(options: &ParserOptions) -> Self {
+        Self {
+            options: options.clone(),
+            agent: new_session_agent(),
+        }
+    }
+
+    fn get_authorization(&self) -> String {
+        let credentials = &self.options.credentials;
+        let username = if credentials.username.is_empty() {
+            DEFAULT_USERNAME
+        } else {
+            &credentials.username
+        };
+        let auth = BASE64.encode(format!("{username}:{}", credentials.password));
+        format!("Basic {auth}")
+    }
+
+    /// Check if host has REST API of RouterOS. Request without authorization is rejected
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let agent = new_session_agent();
+        match get_url_json_with_agent(&agent, host, SYSTEM_RESOURCE_QUERY) {
+            Ok(json) => json["board-name"]
+                .as_str()
+                .map(|board_name| DeviceInformation::from("MikroTik", board_name)),
+            Err(e) if e.http_status == Some(401) => Some(DeviceInformation::from("MikroTik", "")),
+            Err(_) => None,
+        }
+    }
+
+    /// Parse carriers from 'primary-band' and 'ca-band'. Several CA bands are reported
+    /// either as array or as comma-separated string
+    fn parse_carriers_json(json: &serde_json::Value) -> Vec<ComponentCarrier> {
+        let Some(primary_carrier) = json["primary-band"]
+            .as_str()
+            .and_then(|val| parse_band_description(val, CarrierRole::Primary))
+        else {
+            return Vec::new();
+        };
+
+        let ca_bands: Vec<&str> = match &json["ca-band"] {
+            serde_json::Value::Array(ca_bands) => ca_bands
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .collect(),
+            serde_json::Value::String(ca_bands) => ca_bands.split(',').collect(),
+            _ => Vec::new(),
+        };
+
+        let mut carriers = vec![primary_carrier];
+        carriers.extend(
+            ca_bands
+                .iter()
+                .filter_map(|val| parse_band_description(val, CarrierRole::Secondary)),
+        );
+        carriers
+    }
+
+    fn parse_monitor_json(json: &serde_json::Value) -> Result<ModemStatus, ModemError> {
+        let Some(access_technology) = json["access-technology"].as_str() else {
+            eprintln!("LTE monitor doesn't have required field 'access-technology'");
+            return Err(
+                ModemError::data_parsing("access-technology").with_endpoint(LTE_MONITOR_QUERY)
+            );
+        };
+        let mode = get_mode_by_access_technology(access_technology);
+
+        let rssi = get_mikrotik_i64(&json["rssi"]).unwrap_or(0);
+
+        // Operator is numeric PLMN or name depending on firmware
+        let plmn_str = match (json["mcc"].as_str(), json["mnc"].as_str()) {
+            (Some(mcc), Some(mnc)) => format!("{mcc}{mnc}"),
+            _ => json["current-operator"]
+                .as_str()
+                .filter(|val| val.chars().all(|c| c.is_ascii_digit()))
+                .unwrap_or_default()
+                .to_string(),
+        };
+        let plmn = PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string");
+
+        let cell_id = get_mikrotik_i64(&json["current-cellid"]).unwrap_or(0);
+        let pci = get_mikrotik_i64(&json["phy-cellid"]).unwrap_or(-1);
+
+        let mut carriers = MikrotikParser::parse_carriers_json(json);
+        let primary_carrier = carriers.first().copied();
+
+        let mut band = ['\0'; 20];
+        if let Some(primary_carrier) = primary_carrier {
+            copy_string_to_array!(band, format!("LTE B{}", primary_carrier.band));
+        }
+
+        let get_lte_signal_info = || -> Result<LteSignalInfo, ModemError> {
+            Ok(LteSignalInfo {
+                rsrq: required_mikrotik_i64(json, "rsrq")?,
+                rsrp: required_mikrotik_i64(json, "rsrp")?,
+                sinr: required_mikrotik_i64(json, "sinr")?,
+                ca_count: i64::try_from(carriers.len().saturating_sub(1)).unwrap_or(0),
+                enb: get_mikrotik_i64(&json["enb-id"]).unwrap_or(cell_id >> 8),
+                id: get_mikrotik_i64(&json["sector-id"]).unwrap_or(cell_id & 0xFF),
+                pci,
+            })
+        };
+        let get_nr_signal_info = || -> Result<NrSignalInfo, ModemError> {
+            Ok(NrSignalInfo {
+                rsrq: required_mikrotik_i64(json, "nr-rsrq")?,
+                rsrp: required_mikrotik_i64(json, "nr-rsrp")?,
+                sinr: required_mikrotik_i64(json, "nr-sinr")?,
+                band: json["nr-band"]
+                    .as_str()
+                    .and_then(parse_band_number)
+                    .unwrap_or(0),
+                arfcn: get_mikrotik_i64(&json["nr-arfcn"]).unwrap_or(0),
+                gnb: 0,
+                id: 0,
+                pci: get_mikrotik_i64(&json["nr-phy-cellid"]).unwrap_or(-1),
+            })
+        };
+
+        let signal_info = match mode {
+            NetworkMode::Nr => {
+                let (gnb, id) = NrSignalInfo::split_nci(cell_id);
+                SignalInfo::Nr(NrSignalInfo {
+                    gnb,
+                    id,
+                    ..get_nr_signal_info()?
+                })
+            }
+            NetworkMode::NrNsa => SignalInfo::NrNsa(get_lte_signal_info()?, get_nr_signal_info()?),
+            NetworkMode::Lte => SignalInfo::Lte(get_lte_signal_info()?),
+            NetworkMode::Wcdma => {
+                let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
+                let (nb, cc) = (id / 10, id % 10);
+                SignalInfo::Wcdma(WcdmaSignalInfo {
+                    rscp: required_mikrotik_i64(json, "rscp")?,
+                    ecio: required_mikrotik_i64(json, "ecno")?,
+                    nb,
+                    cc,
+                    rnc,
+                    psc: get_mikrotik_i64(&json["psc"]).unwrap_or(0),
+                })
+            }
+            _ => SignalInfo::None,
+        };
+
+        let channel_info = primary_carrier
+            .filter(|_| mode == NetworkMode::Lte || mode == NetworkMode::NrNsa)
+            .and_then(|carrier| carrier.earfcn.map(|earfcn| (earfcn, carrier.bandwidth)))
+            .map(|(earfcn, bandwidth)| {
+                let (dl_frequency, ul_frequency) = match get_lte_frequencies(earfcn) {
+                    Some((dl, ul)) => (Some(dl), ul),
+                    None => (None, None),
+                };
+                ChannelInfo {
+                    earfcn: Some(earfcn),
+                    dl_frequency,
+                    ul_frequency,
+                    dl_bandwidth: bandwidth,
+                    ul_bandwidth: None,
+                }
+            });
+
+        // Details of carriers are shown only for carrier aggregation
+        if carriers.len() < 2 {
+            carriers.clear();
+        }
+
+        let manufacturer_str = json["manufacturer"].as_str().unwrap_or("MikroTik");
+        let model_str = json["model"].as_str().unwrap_or_default();
+
+        Ok(ModemStatus {
+            mode,
+            plmn,
+            rssi,
+            cell_id,
+            signal_info,
+            band,
+            carriers,
+            channel_info,
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_info: DeviceInformation::from(manufacturer_str, model_str),
+            battery_status: None,
+            device_temp: None,
+            traffic_statistics: None,
+            traffic_mode: TrafficMode::Absolute,
+        })
+    }
+}
+
+impl ModemInfoParser for MikrotikParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        let interface = if self.options.modem_id.is_empty() {
+            DEFAULT_INTERFACE
+        } else {
+            &self.options.modem_id
+        };
+
+        // Single measurement instead of continuous monitoring
+        let response = post_url_json_with_agent(
+            &self.agent,
+            host,
+            LTE_MONITOR_QUERY,
+            &[("Authorization", &self.get_authorization())],
+            &json!({"numbers": interface, "once": ""}),
+        )
+        .map_err(|e| {
+            e.context(&format!(
+                "Cannot get LTE monitor of {interface} from host {host}"
+            ))
+        })?;
+
+        // Result is array with item for each interface
+        let monitor = match &response {
+            serde_json::Value::Array(items) => items.first().unwrap_or(&serde_json::Value::Null),
+            json => json,
+        };
+
+        MikrotikParser::parse_monitor_json(monitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_utils::ModemErrorKind;
+
+    /// Item of `/rest/interface/lte/monitor` response of Chateau LTE12 with LTE-A
+    const LTE_MONITOR_JSON: &str = r#"{
+        "pin-status": "no password required", "registration-status": "registered",
+        "functionality": "full", "manufacturer": "MikroTik", "model": "R11e-LTE6",
+        "current-operator": "26203", "access-technology": "Evolved 3G (LTE)",
+        "session-uptime": "2h17m4s", "imei": "860000000000000", "imsi": "262030000000000",
+        "uicc": "8949030000000000000", "subscriber-number": "",
+        "earfcn": "1300 (band 3, bandwidth 20Mhz, cqi 11)",
+        "primary-band": "B3@20Mhz earfcn: 1300 phy-cellid: 187",
+        "ca-band": [
+            "B20@10Mhz earfcn: 6300 phy-cellid: 293",
+            "B1@15Mhz earfcn: 100 phy-cellid: 41"
+        ],
+        "enb-id": "107173", "sector-id": "17", "phy-cellid": "187", "cqi": "11",
+        "rssi": "-69", "rsrp": "-97", "rsrq": "-11", "sinr": "12", "current-cellid": "27436305"
+    }"#;
+
+    /// Item of the response of Chateau 5G in NSA mode
+    const NSA_MONITOR_JSON: &str = r#"{
+        "model": "Chateau 5G", "access-technology": "5G NSA", "mcc": "262", "mnc": "01",
+        "primary-band": "B3@20Mhz earfcn: 1300 phy-cellid: 187",
+        "rssi": "-61", "rsrp": "-88", "rsrq": "-9", "sinr": "15", "current-cellid": "27436305",
+        "nr-band": "n78", "nr-arfcn": "636666", "nr-phy-cellid": "500",
+        "nr-rsrp": "-92", "nr-rsrq": "-11", "nr-sinr": "8"
+    }"#;
+
+    fn parse_json(json: &str) -> Result<ModemStatus, ModemError> {
+        MikrotikParser::parse_monitor_json(&serde_json::from_str(json).unwrap())
+    }
+
+    #[test]
+    fn band_description() {
+        let carrier = parse_band_description(
+            "B3@20Mhz earfcn: 1300 phy-cellid: 187",
+            CarrierRole::Primary,
+        )
+        .unwrap();
+        assert_eq!(carrier.band, 3);
+        assert_eq!(carrier.bandwidth, Some(20.0));
+        assert_eq!(carrier.earfcn, Some(1300));
+        assert_eq!(carrier.pci, Some(187));
+
+        assert!(parse_band_description("B3", CarrierRole::Primary).is_none());
+    }
+
+    #[test]
+    fn lte_monitor() {
+        let modem_status = parse_json(LTE_MONITOR_JSON).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE-A");
+        assert_eq!(modem_status.get_plmn().trim_end_matches('\0'), "26203");
+        assert_eq!(modem_status.get_band(), "LTE B3+B20+B1");
+        assert_eq!(modem_status.rssi, -69);
+        assert_eq!(
+            modem_status.get_frequencies().as_deref(),
+            Some("1815.0/1720.0 MHz")
+        );
+        assert_eq!(modem_status.get_bandwidths().as_deref(), Some("20 MHz"));
+
+        let SignalInfo::Lte(lte_info) = modem_status.signal_info else {
+            panic!("Signal info is not LTE");
+        };
+        assert_eq!(lte_info.rsrp, -97);
+        assert_eq!(lte_info.rsrq, -11);
+        assert_eq!(lte_info.sinr, 12);
+        assert_eq!(lte_info.enb, 107_173);
+        assert_eq!(lte_info.id, 17);
+        assert_eq!(lte_info.pci, 187);
+        assert_eq!(lte_info.ca_count, 2);
+
+        let (manufacturer, model) = modem_status.device_info.get_manufacturer_and_model();
+        assert_eq!(manufacturer, "MikroTik");
+        assert_eq!(model, "R11e-LTE6");
+    }
+
+    #[test]
+    fn ca_bands_string() {
+        let json = serde_json::json!({
+            "primary-band": "B3@20Mhz earfcn: 1300 phy-cellid: 187",
+            "ca-band": "B20@10Mhz earfcn: 6300 phy-cellid: 293,B1@15Mhz earfcn: 100 phy-cellid: 41"
+        });
+        let carriers = MikrotikParser::parse_carriers_json(&json);
+        assert_eq!(carriers.len(), 3);
+        assert!(carriers[2].role == CarrierRole::Secondary);
+        assert_eq!(carriers[2].band, 1);
+        assert_eq!(carriers[2].pci, Some(41));
+    }
+
+    #[test]
+    fn nsa_monitor() {
+        let modem_status = parse_json(NSA_MONITOR_JSON).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE+NR");
+        assert_eq!(modem_status.get_plmn().trim_end_matches('\0'), "26201");
+
+        let SignalInfo::NrNsa(lte_info, nr_info) = modem_status.signal_info else {
+            panic!("Signal info is not NSA");
+        };
+        assert_eq!(lte_info.rsrp, -88);
+        assert_eq!(nr_info.rsrp, -92);
+        assert_eq!(nr_info.band, 78);
+        assert_eq!(nr_info.arfcn, 636_666);
+        assert_eq!(nr_info.pci, 500);
+    }
+
+    #[test]
+    fn missing_signal_value() {
+        let json = NSA_MONITOR_JSON.replace(r#""nr-rsrq": "-11", "#, "");
+        let Err(e) = parse_json(&json) else {
+            panic!("Missing NR RSRQ is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+}
@@ -6,6 +6,8 @@ use crate::utils::copy_string_to_array;
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum NetworkMode {
+    NrNsa = 101,
+    Nr = 11,
     Lte = 7,
     Wcdma = 2,
     Gsm = 0,
@@ -23,6 +25,55 @@ pub struct LteSignalInfo {
     pub pci: i64,
 }
 
+/// Role of component carrier in LTE carrier aggregation
+#[derive(Copy, Clone, PartialEq)]
+pub enum CarrierRole {
+    Primary,
+    Secondary,
+}
+
+/// Component carrier of LTE-A. Parameters that are not reported by the modem are `None`
+#[derive(Copy, Clone)]
+pub struct ComponentCarrier {
+    pub role: CarrierRole,
+    pub band: i64,
+    pub earfcn: Option<i64>,
+    pub bandwidth: Option<f64>,
+    pub pci: Option<i64>,
+    pub rsrp: Option<i64>,
+    pub rsrq: Option<i64>,
+    pub sinr: Option<i64>,
+}
+
+impl ComponentCarrier {
+    pub fn get_role(&self) -> String {
+        match self.role {
+            CarrierRole::Primary => "PCC".to_string(),
+            CarrierRole::Secondary => "SCC".to_string(),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct NrSignalInfo {
+    pub rsrq: i64,
+    pub rsrp: i64,
+    pub sinr: i64,
+    pub band: i64,
+    pub arfcn: i64,
+    pub gnb: i64,
+    pub id: i64,
+    pub pci: i64,
+}
+
+impl NrSignalInfo {
+    /// Split NR Cell Identity into gNB ID and cell ID.
+    /// NCI is 36 bits long, the gNB ID length of 24 bits is assumed
+    pub fn split_nci(nci: i64) -> (i64, i64) {
+        (nci >> 12, nci & 0xFFF)
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct WcdmaSignalInfo {
     pub rscp: i64,
@@ -35,11 +86,29 @@ pub struct WcdmaSignalInfo {
 
 #[derive(Copy, Clone)]
 pub enum SignalInfo {
+    /// 5G standalone
+    Nr(NrSignalInfo),
+    /// 5G non-standalone (EN-DC): LTE anchor and NR leg
+    NrNsa(LteSignalInfo, NrSignalInfo),
     Lte(LteSignalInfo),
     Wcdma(WcdmaSignalInfo),
     None,
 }
 
+/// Radio channel of the serving cell. Parameters that are not reported by the modem are `None`
+#[derive(Copy, Clone)]
+pub struct ChannelInfo {
+    pub earfcn: Option<i64>,
+    /// Downlink frequency in MHz
+    pub dl_frequency: Option<f64>,
+    /// Uplink frequency in MHz
+    pub ul_frequency: Option<f64>,
+    /// Downlink bandwidth in MHz
+    pub dl_bandwidth: Option<f64>,
+    /// Uplink bandwidth in MHz
+    pub ul_bandwidth: Option<f64>,
+}
+
 #[derive(Copy, Clone)]
 pub struct PlmnStatus {
     pub plmn: [char; 6],
@@ -82,7 +151,7 @@ impl BatteryStatus {
 #[derive(Copy, Clone)]
 pub struct DeviceTemperature {
     pub device_temp: i64,
-    pub battery_temp: i64,
+    pub battery_temp: Option<i64>,
 }
 
 #[derive(Copy, Clone)]
@@ -120,7 +189,7 @@ impl DeviceInformation {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct ModemStatus {
     pub mode: NetworkMode,
     pub plmn: PlmnStatus,
@@ -128,6 +197,12 @@ pub struct ModemStatus {
     pub cell_id: i64,
     pub signal_info: SignalInfo,
     pub band: [char; 20],
+    pub carriers: Vec<ComponentCarrier>,
+    pub channel_info: Option<ChannelInfo>,
+    /// Tracking area code of the serving cell
+    pub tac: Option<i64>,
+    /// RSRP of the serving cell by each receive chain (main, diversity, ...)
+    pub antenna_rsrp: Vec<i64>,
 
     pub device_info: DeviceInformation,
     pub battery_status: Option<BatteryStatus>,
@@ -138,14 +213,17 @@ pub struct ModemStatus {
 
 impl ModemStatus {
     pub fn get_ca_count(&self) -> i64 {
-        if let SignalInfo::Lte(lte_info) = self.signal_info {
-            lte_info.ca_count
-        } else {
-            0
+        match self.signal_info {
+            SignalInfo::Lte(lte_info) | SignalInfo::NrNsa(lte_info, _) => lte_info.ca_count,
+            _ => 0,
         }
     }
     pub fn get_mode(&self) -> String {
         match self.mode {
+            NetworkMode::NrNsa => {
+                format!("LTE{}+NR", if self.get_ca_count() > 0 { "-A" } else { "" })
+            }
+            NetworkMode::Nr => "NR SA".to_string(),
             NetworkMode::Lte => {
                 format!("LTE{}", if self.get_ca_count() > 0 { "-A" } else { "" })
             }
@@ -159,13 +237,21 @@ impl ModemStatus {
     }
     pub fn get_band(&self) -> String {
         let ca_count = self.get_ca_count();
+        let scc_bands = self
+            .carriers
+            .iter()
+            .filter(|cc| cc.role == CarrierRole::Secondary && cc.band > 0)
+            .map(|cc| format!("+B{}", cc.band))
+            .collect::<String>();
         let band = format!(
             "{}{}",
             self.band
                 .iter()
                 .collect::<String>()
                 .trim_matches(char::from(0)),
-            if ca_count > 0 {
+            if !scc_bands.is_empty() {
+                scc_bands
+            } else if ca_count > 0 {
                 format!("+{ca_count}CA")
             } else {
                 String::new()
@@ -173,6 +259,34 @@ impl ModemStatus {
         );
         band
     }
+    pub fn get_frequencies(&self) -> Option<String> {
+        let channel_info = self.channel_info?;
+        match (channel_info.dl_frequency, channel_info.ul_frequency) {
+            (Some(dl), Some(ul)) => Some(format!("{dl:.1}/{ul:.1} MHz")),
+            (Some(dl), None) => Some(format!("{dl:.1} MHz")),
+            _ => None,
+        }
+    }
+    pub fn get_bandwidths(&self) -> Option<String> {
+        let channel_info = self.channel_info?;
+        match (channel_info.dl_bandwidth, channel_info.ul_bandwidth) {
+            (Some(dl), Some(ul)) => Some(format!("{dl}/{ul} MHz")),
+            (Some(dl), None) => Some(format!("{dl} MHz")),
+            _ => None,
+        }
+    }
+    pub fn get_antenna_rsrp(&self) -> Option<String> {
+        if self.antenna_rsrp.is_empty() {
+            return None;
+        }
+        Some(
+            self.antenna_rsrp
+                .iter()
+                .map(std::string::ToString::to_string)
+                .collect::<Vec<String>>()
+                .join("/"),
+        )
+    }
     pub fn get_cell_id_hex_and_dec(&self) -> (String, String) {
         let cell_id = self.cell_id.to_string();
         let cell_id_hex = format!("{:X}", self.cell_id);
@@ -207,34 +321,248 @@ impl fmt::Display for ModemStatus {
                     lte_info.rsrq, lte_info.rsrp, lte_info.sinr
                 )
             }
+            SignalInfo::Nr(nr_info) => {
+                format!(
+                    "\nSS-RSRQ/SS-RSRP/SS-SINR : {}dB/{}dBm/{}dB\nNR Band : n{} NR-ARFCN : {}",
+                    nr_info.rsrq, nr_info.rsrp, nr_info.sinr, nr_info.band, nr_info.arfcn
+                )
+            }
+            SignalInfo::NrNsa(lte_info, nr_info) => {
+                format!(
+                    "\nRSRQ/RSRP/SINR : {}dB/{}dBm/{}dB\nSS-RSRQ/SS-RSRP/SS-SINR : {}dB/{}dBm/{}dB\nNR Band : n{} NR-ARFCN : {}",
+                    lte_info.rsrq,
+                    lte_info.rsrp,
+                    lte_info.sinr,
+                    nr_info.rsrq,
+                    nr_info.rsrp,
+                    nr_info.sinr,
+                    nr_info.band,
+                    nr_info.arfcn
+                )
+            }
             SignalInfo::None => String::new(),
         };
 
+        let channel_info = format!(
+            "{}{}{}{}{}",
+            self.channel_info
+                .and_then(|channel_info| channel_info.earfcn)
+                .map(|earfcn| format!("\nEARFCN : {earfcn}"))
+                .unwrap_or_default(),
+            self.get_frequencies()
+                .map(|frequencies| format!("\nFrequency DL/UL : {frequencies}"))
+                .unwrap_or_default(),
+            self.get_bandwidths()
+                .map(|bandwidths| format!("\nBandwidth DL/UL : {bandwidths}"))
+                .unwrap_or_default(),
+            self.tac
+                .map(|tac| format!("\nTAC : {tac}"))
+                .unwrap_or_default(),
+            self.get_antenna_rsrp()
+                .map(|rsrp| format!("\nRSRP by antenna : {rsrp} dBm"))
+                .unwrap_or_default(),
+        );
+
         write!(
             f,
-            "Network mode : {}\nRSSI : {} dBm\nPLMN : {}\nBand : {}\nCell ID : {} / {}{}",
-            mode, self.rssi, plmn, band, cell_id_hex, cell_id, mode_info
+            "Network mode : {}\nRSSI : {} dBm\nPLMN : {}\nBand : {}{}\nCell ID : {} / {}{}",
+            mode, self.rssi, plmn, band, channel_info, cell_id_hex, cell_id, mode_info
         )
     }
 }
 
-/// Modem Error
-#[derive(Clone, Copy, Debug)]
-pub enum ModemError {
+/// Kind of modem error
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModemErrorKind {
     /// Low-level HTTP connection error
     HttpConnection,
+    /// Connection error of serial port or TCP socket with AT commands
+    PortConnection,
     /// Resource access error
     Access,
+    /// Credentials are rejected. Polling stops because repeated login locks the account
+    Authentication,
     /// Parsing of data error
     DataParsing,
     /// All other errors
     Unknown,
 }
 
+impl Display for ModemErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModemErrorKind::HttpConnection => write!(f, "HTTP Error"),
+            ModemErrorKind::PortConnection => write!(f, "Port Error"),
+            ModemErrorKind::Access => write!(f, "Access Error"),
+            ModemErrorKind::Authentication => write!(f, "Authentication Error"),
+            ModemErrorKind::DataParsing => write!(f, "Data Parsing Error"),
+            ModemErrorKind::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+/// Error code and message reported by the modem itself
+#[derive(Clone, Debug)]
+pub struct VendorError {
+    pub code: Option<String>,
+    pub message: Option<String>,
+    /// Human readable explanation of known codes
+    pub explanation: Option<String>,
+}
+
+/// Modem Error with context
+#[derive(Clone, Debug)]
+pub struct ModemError {
+    pub kind: ModemErrorKind,
+    /// Endpoint of the failed request
+    pub endpoint: Option<String>,
+    /// HTTP status of the failed request
+    pub http_status: Option<u16>,
+    /// Error reported by the modem firmware
+    pub vendor_error: Option<Box<VendorError>>,
+    /// Chain of error messages from the outermost context to the root cause
+    pub chain: Vec<String>,
+}
+
+impl ModemError {
+    pub fn new(kind: ModemErrorKind, message: &str) -> Self {
+        Self {
+            kind,
+            endpoint: None,
+            http_status: None,
+            vendor_error: None,
+            chain: vec![message.to_string()],
+        }
+    }
+    pub fn http_connection(message: &str) -> Self {
+        Self::new(ModemErrorKind::HttpConnection, message)
+    }
+    pub fn port_connection(message: &str) -> Self {
+        Self::new(ModemErrorKind::PortConnection, message)
+    }
+    pub fn access(message: &str) -> Self {
+        Self::new(ModemErrorKind::Access, message)
+    }
+    /// Error of rejected credentials that stops polling
+    pub fn authentication(message: &str) -> Self {
+        Self::new(ModemErrorKind::Authentication, message)
+    }
+    /// Error of parsing with name of the missing or invalid field
+    pub fn data_parsing(field: &str) -> Self {
+        Self::new(
+            ModemErrorKind::DataParsing,
+            &format!("Cannot parse '{field}'"),
+        )
+    }
+    pub fn unknown(message: &str) -> Self {
+        Self::new(ModemErrorKind::Unknown, message)
+    }
+    #[must_use]
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.to_string());
+        self
+    }
+    #[must_use]
+    pub fn with_http_status(mut self, http_status: u16) -> Self {
+        self.http_status = Some(http_status);
+        self
+    }
+    #[must_use]
+    pub fn with_vendor_error(mut self, vendor_error: VendorError) -> Self {
+        self.vendor_error = Some(Box::new(vendor_error));
+        self
+    }
+    /// Add message of the outer context
+    #[must_use]
+    pub fn context(mut self, message: &str) -> Self {
+        self.chain.insert(0, message.to_string());
+        self
+    }
+    /// Short description for status line
+    pub fn summary(&self) -> String {
+        let vendor_error = self
+            .vendor_error
+            .as_ref()
+            .and_then(|vendor_error| {
+                vendor_error.explanation.clone().or_else(|| {
+                    vendor_error
+                        .code
+                        .as_ref()
+                        .map(|code| format!("code {code}"))
+                })
+            })
+            .map(|s| format!(": {s}"));
+        let root_cause = self.chain.last().map(|s| format!(": {s}"));
+
+        format!(
+            "{}{}",
+            self.kind,
+            vendor_error.or(root_cause).unwrap_or_default()
+        )
+    }
+    /// Full description with all context
+    pub fn details(&self) -> String {
+        let mut lines = vec![self.kind.to_string()];
+
+        if let Some(endpoint) = &self.endpoint {
+            lines.push(format!("Endpoint: {endpoint}"));
+        }
+        if let Some(http_status) = self.http_status {
+            lines.push(format!("HTTP status: {http_status}"));
+        }
+        if let Some(vendor_error) = &self.vendor_error {
+            if let Some(code) = &vendor_error.code {
+                lines.push(format!("Modem error code: {code}"));
+            }
+            if let Some(message) = &vendor_error.message {
+                lines.push(format!("Modem error message: {message}"));
+            }
+            if let Some(explanation) = &vendor_error.explanation {
+                lines.push(format!("Explanation: {explanation}"));
+            }
+        }
+        for (i, message) in self.chain.iter().enumerate() {
+            if i == 0 {
+                lines.push(message.clone());
+            } else {
+                lines.push(format!("Caused by: {message}"));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl Display for ModemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.summary())
+    }
+}
+
+/// Credentials for web UI of the modem. Empty password means that login is not performed
+#[derive(Clone, Default)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Settings of the connection that are passed to new parser
+#[derive(Clone, Default)]
+pub struct ParserOptions {
+    pub credentials: Credentials,
+    /// Modem of multi-modem router or name of LTE interface. Empty ID means the default one
+    pub modem_id: String,
+    /// Executable of mmcli for ModemManager. Empty path means 'mmcli' from PATH
+    pub mmcli_path: String,
+    /// Poll AT port of Netgear over telnet for additional info
+    pub netgear_telnet: bool,
+}
+
 /*
  * General trait for getting modem info by hostname
  */
 
-pub trait ModemInfoParser {
-    fn get_info(host: &str) -> Result<ModemStatus, ModemError>;
+/// Parser of modem info. Instance is kept between polls so it can hold session state
+pub trait ModemInfoParser: Send {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError>;
 }
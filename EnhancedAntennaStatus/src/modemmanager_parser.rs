@@ -0,0 +1,331 @@
+#![allow(clippy::similar_names)]
+
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::bandwidth_utils::TrafficMode;
+use crate::modem_utils::{
+    DeviceInformation, LteSignalInfo, ModemError, ModemInfoParser, ModemStatus, NetworkMode,
+    NrSignalInfo, ParserOptions, PlmnStatus, SignalInfo, VendorError, WcdmaSignalInfo,
+};
+use crate::utils::{get_json_value, json_str_as_type};
+
+/// Refresh rate of extended signal information in seconds
+const SIGNAL_REFRESH_RATE: i64 = 5;
+
+const DEFAULT_MMCLI_PATH: &str = "mmcli";
+
+const SIGNAL_QUERY: &str = "--signal-get";
+
+/// Get signal parameter reported as string with decimals (e.g. '-95.00'). Missing values are '--'
+fn get_mm_i64(val: &serde_json::Value) -> Option<i64> {
+    json_str_as_type::<f64>(val).map(|val| val.round() as i64)
+}
+
+/// Get required signal parameter. Values are '--' until signal refresh takes effect
+fn required_mm_i64(json: &serde_json::Value, name: &str) -> Result<i64, ModemError> {
+    get_mm_i64(&json[name]).ok_or_else(|| {
+        eprintln!("Signal info doesn't have value of '{name}'");
+        ModemError::data_parsing(name).with_endpoint(SIGNAL_QUERY)
+    })
+}
+
+/// Convert list of 'access-technologies' to `NetworkMode` enum
+fn get_mode_by_access_technologies(access_technologies: &[&str]) -> NetworkMode {
+    let has_lte = access_technologies.iter().any(|act| act.starts_with("lte"));
+    let has_nr = access_technologies.contains(&"5gnr");
+
+    if has_nr && has_lte {
+        NetworkMode::NrNsa
+    } else if has_nr {
+        NetworkMode::Nr
+    } else if has_lte {
+        NetworkMode::Lte
+    } else if access_technologies
+        .iter()
+        .any(|act| matches!(*act, "umts" | "hsdpa" | "hsupa" | "hspa" | "hspa-plus"))
+    {
+        NetworkMode::Wcdma
+    } else if access_technologies
+        .iter()
+        .any(|act| matches!(*act, "gsm" | "gsm-compact" | "gprs" | "edge"))
+    {
+        NetworkMode::Gsm
+    } else {
+        NetworkMode::Unknown
+    }
+}
+
+/*
+ * Utils for ModemManager
+ */
+
+pub struct ModemManagerParser {
+    /// Executable of mmcli or other program that prints the same JSON
+    mmcli_path: String,
+}
+
+impl ModemManagerParser {
+    pub fn new(options: &ParserOptions) -> Self {
+        let mmcli_path = if options.mmcli_path.is_empty() {
+            DEFAULT_MMCLI_PATH.to_string()
+        } else {
+            options.mmcli_path.clone()
+        };
+        Self { mmcli_path }
+    }
+
+    /// Run mmcli for the modem. Modem is selected in format of '-m' option, e.g. 'any' or '0'
+    fn run_mmcli(&self, modem: &str, args: &[&str]) -> Result<String, ModemError> {
+        let endpoint = args.join(" ");
+        let command = &self.mmcli_path;
+
+        let output = Command::new(command)
+            .args(["-m", modem])
+            .args(args)
+            .output()
+            .map_err(|e| {
+                eprintln!("Cannot run {command}: {e}");
+                ModemError::unknown(&format!("Cannot run {command}: {e}")).with_endpoint(&endpoint)
+            })?;
+
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            eprintln!("Command {command} {endpoint} failed: {message}");
+            return Err(ModemError::access(&format!("Command {command} failed"))
+                .with_endpoint(&endpoint)
+                .with_vendor_error(VendorError {
+                    code: output.status.code().map(|code| code.to_string()),
+                    message: Some(message),
+                    explanation: None,
+                }));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    fn get_json(&self, modem: &str, args: &[&str]) -> Result<serde_json::Value, ModemError> {
+        let output = self.run_mmcli(modem, args)?;
+        serde_json::from_str(&output).map_err(|e| {
+            eprintln!("Cannot parse output of mmcli: {e}");
+            ModemError::data_parsing("JSON").with_endpoint(&args.join(" "))
+        })
+    }
+
+    fn parse_device_info_json(json: &serde_json::Value) -> DeviceInformation {
+        let manufacturer_str = get_json_value(json, "modem.generic.manufacturer")
+            .as_str()
+            .unwrap_or_default();
+        let model_str = get_json_value(json, "modem.generic.model")
+            .as_str()
+            .unwrap_or_default();
+        DeviceInformation::from(manufacturer_str, model_str)
+    }
+
+    /// Enable polling of extended signal information if it is disabled
+    fn setup_signal_refresh(&self, modem: &str, signal_json: &serde_json::Value) {
+        let refresh_rate =
+            json_str_as_type::<i64>(get_json_value(signal_json, "modem.signal.refresh.rate"))
+                .unwrap_or(0);
+        if refresh_rate != 0 {
+            return;
+        }
+
+        eprintln!("Enable signal refresh with rate {SIGNAL_REFRESH_RATE} s");
+        let setup_arg = format!("--signal-setup={SIGNAL_REFRESH_RATE}");
+        if let Err(e) = self.run_mmcli(modem, &[&setup_arg]) {
+            eprintln!("Cannot setup signal refresh: {}", e.summary());
+        }
+    }
+
+    fn parse_modem_status_json(
+        modem_json: &serde_json::Value,
+        signal_json: &serde_json::Value,
+        location_json: &serde_json::Value,
+    ) -> Result<ModemStatus, ModemError> {
+        let Some(access_technologies) =
+            get_json_value(modem_json, "modem.generic.access-technologies").as_array()
+        else {
+            eprintln!("Modem info doesn't have required field 'access-technologies'");
+            return Err(ModemError::data_parsing("access-technologies").with_endpoint("-J"));
+        };
+        let access_technologies = access_technologies
+            .iter()
+            .filter_map(serde_json::Value::as_str)
+            .collect::<Vec<&str>>();
+        let mode = get_mode_by_access_technologies(&access_technologies);
+
+        let plmn_str = get_json_value(modem_json, "modem.3gpp.operator-code")
+            .as_str()
+            .filter(|val| *val != "--")
+            .unwrap_or_default();
+        let plmn = PlmnStatus::from_str(plmn_str).expect("Unable to convert PLMN from string");
+
+        // Cell ID is only available when 3GPP location is enabled
+        let cell_id = get_json_value(location_json, "modem.location.3gpp.cid")
+            .as_str()
+            .and_then(|val| i64::from_str_radix(val, 16).ok())
+            .unwrap_or(0);
+
+        let lte = get_json_value(signal_json, "modem.signal.lte");
+        let nr = get_json_value(signal_json, "modem.signal.5g");
+        let umts = get_json_value(signal_json, "modem.signal.umts");
+
+        let lte_signal_info = || -> Result<LteSignalInfo, ModemError> {
+            Ok(LteSignalInfo {
+                rsrq: required_mm_i64(lte, "rsrq")?,
+                rsrp: required_mm_i64(lte, "rsrp")?,
+                sinr: required_mm_i64(lte, "snr")?,
+                ca_count: 0,
+                enb: cell_id >> 8,
+                id: cell_id & 0xFF,
+                pci: -1,
+            })
+        };
+        let nr_signal_info = |gnb: i64, id: i64| -> Result<NrSignalInfo, ModemError> {
+            Ok(NrSignalInfo {
+                rsrq: required_mm_i64(nr, "rsrq")?,
+                rsrp: required_mm_i64(nr, "rsrp")?,
+                sinr: required_mm_i64(nr, "snr")?,
+                band: 0,
+                arfcn: 0,
+                gnb,
+                id,
+                pci: -1,
+            })
+        };
+
+        let (rssi, signal_info) = match mode {
+            NetworkMode::Nr => {
+                let (gnb, id) = NrSignalInfo::split_nci(cell_id);
+                (0, SignalInfo::Nr(nr_signal_info(gnb, id)?))
+            }
+            NetworkMode::NrNsa => (
+                get_mm_i64(&lte["rssi"]).unwrap_or(0),
+                SignalInfo::NrNsa(lte_signal_info()?, nr_signal_info(0, 0)?),
+            ),
+            NetworkMode::Lte => (
+                get_mm_i64(&lte["rssi"]).unwrap_or(0),
+                SignalInfo::Lte(lte_signal_info()?),
+            ),
+            NetworkMode::Wcdma => {
+                let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
+                let (nb, cc) = (id / 10, id % 10);
+                (
+                    get_mm_i64(&umts["rssi"]).unwrap_or(0),
+                    SignalInfo::Wcdma(WcdmaSignalInfo {
+                        rscp: required_mm_i64(umts, "rscp")?,
+                        ecio: required_mm_i64(umts, "ecio")?,
+                        nb,
+                        cc,
+                        rnc,
+                        psc: 0,
+                    }),
+                )
+            }
+            _ => (
+                get_mm_i64(get_json_value(signal_json, "modem.signal.gsm.rssi")).unwrap_or(0),
+                SignalInfo::None,
+            ),
+        };
+
+        Ok(ModemStatus {
+            mode,
+            plmn,
+            rssi,
+            cell_id,
+            signal_info,
+            band: ['\0'; 20],
+            carriers: Vec::new(),
+            channel_info: None,
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_info: ModemManagerParser::parse_device_info_json(modem_json),
+            battery_status: None,
+            device_temp: None,
+            traffic_statistics: None,
+            traffic_mode: TrafficMode::Absolute,
+        })
+    }
+}
+
+impl ModemInfoParser for ModemManagerParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        let modem_json = self
+            .get_json(host, &["-J"])
+            .map_err(|e| e.context(&format!("Cannot get info of modem '{host}'")))?;
+
+        let signal_json = self
+            .get_json(host, &[SIGNAL_QUERY, "-J"])
+            .map_err(|e| e.context(&format!("Cannot get signal info of modem '{host}'")))?;
+        self.setup_signal_refresh(host, &signal_json);
+
+        // Optional info
+        let location_json = self
+            .get_json(host, &["--location-get", "-J"])
+            .unwrap_or(serde_json::Value::Null);
+
+        ModemManagerParser::parse_modem_status_json(&modem_json, &signal_json, &location_json)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+    use crate::modem_utils::ModemErrorKind;
+
+    /// Stand-in of mmcli. Modem '0' has signal values, modem '1' hasn't refreshed them yet
+    const MMCLI_SCRIPT: &str = r#"#!/bin/sh
+case "$3" in
+--signal-get)
+    if [ "$2" = 0 ]; then
+        echo '{"modem":{"signal":{"refresh":{"rate":"5"},"lte":{"rsrp":"-95.00","rsrq":"-11.00","rssi":"-66.00","snr":"6.40"}}}}'
+    else
+        echo '{"modem":{"signal":{"refresh":{"rate":"0"},"lte":{"rsrp":"--","rsrq":"--","rssi":"--","snr":"--"}}}}'
+    fi
+    ;;
+--location-get)
+    echo '{"modem":{"location":{"3gpp":{"cid":"2FAA03","lac":"0000","mcc":"262","mnc":"03","tac":"E9E5"}}}}'
+    ;;
+-J)
+    echo '{"modem":{"generic":{"access-technologies":["lte"],"manufacturer":"Quectel","model":"EM12-G"},"3gpp":{"operator-code":"26203"}}}'
+    ;;
+esac
+"#;
+
+    #[test]
+    fn canned_mmcli_output() {
+        let script_path = std::env::temp_dir().join(format!("eas-mmcli-{}.sh", std::process::id()));
+        std::fs::write(&script_path, MMCLI_SCRIPT).unwrap();
+        std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let options = ParserOptions {
+            mmcli_path: script_path.to_string_lossy().to_string(),
+            ..ParserOptions::default()
+        };
+        let mut parser = ModemManagerParser::new(&options);
+
+        let modem_status = parser.get_info("0");
+        let missing_values = parser.get_info("1");
+        std::fs::remove_file(&script_path).unwrap();
+
+        let modem_status = modem_status.unwrap();
+        assert!(modem_status.mode == NetworkMode::Lte);
+        assert_eq!(modem_status.rssi, -66);
+        assert_eq!(modem_status.cell_id, 0x002F_AA03);
+        let SignalInfo::Lte(lte_signal_info) = modem_status.signal_info else {
+            panic!("Serving cell is not LTE");
+        };
+        assert_eq!(lte_signal_info.rsrp, -95);
+        assert_eq!(lte_signal_info.rsrq, -11);
+        assert_eq!(lte_signal_info.sinr, 6);
+        assert_eq!(lte_signal_info.enb, 0x2FAA);
+
+        let Err(e) = missing_values else {
+            panic!("Missing signal values are accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+}
@@ -1,61 +1,359 @@
 #![allow(clippy::similar_names)]
 
 use std::str::FromStr;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use http::uri::Authority;
+use netgear_telnet::{Connection, ConnectionOptions, GstatusInfo};
+
+use crate::at_parser::{get_gstatus_antenna_rsrp, get_gstatus_channel_info};
 
 use crate::bandwidth_utils::{SIZE_TB, TrafficMode, TrafficStatistics};
 use crate::modem_utils::{
-    BatteryStatus, DeviceInformation, DeviceTemperature, LteSignalInfo, ModemError,
-    ModemInfoParser, ModemStatus, NetworkMode, PlmnStatus, SignalInfo, WcdmaSignalInfo,
+    BatteryStatus, CarrierRole, ComponentCarrier, Credentials, DeviceInformation,
+    DeviceTemperature, LteSignalInfo, ModemError, ModemInfoParser, ModemStatus, NetworkMode,
+    NrSignalInfo, ParserOptions, PlmnStatus, SignalInfo, WcdmaSignalInfo,
+};
+use crate::network_utils::{
+    get_url_json_with_agent, get_url_string_with_agent, new_session_agent, post_url_form_with_agent,
+};
+use crate::utils::{
+    copy_string_to_array, get_json_value, json_str_as_type, parse_band_number, parse_bandwidth_mhz,
 };
-use crate::network_utils::get_url_json;
-use crate::utils::{copy_string_to_array, json_str_as_type};
 
 fn get_mode_by_description(s: &str) -> NetworkMode {
     match s {
         "GsmService" => NetworkMode::Gsm,
         "WcdmaService" => NetworkMode::Wcdma,
         "LteService" => NetworkMode::Lte,
+        "NrService" => NetworkMode::Nr,
         _ => NetworkMode::Unknown,
     }
 }
 
+const INFO_JSON_QUERY: &str = "/model.json?internalapi=1";
+
+/// `AT!GSTATUS?` takes seconds to complete so it is polled less often than web UI
+const GSTATUS_PERIOD: Duration = Duration::from_secs(10);
+
+/// Delay before reconnection when telnet port is closed or doesn't respond
+const GSTATUS_RETRY_PERIOD: Duration = Duration::from_secs(60);
+
+fn missing_field(path: &str) -> ModemError {
+    eprintln!("Info JSON doesn't have required field '{path}'");
+    ModemError::data_parsing(path).with_endpoint(INFO_JSON_QUERY)
+}
+
+/// Get required integer field of info JSON by path
+fn required_i64(json: &serde_json::Value, path: &str) -> Result<i64, ModemError> {
+    get_json_value(json, path)
+        .as_i64()
+        .ok_or_else(|| missing_field(path))
+}
+
+/// Get required string field of info JSON by path
+fn required_str<'a>(json: &'a serde_json::Value, path: &str) -> Result<&'a str, ModemError> {
+    get_json_value(json, path)
+        .as_str()
+        .ok_or_else(|| missing_field(path))
+}
+
+/*
+ * Polling of AT port over telnet
+ */
+
+/// Host of AT port is the host of web UI. Web UI address is URL authority that may
+/// have port, e.g. '192.168.1.1:8080' or '[fd00::1]'
+fn get_telnet_host(host: &str) -> Option<String> {
+    let authority = host.parse::<Authority>().ok()?;
+    let hostname = authority.host();
+    // IPv6 address without brackets
+    let hostname = hostname
+        .strip_prefix('[')
+        .and_then(|hostname| hostname.strip_suffix(']'))
+        .unwrap_or(hostname);
+    Some(hostname.to_string())
+}
+
+/// Background thread that runs `AT!GSTATUS?`. Web UI poll only reads the last result
+/// so slow or missing telnet response doesn't delay it
+struct GstatusPoller {
+    host: String,
+    info: Arc<Mutex<Option<GstatusInfo>>>,
+    /// Thread stops when the sender is dropped
+    _stop: mpsc::Sender<()>,
+}
+
+impl GstatusPoller {
+    fn start(host: &str) -> Self {
+        let info = Arc::new(Mutex::new(None));
+        let (stop_sender, stop_receiver) = mpsc::channel();
+
+        if let Some(telnet_host) = get_telnet_host(host) {
+            let thread_info = Arc::clone(&info);
+            thread::spawn(move || GstatusPoller::run(&telnet_host, &thread_info, &stop_receiver));
+        }
+
+        Self {
+            host: host.to_string(),
+            info,
+            _stop: stop_sender,
+        }
+    }
+
+    fn run(host: &str, info: &Mutex<Option<GstatusInfo>>, stop_receiver: &mpsc::Receiver<()>) {
+        let options = ConnectionOptions::default();
+        let mut connection: Option<Connection> = None;
+        loop {
+            if connection.is_none() {
+                connection = Connection::connect(host, &options).ok();
+            }
+
+            let gstatus_info = match connection.as_mut().map(Connection::gstatus) {
+                // Response without system mode is empty
+                Some(Ok(gstatus_info)) => {
+                    Some(gstatus_info).filter(|gstatus_info| !gstatus_info.system_mode.is_empty())
+                }
+                Some(Err(e)) => {
+                    eprintln!("AT!GSTATUS? failed on {host}: {e}");
+                    connection = None;
+                    None
+                }
+                None => None,
+            };
+            *info.lock().expect("Unable to lock GSTATUS info") = gstatus_info;
+
+            let period = if connection.is_some() {
+                GSTATUS_PERIOD
+            } else {
+                GSTATUS_RETRY_PERIOD
+            };
+            if stop_receiver.recv_timeout(period) != Err(mpsc::RecvTimeoutError::Timeout) {
+                break;
+            }
+        }
+    }
+
+    fn get_info(&self) -> Option<GstatusInfo> {
+        self.info
+            .lock()
+            .expect("Unable to lock GSTATUS info")
+            .clone()
+    }
+}
+
 /*
  * Utils for Netgear
  */
 
-pub struct NetgearParser {}
+pub struct NetgearParser {
+    credentials: Credentials,
+    /// HTTP agent with session cookie that is reused between polls
+    agent: ureq::Agent,
+    /// AT port is polled only if it is enabled in options
+    telnet_enabled: bool,
+    gstatus_poller: Option<GstatusPoller>,
+}
 
 impl NetgearParser {
-    fn get_info_json(host: &str) -> Option<serde_json::Value> {
-        get_url_json(host, "/model.json?internalapi=1")
+    pub fn new(options: &ParserOptions) -> Self {
+        Self {
+            credentials: options.credentials.clone(),
+            agent: new_session_agent(),
+            telnet_enabled: options.netgear_telnet,
+            gstatus_poller: None,
+        }
+    }
+
+    fn get_info_json(agent: &ureq::Agent, host: &str) -> Result<serde_json::Value, ModemError> {
+        get_url_json_with_agent(agent, host, INFO_JSON_QUERY)
+    }
+
+    /// Check if info JSON has advanced WWAN info that is hidden from anonymous users
+    fn is_login_required(json: &serde_json::Value) -> bool {
+        json["wwanadv"].is_null()
+    }
+
+    /// Get session token from hidden 'token' field of the login form
+    fn parse_session_token(page: &str) -> Option<String> {
+        const TOKEN_MARKER: &str = "name=\"token\" value=\"";
+        let start = page.find(TOKEN_MARKER)? + TOKEN_MARKER.len();
+        let len = page[start..].find('"')?;
+        Some(page[start..start + len].to_string())
+    }
+
+    /// Login to web UI. Session cookie is kept by the agent
+    fn login(agent: &ureq::Agent, host: &str, credentials: &Credentials) -> Result<(), ModemError> {
+        if credentials.password.is_empty() {
+            eprintln!("Login is required but password is not set");
+            return Err(ModemError::access(
+                "Login is required but password is not set",
+            ));
+        }
+
+        let page = get_url_string_with_agent(agent, host, "/index.html")?;
+
+        let Some(token) = NetgearParser::parse_session_token(&page) else {
+            eprintln!("Cannot find session token on login page");
+            return Err(ModemError::data_parsing("login page token").with_endpoint("/index.html"));
+        };
+
+        post_url_form_with_agent(
+            agent,
+            host,
+            "/Forms/config",
+            &[
+                ("session.password", credentials.password.as_str()),
+                ("token", token.as_str()),
+            ],
+        )
+        .map_err(|e| e.context("Login to web UI failed"))?;
+
+        Ok(())
+    }
+
+    fn parse_nr_signal_json(json: &serde_json::Value, nci: i64) -> Option<NrSignalInfo> {
+        let rsrp = json["wwan"]["signalStrength"]["nr5gRsrp"].as_i64()?;
+        let rsrq = json["wwan"]["signalStrength"]["nr5gRsrq"].as_i64()?;
+        let sinr = json["wwan"]["signalStrength"]["nr5gSinr"].as_i64()?;
+
+        let band = json["wwanadv"]["nr5gBand"]
+            .as_str()
+            .and_then(parse_band_number)
+            .unwrap_or(0);
+        let arfcn = json["wwanadv"]["nr5gArfcn"].as_i64().unwrap_or(0);
+        let pci = json["wwanadv"]["nr5gPci"].as_i64().unwrap_or(-1);
+
+        let nci = json["wwanadv"]["nr5gCellId"].as_i64().unwrap_or(nci);
+        let (gnb, id) = NrSignalInfo::split_nci(nci);
+
+        Some(NrSignalInfo {
+            rsrq,
+            rsrp,
+            sinr,
+            band,
+            arfcn,
+            gnb,
+            id,
+            pci,
+        })
+    }
+
+    /// Get list of component carriers: PCC from current cell and SCCs from 'wwan.ca'
+    fn parse_ca_json(json: &serde_json::Value) -> Vec<ComponentCarrier> {
+        let as_i64 = |val: &serde_json::Value| val.as_i64().or_else(|| json_str_as_type(val));
+
+        let mut carriers = vec![ComponentCarrier {
+            role: CarrierRole::Primary,
+            band: json["wwanadv"]["curBand"]
+                .as_str()
+                .and_then(parse_band_number)
+                .unwrap_or(0),
+            earfcn: as_i64(&json["wwanadv"]["chanId"]),
+            bandwidth: None,
+            pci: as_i64(&json["wwanadv"]["primScode"]),
+            rsrp: as_i64(&json["wwan"]["signalStrength"]["rsrp"]),
+            rsrq: as_i64(&json["wwan"]["signalStrength"]["rsrq"]),
+            sinr: as_i64(&json["wwan"]["signalStrength"]["sinr"]),
+        }];
+
+        if let Some(scc_list) = json["wwan"]["ca"]["SCC"].as_array() {
+            for scc in scc_list {
+                if scc["enabled"].as_bool() == Some(false) {
+                    continue;
+                }
+
+                let band = scc["band"]
+                    .as_str()
+                    .and_then(parse_band_number)
+                    .or_else(|| scc["band"].as_i64())
+                    .unwrap_or(0);
+
+                carriers.push(ComponentCarrier {
+                    role: CarrierRole::Secondary,
+                    band,
+                    earfcn: as_i64(&scc["earfcn"]),
+                    bandwidth: scc["bandwidth"]
+                        .as_str()
+                        .and_then(parse_bandwidth_mhz)
+                        .or_else(|| scc["bandwidth"].as_f64()),
+                    pci: as_i64(&scc["pci"]),
+                    rsrp: as_i64(&scc["rsrp"]),
+                    rsrq: as_i64(&scc["rsrq"]),
+                    sinr: as_i64(&scc["sinr"]),
+                });
+            }
+        }
+
+        carriers
+    }
+
+    fn parse_device_info_json(json: &serde_json::Value) -> DeviceInformation {
+        let manufacturer_str = json["general"]["companyName"].as_str().unwrap_or("NETGEAR");
+        let model_str = json["general"]["deviceName"].as_str().unwrap_or_default();
+        DeviceInformation::from(manufacturer_str, model_str)
+    }
+
+    /// Check if host has Netgear info JSON. Model is available without login
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let agent = new_session_agent();
+        let json = NetgearParser::get_info_json(&agent, host).ok()?;
+        if json["general"].is_object() {
+            Some(NetgearParser::parse_device_info_json(&json))
+        } else {
+            None
+        }
+    }
+
+    /// Add parameters of LTE primary carrier that are missing in info JSON
+    fn merge_gstatus_info(modem_status: &mut ModemStatus, gstatus_info: &GstatusInfo) {
+        if modem_status.mode != NetworkMode::Lte && modem_status.mode != NetworkMode::NrNsa {
+            return;
+        }
+
+        if let Some(channel_info) = get_gstatus_channel_info(gstatus_info) {
+            if let Some(primary_carrier) = modem_status.carriers.first_mut()
+                && primary_carrier.bandwidth.is_none()
+            {
+                primary_carrier.bandwidth = channel_info.dl_bandwidth;
+            }
+            modem_status.channel_info = Some(channel_info);
+        }
+        modem_status.tac = gstatus_info.tac;
+        modem_status.antenna_rsrp = get_gstatus_antenna_rsrp(gstatus_info);
     }
 
-    fn parse_info_json(json: &serde_json::Value) -> ModemStatus {
+    fn parse_info_json(json: &serde_json::Value) -> Result<ModemStatus, ModemError> {
         let ca_count = json["wwan"]["ca"]["SCCcount"].as_i64().unwrap_or(0);
 
-        let mode = get_mode_by_description(json["wwan"]["currentNWserviceType"].as_str().unwrap());
+        let mut mode = get_mode_by_description(required_str(json, "wwan.currentNWserviceType")?);
 
-        let rssi = json["wwan"]["signalStrength"]["rssi"].as_i64().unwrap();
+        let rssi = required_i64(json, "wwan.signalStrength.rssi")?;
 
         let plmn_str = format!(
             "{}{}",
-            json["wwanadv"]["MCC"].as_str().unwrap(),
-            json["wwanadv"]["MNC"].as_str().unwrap()
+            json["wwanadv"]["MCC"].as_str().unwrap_or("000"),
+            json["wwanadv"]["MNC"].as_str().unwrap_or("00")
         );
         let plmn = PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string");
 
-        let band_str = json["wwanadv"]["curBand"].as_str().unwrap().to_string();
+        let band_str = json["wwanadv"]["curBand"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
         let mut band = ['\0'; 20];
         copy_string_to_array!(band, band_str);
 
-        let cell_id = json["wwanadv"]["cellId"].as_i64().unwrap();
+        let cell_id = json["wwanadv"]["cellId"].as_i64().unwrap_or(0);
 
         let signal_info: SignalInfo = match mode {
             NetworkMode::Wcdma => {
-                let rscp = json["wwan"]["signalStrength"]["rscp"].as_i64().unwrap();
-                let ecio = json["wwan"]["signalStrength"]["ecio"].as_i64().unwrap();
+                let rscp = required_i64(json, "wwan.signalStrength.rscp")?;
+                let ecio = required_i64(json, "wwan.signalStrength.ecio")?;
 
-                let psc = json["wwanadv"]["primScode"].as_i64().unwrap();
+                let psc = json["wwanadv"]["primScode"].as_i64().unwrap_or(0);
 
                 let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
 
@@ -71,15 +369,15 @@ impl NetgearParser {
                 })
             }
             NetworkMode::Lte => {
-                let rsrq = json["wwan"]["signalStrength"]["rsrq"].as_i64().unwrap();
-                let rsrp = json["wwan"]["signalStrength"]["rsrp"].as_i64().unwrap();
-                let sinr = json["wwan"]["signalStrength"]["sinr"].as_i64().unwrap();
+                let rsrq = required_i64(json, "wwan.signalStrength.rsrq")?;
+                let rsrp = required_i64(json, "wwan.signalStrength.rsrp")?;
+                let sinr = required_i64(json, "wwan.signalStrength.sinr")?;
 
-                let pci = json["wwanadv"]["primScode"].as_i64().unwrap();
+                let pci = json["wwanadv"]["primScode"].as_i64().unwrap_or(-1);
 
                 let (enb, id) = (cell_id >> 8, cell_id & 0xFF);
 
-                SignalInfo::Lte(LteSignalInfo {
+                let lte_info = LteSignalInfo {
                     rsrq,
                     rsrp,
                     sinr,
@@ -87,75 +385,158 @@ impl NetgearParser {
                     enb,
                     id,
                     pci,
-                })
+                };
+
+                // LTE anchor with NR leg (EN-DC)
+                if let Some(nr_info) = NetgearParser::parse_nr_signal_json(json, 0) {
+                    mode = NetworkMode::NrNsa;
+                    SignalInfo::NrNsa(lte_info, nr_info)
+                } else {
+                    SignalInfo::Lte(lte_info)
+                }
             }
+            NetworkMode::Nr => SignalInfo::Nr(
+                NetgearParser::parse_nr_signal_json(json, cell_id)
+                    .ok_or_else(|| missing_field("wwan.signalStrength.nr5gRsrp"))?,
+            ),
             _ => SignalInfo::None,
         };
 
+        let carriers = match mode {
+            NetworkMode::Lte | NetworkMode::NrNsa => NetgearParser::parse_ca_json(json),
+            _ => Vec::new(),
+        };
+
         // Modem model
-        let manufacturer_str = json["general"]["companyName"].as_str().unwrap().to_string();
-        let model_str = json["general"]["deviceName"].as_str().unwrap().to_string();
-        let device_info = DeviceInformation::from(&manufacturer_str, &model_str);
+        let device_info = NetgearParser::parse_device_info_json(json);
 
-        // Battery info
-        let battery_percent = json["power"]["battChargeLevel"].as_i64().unwrap();
+        // Battery info (routers without battery don't have it)
+        let battery_status = json["power"]["battChargeLevel"]
+            .as_i64()
+            .map(|battery_percent| {
+                let battery_status_str = json["power"]["battChargeSource"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+                let mut battery_status = ['\0'; 20];
+                copy_string_to_array!(battery_status, battery_status_str);
 
-        let battery_status_str = json["power"]["battChargeSource"]
-            .as_str()
-            .unwrap()
-            .to_string();
-        let mut battery_status = ['\0'; 20];
-        copy_string_to_array!(battery_status, battery_status_str);
-
-        let battery_status = BatteryStatus {
-            percent: battery_percent,
-            status: battery_status,
-        };
+                BatteryStatus {
+                    percent: battery_percent,
+                    status: battery_status,
+                }
+            });
 
         // Temperature
-        let device_temp = DeviceTemperature {
-            device_temp: json["general"]["devTemperature"].as_i64().unwrap(),
-            battery_temp: json["power"]["batteryTemperature"].as_i64().unwrap(),
-        };
+        let device_temp = json["general"]["devTemperature"]
+            .as_i64()
+            .map(|device_temp| DeviceTemperature {
+                device_temp,
+                battery_temp: json["power"]["batteryTemperature"].as_i64(),
+            });
 
         // Bandwidth
-        let dl = if let Some(dl) = json_str_as_type::<i64>(&json["wwan"]["dataTransferredRx"]) {
-            if dl <= SIZE_TB { dl * 8 } else { 0 }
-        } else {
-            0
-        };
-        let ul = if let Some(ul) = json_str_as_type::<i64>(&json["wwan"]["dataTransferredTx"]) {
-            if ul <= SIZE_TB { ul * 8 } else { 0 }
+        let dl = json_str_as_type::<i64>(&json["wwan"]["dataTransferredRx"]);
+        let ul = json_str_as_type::<i64>(&json["wwan"]["dataTransferredTx"]);
+        let traffic_statistics = if dl.is_none() && ul.is_none() {
+            None
         } else {
-            0
+            let to_bits = |bytes: Option<i64>| {
+                bytes
+                    .filter(|bytes| *bytes <= SIZE_TB)
+                    .map_or(0, |bytes| bytes * 8)
+            };
+            Some(TrafficStatistics {
+                dl: to_bits(dl),
+                ul: to_bits(ul),
+            })
         };
-        let traffic_statistics = TrafficStatistics { dl, ul };
 
-        ModemStatus {
+        Ok(ModemStatus {
             mode,
             plmn,
             rssi,
             cell_id,
             signal_info,
             band,
+            carriers,
+            channel_info: None,
+            tac: None,
+            antenna_rsrp: Vec::new(),
             device_info,
-            battery_status: Some(battery_status),
-            device_temp: Some(device_temp),
-            traffic_statistics: Some(traffic_statistics),
+            battery_status,
+            device_temp,
+            traffic_statistics,
             traffic_mode: TrafficMode::Cumulative,
-        }
+        })
     }
 }
 
 impl ModemInfoParser for NetgearParser {
-    fn get_info(host: &str) -> Result<ModemStatus, ModemError> {
-        if let Some(json) = NetgearParser::get_info_json(host) {
-            let modem_info = NetgearParser::parse_info_json(&json);
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        // Session cookie is kept by the agent so login is needed only when it expires
+        let agent = &self.agent;
 
-            Ok(modem_info)
-        } else {
-            eprintln!("Cannot access info JSON from host {host}");
-            Err(ModemError::HttpConnection)
+        let mut json = NetgearParser::get_info_json(agent, host)
+            .map_err(|e| e.context(&format!("Cannot access info JSON from host {host}")))?;
+
+        // Newer firmwares require login to show full info
+        if NetgearParser::is_login_required(&json) {
+            NetgearParser::login(agent, host, &self.credentials)?;
+
+            json = NetgearParser::get_info_json(agent, host)?;
+            if NetgearParser::is_login_required(&json) {
+                // Password is rejected. It is not posted again on the next poll
+                eprintln!("Info JSON is not available after login");
+                return Err(ModemError::authentication(
+                    "Advanced info is hidden after login, check the password",
+                )
+                .with_endpoint(INFO_JSON_QUERY));
+            }
+        }
+
+        let mut modem_status = NetgearParser::parse_info_json(&json)?;
+
+        // Optional info from AT port
+        if self.telnet_enabled
+            && self
+                .gstatus_poller
+                .as_ref()
+                .is_none_or(|poller| poller.host != host)
+        {
+            self.gstatus_poller = Some(GstatusPoller::start(host));
         }
+        if let Some(gstatus_info) = self
+            .gstatus_poller
+            .as_ref()
+            .and_then(GstatusPoller::get_info)
+        {
+            NetgearParser::merge_gstatus_info(&mut modem_status, &gstatus_info);
+        }
+
+        Ok(modem_status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn telnet_host() {
+        assert_eq!(
+            get_telnet_host("192.168.1.1").as_deref(),
+            Some("192.168.1.1")
+        );
+        assert_eq!(
+            get_telnet_host("192.168.1.1:8080").as_deref(),
+            Some("192.168.1.1")
+        );
+        assert_eq!(get_telnet_host("[fd00::1]").as_deref(), Some("fd00::1"));
+        assert_eq!(
+            get_telnet_host("[fd00::1]:8080").as_deref(),
+            Some("fd00::1")
+        );
+        assert_eq!(get_telnet_host("mr1100.lan").as_deref(), Some("mr1100.lan"));
     }
 }
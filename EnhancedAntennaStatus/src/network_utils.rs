@@ -4,130 +4,454 @@
 use http::Uri;
 use std::time::Duration;
 
+use crate::modem_utils::{Credentials, ModemError};
+use crate::utils::md5_hex;
+
 const HTTP_TIMEOUT: Duration = Duration::from_millis(3_000);
 
-pub fn get_url_json(host: &str, query: &str) -> Option<serde_json::Value> {
-    if let Ok(path) = Uri::builder()
+fn build_url(host: &str, query: &str) -> Result<String, ModemError> {
+    Uri::builder()
         .scheme("http")
         .authority(host)
         .path_and_query(query)
         .build()
-    {
-        let agent = ureq::AgentBuilder::new()
-            .timeout_connect(HTTP_TIMEOUT)
-            .build();
-
-        let req = agent.get(&path.to_string());
-        match req.call() {
-            Ok(response) => {
-                if let Ok(json) = response.into_json::<serde_json::Value>() {
-                    return Some(json);
-                }
-            }
-            Err(ureq::Error::Status(code, response)) => {
-                eprintln!(
-                    "HTTP error code={} response={}",
-                    code,
-                    response.status_text()
-                );
-            }
-            Err(e) => {
-                eprintln!("HTTP error={}", &e.to_string());
-            }
+        .map(|path| path.to_string())
+        .map_err(|e| {
+            ModemError::http_connection(&format!("Invalid address: {e}")).with_endpoint(query)
+        })
+}
+
+/// Convert error of HTTP request into modem error with context
+fn request_error(query: &str, e: ureq::Error) -> ModemError {
+    match e {
+        ureq::Error::Status(code, response) => {
+            eprintln!(
+                "HTTP error code={} response={}",
+                code,
+                response.status_text()
+            );
+            ModemError::http_connection(&format!("HTTP status {code} {}", response.status_text()))
+                .with_endpoint(query)
+                .with_http_status(code)
+        }
+        e => {
+            eprintln!("HTTP error={e:?}");
+            ModemError::http_connection(&e.to_string()).with_endpoint(query)
         }
     }
-    None
 }
 
-pub fn get_url_xml(host: &str, query: &str) -> Option<xmltree::Element> {
-    if let Ok(path) = Uri::builder()
-        .scheme("http")
-        .authority(host)
-        .path_and_query(query)
+fn parse_xml(query: &str, xml: &str) -> Result<xmltree::Element, ModemError> {
+    xmltree::Element::parse(xml.as_bytes()).map_err(|e| {
+        eprintln!("XML DOM error={}", &e.to_string());
+        ModemError::data_parsing("XML")
+            .context(&e.to_string())
+            .with_endpoint(query)
+    })
+}
+
+/// Referer of web UI start page. Some web UIs (e.g. ZTE) reject API requests without it
+fn get_referer(host: &str) -> String {
+    format!("http://{host}/index.html")
+}
+
+/// Create HTTP agent that keeps cookies of web UI session and connections to the modem
+/// between requests
+pub fn new_session_agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(HTTP_TIMEOUT)
         .build()
-    {
-        let agent = ureq::AgentBuilder::new()
-            .timeout_connect(HTTP_TIMEOUT)
-            .build();
-
-        let req = agent.get(&path.to_string()).set("Accept", "*/*");
-
-        match req.call() {
-            Ok(response) => {
-                if let Ok(xml) = response.into_string() {
-                    match xmltree::Element::parse(xml.as_bytes()) {
-                        Ok(parsed_xml) => {
-                            return Some(parsed_xml);
-                        }
-                        Err(e) => {
-                            eprintln!("XML DOM error={}", &e.to_string());
-                        }
-                    }
-                }
-            }
-            Err(ureq::Error::Status(code, response)) => {
-                eprintln!(
-                    "HTTP error code={} response={}",
-                    code,
-                    response.status_text()
-                );
-            }
-            Err(e) => {
-                eprintln!("HTTP error={}", &e.to_string());
-            }
-        }
+}
+
+pub fn get_url_json_with_agent(
+    agent: &ureq::Agent,
+    host: &str,
+    query: &str,
+) -> Result<serde_json::Value, ModemError> {
+    let path = build_url(host, query)?;
+
+    let req = agent.get(&path).set("Referer", &get_referer(host));
+    match req.call() {
+        Ok(response) => response.into_json::<serde_json::Value>().map_err(|e| {
+            ModemError::data_parsing("JSON")
+                .context(&e.to_string())
+                .with_endpoint(query)
+        }),
+        Err(e) => Err(request_error(query, e)),
+    }
+}
+
+/// Get JSON of REST API that is authorized with bearer token
+pub fn get_url_json_with_bearer_token(
+    agent: &ureq::Agent,
+    host: &str,
+    token: &str,
+    query: &str,
+) -> Result<serde_json::Value, ModemError> {
+    let path = build_url(host, query)?;
+
+    let req = agent
+        .get(&path)
+        .set("Accept", "application/json")
+        .set("Authorization", &format!("Bearer {token}"));
+    match req.call() {
+        Ok(response) => response.into_json::<serde_json::Value>().map_err(|e| {
+            ModemError::data_parsing("JSON")
+                .context(&e.to_string())
+                .with_endpoint(query)
+        }),
+        Err(e) => Err(request_error(query, e)),
+    }
+}
+
+pub fn get_url_string_with_agent(
+    agent: &ureq::Agent,
+    host: &str,
+    query: &str,
+) -> Result<String, ModemError> {
+    let path = build_url(host, query)?;
+
+    let req = agent.get(&path).set("Accept", "*/*");
+    match req.call() {
+        Ok(response) => response
+            .into_string()
+            .map_err(|e| ModemError::http_connection(&e.to_string()).with_endpoint(query)),
+        Err(e) => Err(request_error(query, e)),
     }
-    None
+}
+
+/// Post JSON request with additional headers. Returns JSON of the response
+pub fn post_url_json_with_agent(
+    agent: &ureq::Agent,
+    host: &str,
+    query: &str,
+    headers: &[(&str, &str)],
+    json: &serde_json::Value,
+) -> Result<serde_json::Value, ModemError> {
+    let path = build_url(host, query)?;
+
+    let mut req = agent
+        .post(&path)
+        .set("Accept", "application/json")
+        .set("Referer", &get_referer(host));
+    for (header, value) in headers {
+        req = req.set(header, value);
+    }
+    match req.send_json(json) {
+        Ok(response) => response.into_json::<serde_json::Value>().map_err(|e| {
+            ModemError::data_parsing("JSON")
+                .context(&e.to_string())
+                .with_endpoint(query)
+        }),
+        Err(e) => Err(request_error(query, e)),
+    }
+}
+
+/// Post HTML form. Returns body of the response
+pub fn post_url_form_with_agent(
+    agent: &ureq::Agent,
+    host: &str,
+    query: &str,
+    form: &[(&str, &str)],
+) -> Result<String, ModemError> {
+    let path = build_url(host, query)?;
+
+    let req = agent
+        .post(&path)
+        .set("Accept", "*/*")
+        .set("Referer", &get_referer(host));
+    match req.send_form(form) {
+        Ok(response) => Ok(response.into_string().unwrap_or_default()),
+        Err(e) => Err(request_error(query, e)),
+    }
+}
+
+/// Set headers of Huawei web UI session. Cookie from the session info is sent only
+/// until the modem sets its own cookie that is kept by the agent
+fn set_session_headers(
+    agent: &ureq::Agent,
+    req: ureq::Request,
+    host: &str,
+    sesion_token: Option<&(String, String)>,
+) -> ureq::Request {
+    let Some((session_info, token_info)) = sesion_token else {
+        return req;
+    };
+
+    let mut req = req
+        .set("Host", host)
+        .set("X-Requested-With", "XMLHttpRequest")
+        .set("__RequestVerificationToken", token_info.as_str());
+    if agent.cookie_store().iter_any().next().is_none() {
+        req = req.set("Cookie", session_info.as_str());
+    }
+    req
+}
+
+pub fn get_url_xml_with_agent(
+    agent: &ureq::Agent,
+    host: &str,
+    query: &str,
+) -> Result<xmltree::Element, ModemError> {
+    get_url_xml_with_session_token(agent, host, None, query)
 }
 
 pub fn get_url_xml_with_session_token(
+    agent: &ureq::Agent,
     host: &str,
     sesion_token: Option<&(String, String)>,
     query: &str,
-) -> Option<xmltree::Element> {
-    if let Ok(path) = Uri::builder()
-        .scheme("http")
-        .authority(host)
-        .path_and_query(query)
-        .build()
-    {
-        let agent = ureq::AgentBuilder::new()
-            .timeout_connect(HTTP_TIMEOUT)
-            .build();
-
-        let mut req = agent.get(&path.to_string()).set("Accept", "*/*");
-        if let Some((session_info, token_info)) = sesion_token {
-            req = req
-                .set("Host", host)
-                .set("X-Requested-With", "XMLHttpRequest")
-                .set("Cookie", session_info.as_str())
-                .set("__RequestVerificationToken", token_info.as_str());
+) -> Result<xmltree::Element, ModemError> {
+    let path = build_url(host, query)?;
+
+    let req = agent.get(&path).set("Accept", "*/*");
+    let req = set_session_headers(agent, req, host, sesion_token);
+
+    match req.call() {
+        Ok(response) => {
+            let xml = response
+                .into_string()
+                .map_err(|e| ModemError::http_connection(&e.to_string()).with_endpoint(query))?;
+            parse_xml(query, &xml)
         }
+        Err(e) => Err(request_error(query, e)),
+    }
+}
 
-        match req.call() {
-            Ok(response) => {
-                if let Ok(xml) = response.into_string() {
-                    match xmltree::Element::parse(xml.as_bytes()) {
-                        Ok(parsed_xml) => {
-                            return Some(parsed_xml);
-                        }
-                        Err(e) => {
-                            eprintln!("XML DOM error={}", &e.to_string());
-                        }
-                    }
-                }
-            }
-            Err(ureq::Error::Status(code, response)) => {
-                eprintln!(
-                    "HTTP error code={} response={}",
-                    code,
-                    response.status_text()
-                );
-            }
-            Err(e) => {
-                eprintln!("HTTP error={e:?}");
-            }
+/// Response to POST request in Huawei web UI with updated session and token
+pub struct XmlPostResponse {
+    pub xml: xmltree::Element,
+    /// New session from 'Set-Cookie' header
+    pub session_info: Option<String>,
+    /// New verification token for the next request
+    pub token_info: Option<String>,
+}
+
+pub fn post_url_xml_with_session_token(
+    agent: &ureq::Agent,
+    host: &str,
+    sesion_token: Option<&(String, String)>,
+    query: &str,
+    body: &str,
+) -> Result<XmlPostResponse, ModemError> {
+    let path = build_url(host, query)?;
+
+    let req = agent.post(&path).set("Accept", "*/*").set(
+        "Content-Type",
+        "application/x-www-form-urlencoded; charset=UTF-8",
+    );
+    let req = set_session_headers(agent, req, host, sesion_token);
+
+    match req.send_string(body) {
+        Ok(response) => {
+            let session_info = response
+                .all("Set-Cookie")
+                .into_iter()
+                .find_map(|cookie| cookie.split(';').next())
+                .map(std::string::ToString::to_string);
+            let token_info = response
+                .header("__RequestVerificationTokenone")
+                .or_else(|| response.header("__RequestVerificationToken"))
+                .map(std::string::ToString::to_string);
+
+            let xml = response
+                .into_string()
+                .map_err(|e| ModemError::http_connection(&e.to_string()).with_endpoint(query))?;
+
+            Ok(XmlPostResponse {
+                xml: parse_xml(query, &xml)?,
+                session_info,
+                token_info,
+            })
         }
+        Err(e) => Err(request_error(query, e)),
+    }
+}
+
+/// Get parameters of 'WWW-Authenticate: Digest realm="...", nonce="...", qop="auth"'
+fn parse_digest_challenge(challenge: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut rest = challenge.trim_start_matches("Digest").trim();
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let value = value.trim_start();
+        let (value, next) = if let Some(value) = value.strip_prefix('"') {
+            value.split_once('"').unwrap_or((value, ""))
+        } else {
+            value.split_once(',').unwrap_or((value, ""))
+        };
+        params.push((key, value.trim().to_string()));
+        rest = next;
+    }
+    params
+}
+
+/// Build 'Authorization' header for digest challenge (RFC 2617) with MD5 algorithm.
+/// Client nonce is used only if server requests 'auth' quality of protection
+fn get_digest_authorization(
+    challenge: &str,
+    method: &str,
+    uri: &str,
+    credentials: &Credentials,
+    cnonce: &str,
+) -> String {
+    let params = parse_digest_challenge(challenge);
+    let get_param = |key: &str| {
+        params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(key))
+            .map_or("", |(_, value)| value.as_str())
+    };
+    let (realm, nonce) = (get_param("realm"), get_param("nonce"));
+    let username = &credentials.username;
+
+    let ha1 = md5_hex(&format!("{username}:{realm}:{}", credentials.password));
+    let ha2 = md5_hex(&format!("{method}:{uri}"));
+
+    let mut authorization = format!(
+        "Digest username=\"{username}\", realm=\"{realm}\", nonce=\"{nonce}\", uri=\"{uri}\", algorithm=MD5"
+    );
+    if get_param("qop").split(',').any(|qop| qop.trim() == "auth") {
+        let nc = "00000001";
+        let response = md5_hex(&format!("{ha1}:{nonce}:{nc}:{cnonce}:auth:{ha2}"));
+        authorization.push_str(&format!(
+            ", response=\"{response}\", qop=auth, nc={nc}, cnonce=\"{cnonce}\""
+        ));
+    } else {
+        let response = md5_hex(&format!("{ha1}:{nonce}:{ha2}"));
+        authorization.push_str(&format!(", response=\"{response}\""));
+    }
+
+    let opaque = get_param("opaque");
+    if !opaque.is_empty() {
+        authorization.push_str(&format!(", opaque=\"{opaque}\""));
+    }
+    authorization
+}
+
+/// Post XML request that is protected with HTTP digest authentication.
+/// Request is sent again with credentials if server responds with digest challenge
+pub fn post_url_xml_with_digest_auth(
+    agent: &ureq::Agent,
+    host: &str,
+    query: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+    credentials: &Credentials,
+) -> Result<xmltree::Element, ModemError> {
+    let path = build_url(host, query)?;
+
+    let new_request = || {
+        let mut req = agent
+            .post(&path)
+            .set("Content-Type", "text/xml; charset=\"utf-8\"");
+        for (header, value) in headers {
+            req = req.set(header, value);
+        }
+        req
+    };
+
+    let response = match new_request().send_string(body) {
+        Ok(response) => response,
+        Err(ureq::Error::Status(401, response)) => {
+            let Some(challenge) = response
+                .header("WWW-Authenticate")
+                .filter(|challenge| challenge.starts_with("Digest"))
+            else {
+                return Err(ModemError::access("Digest authentication is not offered")
+                    .with_endpoint(query)
+                    .with_http_status(401));
+            };
+            let cnonce = format!("{:016x}", rand::random::<u64>());
+            let authorization =
+                get_digest_authorization(challenge, "POST", query, credentials, &cnonce);
+
+            new_request()
+                .set("Authorization", &authorization)
+                .send_string(body)
+                .map_err(|e| request_error(query, e))?
+        }
+        Err(e) => return Err(request_error(query, e)),
+    };
+
+    let xml = response
+        .into_string()
+        .map_err(|e| ModemError::http_connection(&e.to_string()).with_endpoint(query))?;
+    parse_xml(query, &xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Challenge of the example in RFC 2617, section 3.5
+    const RFC2617_CHALLENGE: &str = "Digest realm=\"testrealm@host.com\", qop=\"auth,auth-int\", \
+        nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", opaque=\"5ccc069c403ebaf9f0171e9517f40e41\"";
+
+    #[test]
+    fn digest_challenge() {
+        let params = parse_digest_challenge(RFC2617_CHALLENGE);
+        assert_eq!(
+            params,
+            vec![
+                ("realm".to_string(), "testrealm@host.com".to_string()),
+                ("qop".to_string(), "auth,auth-int".to_string()),
+                (
+                    "nonce".to_string(),
+                    "dcd98b7102dd2f0e8b11d0f600bfb0c093".to_string()
+                ),
+                (
+                    "opaque".to_string(),
+                    "5ccc069c403ebaf9f0171e9517f40e41".to_string()
+                ),
+            ]
+        );
+
+        // Unquoted values
+        let params = parse_digest_challenge("Digest realm=\"F!Box\", nonce=ABC123, algorithm=MD5");
+        assert_eq!(params[1], ("nonce".to_string(), "ABC123".to_string()));
+        assert_eq!(params[2], ("algorithm".to_string(), "MD5".to_string()));
+    }
+
+    #[test]
+    fn digest_authorization() {
+        let credentials = Credentials {
+            username: "Mufasa".to_string(),
+            password: "Circle Of Life".to_string(),
+        };
+        let authorization = get_digest_authorization(
+            RFC2617_CHALLENGE,
+            "GET",
+            "/dir/index.html",
+            &credentials,
+            "0a4f113b",
+        );
+        assert_eq!(
+            authorization,
+            "Digest username=\"Mufasa\", realm=\"testrealm@host.com\", \
+            nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", uri=\"/dir/index.html\", algorithm=MD5, \
+            response=\"6629fae49393a05397450978507c4ef1\", qop=auth, nc=00000001, \
+            cnonce=\"0a4f113b\", opaque=\"5ccc069c403ebaf9f0171e9517f40e41\""
+        );
+    }
+
+    #[test]
+    fn digest_authorization_without_qop() {
+        let credentials = Credentials {
+            username: "Mufasa".to_string(),
+            password: "Circle Of Life".to_string(),
+        };
+        let authorization = get_digest_authorization(
+            "Digest realm=\"testrealm@host.com\", nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\"",
+            "GET",
+            "/dir/index.html",
+            &credentials,
+            "0a4f113b",
+        );
+        // MD5 of 'HA1:nonce:HA2' (RFC 2069 compatibility)
+        let ha1 = md5_hex("Mufasa:testrealm@host.com:Circle Of Life");
+        let ha2 = md5_hex("GET:/dir/index.html");
+        let response = md5_hex(&format!("{ha1}:dcd98b7102dd2f0e8b11d0f600bfb0c093:{ha2}"));
+        assert!(authorization.ends_with(&format!(", response=\"{response}\"")));
     }
-    None
 }
@@ -0,0 +1,671 @@
+#![allow(clippy::similar_names)]
+
+use std::str::FromStr;
+
+use serde_json::json;
+
+use crate::bandwidth_utils::{TrafficMode, TrafficStatistics};
+use crate::earfcn_utils::get_lte_frequencies;
+use crate::modem_utils::{
+    CarrierRole, ChannelInfo, ComponentCarrier, Credentials, DeviceInformation, LteSignalInfo,
+    ModemError, ModemErrorKind, ModemInfoParser, ModemStatus, NetworkMode, NrSignalInfo,
+    PlmnStatus, SignalInfo, VendorError, WcdmaSignalInfo,
+};
+use crate::network_utils::{new_session_agent, post_url_json_with_agent};
+use crate::utils::{copy_string_to_array, parse_band_number, parse_bandwidth_mhz};
+
+const UBUS_QUERY: &str = "/ubus";
+
+/// Session ID for calls before login
+const NULL_SESSION: &str = "00000000000000000000000000000000";
+
+const DEFAULT_USERNAME: &str = "root";
+
+/// Control device of QMI modem if there is no interface with 'qmi' protocol in config
+const DEFAULT_QMI_DEVICE: &str = "/dev/cdc-wdm0";
+
+/// Path of uqmi. rpcd executes commands only by absolute path
+const UQMI_COMMAND: &str = "/sbin/uqmi";
+
+/// Human-readable meaning of ubus status codes
+fn explain_ubus_status(code: &str) -> Option<&'static str> {
+    match code {
+        "1" => Some("Invalid command"),
+        "2" => Some("Invalid argument"),
+        "3" => Some("Method not found"),
+        "4" => Some("Object not found"),
+        "5" => Some("No data"),
+        "6" => Some("Permission denied, login is required"),
+        "7" => Some("Request timed out"),
+        "8" => Some("Operation not supported"),
+        "-32002" => Some("Access denied, session has expired"),
+        _ => None,
+    }
+}
+
+/// Check if error means that session is not valid anymore
+fn is_session_error(e: &ModemError) -> bool {
+    e.vendor_error
+        .as_ref()
+        .and_then(|vendor_error| vendor_error.code.as_deref())
+        .is_some_and(|code| code == "6" || code == "-32002")
+}
+
+/// Network interface of QMI modem in OpenWrt config
+struct QmiInterface {
+    /// Control device, e.g. '/dev/cdc-wdm0'
+    device: String,
+    /// Network device with traffic counters, e.g. 'wwan0'
+    l3_device: Option<String>,
+}
+
+/*
+ * Utils for OpenWrt
+ */
+
+pub struct OpenWrtParser {
+    credentials: Credentials,
+    agent: ureq::Agent,
+    /// Session of rpcd that is reused between polls
+    session: Option<String>,
+    interface: Option<QmiInterface>,
+}
+
+impl OpenWrtParser {
+    pub fn new(credentials: &Credentials) -> Self {
+        Self {
+            credentials: credentials.clone(),
+            agent: new_session_agent(),
+            session: None,
+            interface: None,
+        }
+    }
+
+    /// Call method of ubus object. Returns data of the result
+    fn call(
+        agent: &ureq::Agent,
+        host: &str,
+        session: &str,
+        object: &str,
+        method: &str,
+        args: &serde_json::Value,
+    ) -> Result<serde_json::Value, ModemError> {
+        let endpoint = format!("{UBUS_QUERY} {object} {method}");
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "call",
+            "params": [session, object, method, args],
+        });
+        let mut response = post_url_json_with_agent(agent, host, UBUS_QUERY, &[], &request)
+            .map_err(|e| e.context(&format!("Call of '{object} {method}' failed")))?;
+
+        // Errors of JSON-RPC layer
+        let error = &response["error"];
+        if !error.is_null() {
+            let code = error["code"].as_i64().map(|code| code.to_string());
+            eprintln!("Call of '{object} {method}' error: {error}");
+            return Err(ModemError::new(
+                ModemErrorKind::Access,
+                &format!("Call of '{object} {method}' failed"),
+            )
+            .with_endpoint(&endpoint)
+            .with_vendor_error(VendorError {
+                explanation: code
+                    .as_deref()
+                    .and_then(explain_ubus_status)
+                    .map(std::string::ToString::to_string),
+                code,
+                message: error["message"]
+                    .as_str()
+                    .map(std::string::ToString::to_string),
+            }));
+        }
+
+        // Result is array of ubus status and optional data
+        match response["result"][0].as_i64() {
+            Some(0) => Ok(response["result"][1].take()),
+            status => {
+                let code = status.unwrap_or(-1).to_string();
+                eprintln!("Call of '{object} {method}' returned status {code}");
+                Err(ModemError::new(
+                    ModemErrorKind::Access,
+                    &format!("Call of '{object} {method}' failed"),
+                )
+                .with_endpoint(&endpoint)
+                .with_vendor_error(VendorError {
+                    explanation: explain_ubus_status(&code).map(std::string::ToString::to_string),
+                    code: Some(code),
+                    message: None,
+                }))
+            }
+        }
+    }
+
+    fn login(&self, host: &str) -> Result<String, ModemError> {
+        let username = if self.credentials.username.is_empty() {
+            DEFAULT_USERNAME
+        } else {
+            &self.credentials.username
+        };
+
+        let result = OpenWrtParser::call(
+            &self.agent,
+            host,
+            NULL_SESSION,
+            "session",
+            "login",
+            &json!({"username": username, "password": self.credentials.password}),
+        )
+        .map_err(|e| e.context("Login failed"))?;
+
+        result["ubus_rpc_session"]
+            .as_str()
+            .map(std::string::ToString::to_string)
+            .ok_or_else(|| {
+                eprintln!("Login response doesn't have required field 'ubus_rpc_session'");
+                ModemError::data_parsing("ubus_rpc_session").with_endpoint(UBUS_QUERY)
+            })
+    }
+
+    /// Call method with current session. Session is created again if it has expired
+    fn call_with_session(
+        &mut self,
+        host: &str,
+        object: &str,
+        method: &str,
+        args: &serde_json::Value,
+    ) -> Result<serde_json::Value, ModemError> {
+        if let Some(session) = &self.session {
+            match OpenWrtParser::call(&self.agent, host, session, object, method, args) {
+                Err(e) if is_session_error(&e) => {
+                    eprintln!("Session is not valid, login again");
+                }
+                result => return result,
+            }
+        }
+
+        let session = self.login(host)?;
+        let result = OpenWrtParser::call(&self.agent, host, &session, object, method, args);
+        self.session = Some(session);
+        result
+    }
+
+    /// Run uqmi on the router and parse its JSON output
+    fn exec_uqmi(
+        &mut self,
+        host: &str,
+        device: &str,
+        command: &str,
+    ) -> Result<serde_json::Value, ModemError> {
+        let result = self.call_with_session(
+            host,
+            "file",
+            "exec",
+            &json!({"command": UQMI_COMMAND, "params": ["-d", device, command]}),
+        )?;
+
+        let code = result["code"].as_i64().unwrap_or(0);
+        if code != 0 {
+            let message = result["stderr"].as_str().unwrap_or_default().trim();
+            eprintln!("Command uqmi {command} failed: {message}");
+            return Err(
+                ModemError::access(&format!("Command uqmi {command} failed"))
+                    .with_endpoint(command)
+                    .with_vendor_error(VendorError {
+                        code: Some(code.to_string()),
+                        message: Some(message.to_string()),
+                        explanation: None,
+                    }),
+            );
+        }
+
+        let stdout = result["stdout"].as_str().unwrap_or_default();
+        serde_json::from_str(stdout).map_err(|e| {
+            eprintln!("Cannot parse output of uqmi {command}: {e}");
+            ModemError::data_parsing("JSON").with_endpoint(command)
+        })
+    }
+
+    /// Find interface with 'qmi' protocol and its control device
+    fn find_qmi_interface(&mut self, host: &str) -> QmiInterface {
+        let dump = self
+            .call_with_session(host, "network.interface", "dump", &json!({}))
+            .unwrap_or(serde_json::Value::Null);
+
+        let interface = dump["interface"].as_array().and_then(|interfaces| {
+            interfaces
+                .iter()
+                .find(|interface| interface["proto"].as_str() == Some("qmi"))
+        });
+        let Some(interface) = interface else {
+            eprintln!("Interface with QMI protocol is not found, using {DEFAULT_QMI_DEVICE}");
+            return QmiInterface {
+                device: DEFAULT_QMI_DEVICE.to_string(),
+                l3_device: None,
+            };
+        };
+
+        let name = interface["interface"].as_str().unwrap_or_default();
+        let config = self
+            .call_with_session(
+                host,
+                "uci",
+                "get",
+                &json!({"config": "network", "section": name}),
+            )
+            .unwrap_or(serde_json::Value::Null);
+
+        QmiInterface {
+            device: config["values"]["device"]
+                .as_str()
+                .unwrap_or(DEFAULT_QMI_DEVICE)
+                .to_string(),
+            l3_device: interface["l3_device"]
+                .as_str()
+                .map(std::string::ToString::to_string),
+        }
+    }
+
+    /// Check if host has ubus endpoint of rpcd
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let agent = new_session_agent();
+
+        // Unauthenticated call is rejected by rpcd with ubus status
+        match OpenWrtParser::call(&agent, host, NULL_SESSION, "system", "board", &json!({})) {
+            Ok(board) => Some(OpenWrtParser::parse_device_info_json(&board)),
+            Err(e) if e.vendor_error.is_some() => Some(DeviceInformation::from("OpenWrt", "")),
+            Err(_) => None,
+        }
+    }
+
+    fn parse_device_info_json(json: &serde_json::Value) -> DeviceInformation {
+        let model_str = json["model"].as_str().unwrap_or_default();
+        DeviceInformation::from("OpenWrt", model_str)
+    }
+
+    fn parse_serving_system_json(json: &serde_json::Value) -> PlmnStatus {
+        let plmn_str = match (json["plmn_mcc"].as_i64(), json["plmn_mnc"].as_i64()) {
+            (Some(mcc), Some(mnc)) => format!("{mcc:03}{mnc:02}"),
+            _ => String::new(),
+        };
+        PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string")
+    }
+
+    /// Get cell ID from output of `--get-system-info`. Newer versions of uqmi report
+    /// eNB ID and local cell ID separately
+    fn parse_system_info_json(json: &serde_json::Value, mode: NetworkMode) -> i64 {
+        let system_info = match mode {
+            NetworkMode::Wcdma => &json["wcdma"],
+            _ => &json["lte"],
+        };
+        let cell_id = system_info["cell_id"].as_i64().unwrap_or(0);
+        match system_info["enodeb_id"].as_i64() {
+            Some(enb) => (enb << 8) | cell_id,
+            None => cell_id,
+        }
+    }
+
+    /// Parse carriers of `--get-lte-cphy-ca-info` with keys 'primary', 'secondary_1', ...
+    fn parse_ca_info_json(json: &serde_json::Value) -> Vec<ComponentCarrier> {
+        let Some(json) = json.as_object() else {
+            return Vec::new();
+        };
+
+        json.iter()
+            .filter_map(|(key, carrier)| {
+                let role = if key == "primary" {
+                    CarrierRole::Primary
+                } else if key.starts_with("secondary") {
+                    CarrierRole::Secondary
+                } else {
+                    return None;
+                };
+                let band = carrier["band"]
+                    .as_i64()
+                    .or_else(|| carrier["band"].as_str().and_then(parse_band_number))?;
+                Some(ComponentCarrier {
+                    role,
+                    band,
+                    earfcn: carrier["rx_channel"]
+                        .as_i64()
+                        .or_else(|| carrier["frequency"].as_i64()),
+                    bandwidth: carrier["bandwidth"]
+                        .as_f64()
+                        .or_else(|| carrier["bandwidth"].as_str().and_then(parse_bandwidth_mhz)),
+                    pci: carrier["physical_cell_id"].as_i64(),
+                    rsrp: None,
+                    rsrq: None,
+                    sinr: None,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_signal_info_json(
+        json: &serde_json::Value,
+        cell_id: i64,
+    ) -> Result<(NetworkMode, i64, SignalInfo), ModemError> {
+        let Some(signal_type) = json["type"].as_str() else {
+            eprintln!("Signal info doesn't have required field 'type'");
+            return Err(ModemError::data_parsing("type").with_endpoint("--get-signal-info"));
+        };
+
+        let get_value = |key: &str| json[key].as_f64().map(|val| val.round() as i64);
+        let required_value = |key: &str| {
+            get_value(key).ok_or_else(|| {
+                eprintln!("Signal info doesn't have required field '{key}'");
+                ModemError::data_parsing(key).with_endpoint("--get-signal-info")
+            })
+        };
+        let rssi = get_value("rssi").unwrap_or(0);
+
+        Ok(match signal_type {
+            "lte" => (
+                NetworkMode::Lte,
+                rssi,
+                SignalInfo::Lte(LteSignalInfo {
+                    rsrq: required_value("rsrq")?,
+                    rsrp: required_value("rsrp")?,
+                    sinr: required_value("snr")?,
+                    ca_count: 0,
+                    enb: cell_id >> 8,
+                    id: cell_id & 0xFF,
+                    pci: -1,
+                }),
+            ),
+            "5gnr" | "nr5g" => {
+                let (gnb, id) = NrSignalInfo::split_nci(cell_id);
+                (
+                    NetworkMode::Nr,
+                    rssi,
+                    SignalInfo::Nr(NrSignalInfo {
+                        rsrq: required_value("rsrq")?,
+                        rsrp: required_value("rsrp")?,
+                        sinr: required_value("snr")?,
+                        band: 0,
+                        arfcn: 0,
+                        gnb,
+                        id,
+                        pci: -1,
+                    }),
+                )
+            }
+            "wcdma" | "hdr" | "tdscdma" => {
+                let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
+                let (nb, cc) = (id / 10, id % 10);
+                (
+                    NetworkMode::Wcdma,
+                    rssi,
+                    SignalInfo::Wcdma(WcdmaSignalInfo {
+                        rscp: required_value("rscp")?,
+                        ecio: required_value("ecio")?,
+                        nb,
+                        cc,
+                        rnc,
+                        psc: 0,
+                    }),
+                )
+            }
+            "gsm" => (NetworkMode::Gsm, rssi, SignalInfo::None),
+            _ => (NetworkMode::Unknown, rssi, SignalInfo::None),
+        })
+    }
+
+    fn parse_traffic_statistics_json(json: &serde_json::Value) -> Option<TrafficStatistics> {
+        let statistics = &json["statistics"];
+        // Total number of bytes since start of the interface
+        Some(TrafficStatistics {
+            dl: statistics["rx_bytes"].as_i64()? * 8,
+            ul: statistics["tx_bytes"].as_i64()? * 8,
+        })
+    }
+
+    fn get_modem_status(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        if self.interface.is_none() {
+            self.interface = Some(self.find_qmi_interface(host));
+        }
+        let (device, l3_device) = self
+            .interface
+            .as_ref()
+            .map(|interface| (interface.device.clone(), interface.l3_device.clone()))
+            .expect("Interface is found");
+
+        let signal_json = self
+            .exec_uqmi(host, &device, "--get-signal-info")
+            .map_err(|e| e.context(&format!("Cannot get signal info from host {host}")))?;
+
+        // Optional info
+        let system_info_json = self
+            .exec_uqmi(host, &device, "--get-system-info")
+            .unwrap_or(serde_json::Value::Null);
+        let serving_system_json = self
+            .exec_uqmi(host, &device, "--get-serving-system")
+            .unwrap_or(serde_json::Value::Null);
+
+        let mode_hint = match signal_json["type"].as_str() {
+            Some("wcdma") => NetworkMode::Wcdma,
+            _ => NetworkMode::Lte,
+        };
+        let cell_id = OpenWrtParser::parse_system_info_json(&system_info_json, mode_hint);
+        let (mode, rssi, mut signal_info) =
+            OpenWrtParser::parse_signal_info_json(&signal_json, cell_id)?;
+
+        let mut carriers = Vec::new();
+        if mode == NetworkMode::Lte {
+            let ca_info_json = self
+                .exec_uqmi(host, &device, "--get-lte-cphy-ca-info")
+                .unwrap_or(serde_json::Value::Null);
+            carriers = OpenWrtParser::parse_ca_info_json(&ca_info_json);
+            carriers.sort_by_key(|carrier| carrier.role == CarrierRole::Secondary);
+        }
+
+        let primary_carrier = carriers
+            .iter()
+            .find(|carrier| carrier.role == CarrierRole::Primary)
+            .copied();
+
+        let mut band = ['\0'; 20];
+        let mut channel_info = None;
+        if let Some(primary_carrier) = primary_carrier {
+            copy_string_to_array!(band, format!("LTE B{}", primary_carrier.band));
+
+            channel_info = primary_carrier.earfcn.map(|earfcn| {
+                let (dl_frequency, ul_frequency) = match get_lte_frequencies(earfcn) {
+                    Some((dl, ul)) => (Some(dl), ul),
+                    None => (None, None),
+                };
+                ChannelInfo {
+                    earfcn: Some(earfcn),
+                    dl_frequency,
+                    ul_frequency,
+                    dl_bandwidth: primary_carrier.bandwidth,
+                    ul_bandwidth: None,
+                }
+            });
+
+            if let SignalInfo::Lte(ref mut lte_signal_info) = signal_info {
+                lte_signal_info.ca_count = i64::try_from(carriers.len() - 1).unwrap_or(0);
+                lte_signal_info.pci = primary_carrier.pci.unwrap_or(-1);
+            }
+        } else {
+            carriers.clear();
+        }
+
+        let traffic_statistics = match l3_device {
+            Some(l3_device) => self
+                .call_with_session(
+                    host,
+                    "network.device",
+                    "status",
+                    &json!({"name": l3_device}),
+                )
+                .ok()
+                .and_then(|status| OpenWrtParser::parse_traffic_statistics_json(&status)),
+            None => None,
+        };
+
+        let device_info = self
+            .call_with_session(host, "system", "board", &json!({}))
+            .map_or_else(
+                |_| DeviceInformation::from("OpenWrt", ""),
+                |board| OpenWrtParser::parse_device_info_json(&board),
+            );
+
+        Ok(ModemStatus {
+            mode,
+            plmn: OpenWrtParser::parse_serving_system_json(&serving_system_json),
+            rssi,
+            cell_id,
+            signal_info,
+            band,
+            carriers,
+            channel_info,
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_info,
+            battery_status: None,
+            device_temp: None,
+            traffic_statistics,
+            traffic_mode: TrafficMode::Cumulative,
+        })
+    }
+}
+
+impl ModemInfoParser for OpenWrtParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        self.get_modem_status(host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output of `uqmi --get-signal-info` in LTE mode
+    const LTE_SIGNAL_INFO_JSON: &str =
+        r#"{"type": "lte", "rssi": -67, "rsrq": -11, "rsrp": -97, "snr": 8.4}"#;
+
+    /// Output of `uqmi --get-system-info` with separate eNB ID
+    const SYSTEM_INFO_JSON: &str = r#"{
+        "wcdma": {"service_status": "none"},
+        "lte": {
+            "service_status": "available", "true_service_status": "available",
+            "preferred_data_path": false, "domain": "cs-ps", "service": "cs-ps",
+            "roaming_status": "off", "forbidden": false, "mcc": "262", "mnc": "03",
+            "tracking_area_code": 16134, "enodeb_id": 107173, "cell_id": 17
+        }
+    }"#;
+
+    /// Output of `uqmi --get-lte-cphy-ca-info` with one secondary carrier
+    const CA_INFO_JSON: &str = r#"{
+        "primary": {
+            "physical_cell_id": 293, "rx_channel": 6300, "bandwidth": "10MHz",
+            "band": "E-UTRA band 20"
+        },
+        "secondary_1": {
+            "physical_cell_id": 12, "rx_channel": 1300, "bandwidth": "20MHz",
+            "band": "E-UTRA band 3", "state": "activated"
+        }
+    }"#;
+
+    fn from_json(json: &str) -> serde_json::Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn lte_signal_info() {
+        let cell_id =
+            OpenWrtParser::parse_system_info_json(&from_json(SYSTEM_INFO_JSON), NetworkMode::Lte);
+        assert_eq!(cell_id, (107_173 << 8) | 17);
+
+        let (mode, rssi, signal_info) =
+            OpenWrtParser::parse_signal_info_json(&from_json(LTE_SIGNAL_INFO_JSON), cell_id)
+                .unwrap();
+        assert!(mode == NetworkMode::Lte);
+        assert_eq!(rssi, -67);
+
+        let SignalInfo::Lte(lte_info) = signal_info else {
+            panic!("Signal info is not LTE");
+        };
+        assert_eq!(lte_info.rsrp, -97);
+        assert_eq!(lte_info.rsrq, -11);
+        assert_eq!(lte_info.sinr, 8);
+        assert_eq!(lte_info.enb, 107_173);
+        assert_eq!(lte_info.id, 17);
+    }
+
+    #[test]
+    fn wcdma_signal_info() {
+        let json = from_json(r#"{"type": "wcdma", "rssi": -73, "rscp": -89, "ecio": -6.5}"#);
+        let (mode, _, signal_info) = OpenWrtParser::parse_signal_info_json(&json, 0).unwrap();
+        assert!(mode == NetworkMode::Wcdma);
+
+        let SignalInfo::Wcdma(wcdma_info) = signal_info else {
+            panic!("Signal info is not WCDMA");
+        };
+        assert_eq!(wcdma_info.rscp, -89);
+        assert_eq!(wcdma_info.ecio, -7);
+    }
+
+    #[test]
+    fn missing_signal_value() {
+        let json = from_json(r#"{"type": "lte", "rssi": -67, "rsrp": -97, "snr": 8.4}"#);
+        let Err(e) = OpenWrtParser::parse_signal_info_json(&json, 0) else {
+            panic!("Missing RSRQ is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+
+    #[test]
+    fn serving_system() {
+        let json = from_json(
+            r#"{"registration": "registered", "plmn_mcc": 262, "plmn_mnc": 3,
+                "plmn_description": "o2 - de", "roaming": false}"#,
+        );
+        let plmn = OpenWrtParser::parse_serving_system_json(&json);
+        assert_eq!(plmn.to_string().trim_end_matches('\0'), "26203");
+    }
+
+    #[test]
+    fn ca_info() {
+        let carriers = OpenWrtParser::parse_ca_info_json(&from_json(CA_INFO_JSON));
+        assert_eq!(carriers.len(), 2);
+
+        let primary = &carriers[0];
+        assert!(primary.role == CarrierRole::Primary);
+        assert_eq!(primary.band, 20);
+        assert_eq!(primary.earfcn, Some(6300));
+        assert_eq!(primary.bandwidth, Some(10.0));
+        assert_eq!(primary.pci, Some(293));
+
+        let secondary = &carriers[1];
+        assert!(secondary.role == CarrierRole::Secondary);
+        assert_eq!(secondary.band, 3);
+        assert_eq!(secondary.earfcn, Some(1300));
+    }
+
+    #[test]
+    fn traffic_statistics() {
+        let json =
+            from_json(r#"{"up": true, "statistics": {"rx_bytes": 1048576, "tx_bytes": 65536}}"#);
+        let traffic_statistics = OpenWrtParser::parse_traffic_statistics_json(&json).unwrap();
+        assert_eq!(traffic_statistics.dl, 8_388_608);
+        assert_eq!(traffic_statistics.ul, 524_288);
+    }
+
+    #[test]
+    fn session_errors() {
+        let error = |code: &str| {
+            ModemError::access("Call failed").with_vendor_error(VendorError {
+                code: Some(code.to_string()),
+                message: None,
+                explanation: None,
+            })
+        };
+        assert!(is_session_error(&error("6")));
+        assert!(is_session_error(&error("-32002")));
+        assert!(!is_session_error(&error("4")));
+    }
+}
@@ -0,0 +1,274 @@
+/*
+ * Registry of modem parser backends
+ */
+use crate::alcatel_parser::AlcatelParser;
+use crate::at_parser::{AtDialect, AtParser, is_serial_device};
+use crate::fritzbox_parser::FritzBoxParser;
+use crate::huawei_parser::HuaweiParser;
+use crate::inseego_parser::InseegoParser;
+use crate::mikrotik_parser::MikrotikParser;
+use crate::modem_utils::{DeviceInformation, ModemInfoParser, ParserOptions};
+use crate::modemmanager_parser::ModemManagerParser;
+use crate::netgear_parser::NetgearParser;
+use crate::openwrt_parser::OpenWrtParser;
+use crate::teltonika_parser::TeltonikaParser;
+use crate::tplink_parser::TpLinkParser;
+use crate::zte_parser::ZteParser;
+
+/// Features of backend that affect UI
+pub struct ParserCapabilities {
+    /// Login to web UI uses username
+    pub username: bool,
+    /// Login to web UI uses password
+    pub password: bool,
+    /// Router can have several modems (or LTE interfaces) that are selected by ID
+    pub modem_id: bool,
+}
+
+/// How backend reaches the modem
+#[derive(Clone, Copy, PartialEq)]
+pub enum BackendKind {
+    /// HTTP API of web UI
+    WebUi,
+    /// AT commands over serial port or TCP socket
+    AtPort(AtDialect),
+    /// Local command line tool
+    Command,
+}
+
+/// Named backend for getting modem info
+pub struct ParserBackend {
+    /// Display name in Manufacturer choice
+    pub name: &'static str,
+    pub kind: BackendKind,
+    /// Standard addresses of the modem web UI or AT port
+    pub default_hosts: &'static [&'static str],
+    pub capabilities: ParserCapabilities,
+    /// Create new parser instance for polling session
+    pub create: fn(&ParserOptions) -> Box<dyn ModemInfoParser>,
+    /// Probe host for web UI of the backend and get model of the modem.
+    /// Only web UI backends are probed
+    pub detect: Option<fn(&str) -> Option<DeviceInformation>>,
+}
+
+pub static PARSER_BACKENDS: [ParserBackend; 15] = [
+    ParserBackend {
+        name: "Netgear",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.1.1"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: true,
+            modem_id: false,
+        },
+        create: |options| Box::new(NetgearParser::new(options)),
+        detect: Some(NetgearParser::detect),
+    },
+    ParserBackend {
+        name: "Huawei",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.8.1"],
+        capabilities: ParserCapabilities {
+            username: true,
+            password: true,
+            modem_id: false,
+        },
+        create: |options| Box::new(HuaweiParser::new(&options.credentials)),
+        detect: Some(HuaweiParser::detect),
+    },
+    ParserBackend {
+        name: "ZTE",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.0.1"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: true,
+            modem_id: false,
+        },
+        create: |options| Box::new(ZteParser::new(&options.credentials)),
+        detect: Some(ZteParser::detect),
+    },
+    ParserBackend {
+        name: "TP-Link",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.0.1"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: true,
+            modem_id: false,
+        },
+        create: |options| Box::new(TpLinkParser::new(&options.credentials)),
+        detect: Some(TpLinkParser::detect),
+    },
+    ParserBackend {
+        name: "Alcatel",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.1.1"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: false,
+            modem_id: false,
+        },
+        create: |_| Box::new(AlcatelParser::new()),
+        detect: Some(AlcatelParser::detect),
+    },
+    ParserBackend {
+        name: "Inseego",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.1.1"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: true,
+            modem_id: false,
+        },
+        create: |options| Box::new(InseegoParser::new(&options.credentials)),
+        detect: Some(InseegoParser::detect),
+    },
+    ParserBackend {
+        name: "OpenWrt",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.1.1"],
+        capabilities: ParserCapabilities {
+            username: true,
+            password: true,
+            modem_id: false,
+        },
+        create: |options| Box::new(OpenWrtParser::new(&options.credentials)),
+        detect: Some(OpenWrtParser::detect),
+    },
+    ParserBackend {
+        name: "Teltonika",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.1.1"],
+        capabilities: ParserCapabilities {
+            username: true,
+            password: true,
+            modem_id: true,
+        },
+        create: |options| Box::new(TeltonikaParser::new(options)),
+        detect: Some(TeltonikaParser::detect),
+    },
+    ParserBackend {
+        name: "MikroTik",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.88.1"],
+        capabilities: ParserCapabilities {
+            username: true,
+            password: true,
+            modem_id: true,
+        },
+        create: |options| Box::new(MikrotikParser::new(options)),
+        detect: Some(MikrotikParser::detect),
+    },
+    ParserBackend {
+        name: "FRITZ!Box",
+        kind: BackendKind::WebUi,
+        default_hosts: &["192.168.178.1"],
+        capabilities: ParserCapabilities {
+            username: true,
+            password: true,
+            modem_id: false,
+        },
+        create: |options| Box::new(FritzBoxParser::new(&options.credentials)),
+        detect: Some(FritzBoxParser::detect),
+    },
+    ParserBackend {
+        name: "ModemManager",
+        kind: BackendKind::Command,
+        default_hosts: &["any"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: false,
+            modem_id: false,
+        },
+        create: |options| Box::new(ModemManagerParser::new(options)),
+        detect: None,
+    },
+    ParserBackend {
+        name: "AT (Quectel)",
+        kind: BackendKind::AtPort(AtDialect::Quectel),
+        default_hosts: &["/dev/ttyUSB2"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: false,
+            modem_id: false,
+        },
+        create: |_| Box::new(AtParser::new(AtDialect::Quectel)),
+        detect: None,
+    },
+    ParserBackend {
+        name: "AT (Sierra)",
+        kind: BackendKind::AtPort(AtDialect::Sierra),
+        default_hosts: &["/dev/ttyUSB2"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: false,
+            modem_id: false,
+        },
+        create: |_| Box::new(AtParser::new(AtDialect::Sierra)),
+        detect: None,
+    },
+    ParserBackend {
+        name: "AT (Huawei)",
+        kind: BackendKind::AtPort(AtDialect::Huawei),
+        default_hosts: &["/dev/ttyUSB2"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: false,
+            modem_id: false,
+        },
+        create: |_| Box::new(AtParser::new(AtDialect::Huawei)),
+        detect: None,
+    },
+    ParserBackend {
+        name: "AT (3GPP)",
+        kind: BackendKind::AtPort(AtDialect::Generic),
+        default_hosts: &["/dev/ttyUSB2"],
+        capabilities: ParserCapabilities {
+            username: false,
+            password: false,
+            modem_id: false,
+        },
+        create: |_| Box::new(AtParser::new(AtDialect::Generic)),
+        detect: None,
+    },
+];
+
+/// Get backend by index of Manufacturer choice
+pub fn get_backend(id: i32) -> Option<&'static ParserBackend> {
+    usize::try_from(id)
+        .ok()
+        .and_then(|id| PARSER_BACKENDS.get(id))
+}
+
+/// Find backend that supports the modem on host. Web UI backends are probed in order
+/// of the registry. Serial port is probed once for AT dialect. Local commands are not run
+pub fn detect_backend(host: &str) -> Option<(&'static ParserBackend, DeviceInformation)> {
+    if is_serial_device(host) {
+        eprintln!("Probing serial port {host} for AT dialect");
+        let (dialect, device_info) = AtParser::detect_dialect(host)?;
+        return PARSER_BACKENDS
+            .iter()
+            .find(|backend| backend.kind == BackendKind::AtPort(dialect))
+            .map(|backend| (backend, device_info));
+    }
+
+    PARSER_BACKENDS.iter().find_map(|backend| {
+        let detect = backend.detect?;
+        eprintln!("Probing host {host} for {} modem", backend.name);
+        detect(host).map(|device_info| (backend, device_info))
+    })
+}
+
+/// List of standard addresses of all backends without duplicates
+pub fn get_default_hosts() -> Vec<&'static str> {
+    let mut hosts: Vec<&'static str> = Vec::new();
+    for backend in &PARSER_BACKENDS {
+        for host in backend.default_hosts {
+            if !hosts.contains(host) {
+                hosts.push(host);
+            }
+        }
+    }
+    hosts
+}
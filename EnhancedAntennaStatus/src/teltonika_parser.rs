@@ -0,0 +1,499 @@
+#![allow(clippy::similar_names)]
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::bandwidth_utils::TrafficMode;
+use crate::earfcn_utils::get_lte_frequencies;
+use crate::modem_utils::{
+    CarrierRole, ChannelInfo, ComponentCarrier, DeviceInformation, DeviceTemperature,
+    LteSignalInfo, ModemError, ModemInfoParser, ModemStatus, NetworkMode, NrSignalInfo,
+    ParserOptions, PlmnStatus, SignalInfo, WcdmaSignalInfo,
+};
+use crate::network_utils::{
+    get_url_json_with_bearer_token, new_session_agent, post_url_json_with_agent,
+};
+use crate::utils::{copy_string_to_array, parse_band_number, parse_bandwidth_mhz};
+
+const LOGIN_QUERY: &str = "/api/login";
+const MODEMS_STATUS_QUERY: &str = "/api/modems/status";
+
+const DEFAULT_USERNAME: &str = "admin";
+
+/// Token is refreshed earlier than it expires to avoid failed requests
+const TOKEN_EXPIRATION_MARGIN: Duration = Duration::from_secs(10);
+
+/// Parse numeric parameter that may be reported either as number or as string
+fn get_teltonika_i64(val: &serde_json::Value) -> Option<i64> {
+    val.as_i64()
+        .or_else(|| val.as_f64().map(|val| val.round() as i64))
+        .or_else(|| val.as_str().and_then(|val| val.trim().parse::<i64>().ok()))
+}
+
+/// Get required numeric field of modem status
+fn required_teltonika_i64(modem: &serde_json::Value, name: &str) -> Result<i64, ModemError> {
+    get_teltonika_i64(&modem[name]).ok_or_else(|| {
+        eprintln!("Modem status doesn't have required field '{name}'");
+        ModemError::data_parsing(name).with_endpoint(MODEMS_STATUS_QUERY)
+    })
+}
+
+/// Convert connection type (e.g. 'LTE', '5G-NSA', '3G') to `NetworkMode` enum
+fn get_mode_by_conntype(conntype: &str) -> NetworkMode {
+    let conntype = conntype.to_uppercase();
+    if conntype.contains("NSA") {
+        NetworkMode::NrNsa
+    } else if conntype.contains("5G") || conntype.contains("NR") {
+        NetworkMode::Nr
+    } else if conntype.contains("LTE") || conntype.contains("4G") {
+        NetworkMode::Lte
+    } else if ["3G", "WCDMA", "UMTS", "HSPA"]
+        .iter()
+        .any(|val| conntype.contains(val))
+    {
+        NetworkMode::Wcdma
+    } else if ["2G", "GSM", "EDGE", "GPRS"]
+        .iter()
+        .any(|val| conntype.contains(val))
+    {
+        NetworkMode::Gsm
+    } else {
+        NetworkMode::Unknown
+    }
+}
+
+/// Bearer token of REST API session
+struct TeltonikaToken {
+    token: String,
+    expires_at: Instant,
+}
+
+/*
+ * Utils for Teltonika
+ */
+
+pub struct TeltonikaParser {
+    options: ParserOptions,
+    agent: ureq::Agent,
+    /// Token that is reused between polls until it expires
+    token: Option<TeltonikaToken>,
+}
+
+impl TeltonikaParser {
+    pub fn new(options: &ParserOptions) -> Self {
+        Self {
+            options: options.clone(),
+            agent: new_session_agent(),
+            token: None,
+        }
+    }
+
+    fn login(&self, host: &str) -> Result<TeltonikaToken, ModemError> {
+        let credentials = &self.options.credentials;
+        if credentials.password.is_empty() {
+            eprintln!("Login is required but password is not set");
+            return Err(
+                ModemError::access("Login is required but password is not set")
+                    .with_endpoint(LOGIN_QUERY),
+            );
+        }
+        let username = if credentials.username.is_empty() {
+            DEFAULT_USERNAME
+        } else {
+            &credentials.username
+        };
+
+        let response = post_url_json_with_agent(
+            &self.agent,
+            host,
+            LOGIN_QUERY,
+            &[],
+            &json!({"username": username, "password": credentials.password}),
+        )
+        .map_err(|e| {
+            // Rejected credentials are not retried because RutOS blocks repeated login
+            if e.http_status == Some(401) {
+                eprintln!("Credentials are rejected");
+                ModemError::authentication("Credentials are rejected")
+                    .with_endpoint(LOGIN_QUERY)
+                    .with_http_status(401)
+            } else {
+                e.context("Login failed")
+            }
+        })?;
+
+        let data = &response["data"];
+        let Some(token) = data["token"].as_str() else {
+            eprintln!("Login response doesn't have required field 'token'");
+            return Err(ModemError::data_parsing("token").with_endpoint(LOGIN_QUERY));
+        };
+        let expires = data["expires"]
+            .as_u64()
+            .map_or(Duration::ZERO, Duration::from_secs);
+
+        Ok(TeltonikaToken {
+            token: token.to_string(),
+            expires_at: Instant::now() + expires.saturating_sub(TOKEN_EXPIRATION_MARGIN),
+        })
+    }
+
+    /// Get JSON with valid token. Token is refreshed if it expired or was rejected
+    fn get_json(&mut self, host: &str, query: &str) -> Result<serde_json::Value, ModemError> {
+        if let Some(token) = &self.token
+            && Instant::now() < token.expires_at
+        {
+            match get_url_json_with_bearer_token(&self.agent, host, &token.token, query) {
+                Err(e) if e.http_status == Some(401) => {
+                    eprintln!("Token was rejected, login again");
+                }
+                result => return result,
+            }
+        }
+
+        let token = self.login(host)?;
+        let result = get_url_json_with_bearer_token(&self.agent, host, &token.token, query);
+        self.token = Some(token);
+        result
+    }
+
+    /// Check if host has REST API of RutOS. Request without token is rejected with JSON error
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let agent = new_session_agent();
+        match get_url_json_with_bearer_token(&agent, host, "", MODEMS_STATUS_QUERY) {
+            Ok(json) if json["success"].is_boolean() => {}
+            Err(e) if e.http_status == Some(401) => {}
+            _ => return None,
+        }
+        Some(DeviceInformation::from("Teltonika", ""))
+    }
+
+    /// Select modem by ID. Empty ID selects the first modem
+    fn select_modem<'a>(
+        modems: &'a [serde_json::Value],
+        modem_id: &str,
+    ) -> Result<&'a serde_json::Value, ModemError> {
+        let modem = if modem_id.is_empty() {
+            modems.first()
+        } else {
+            modems
+                .iter()
+                .find(|modem| modem["id"].as_str() == Some(modem_id))
+        };
+
+        modem.ok_or_else(|| {
+            let ids = modems
+                .iter()
+                .filter_map(|modem| modem["id"].as_str())
+                .collect::<Vec<&str>>()
+                .join(", ");
+            eprintln!("Modem '{modem_id}' is not found. Available modems: {ids}");
+            ModemError::access(&format!(
+                "Modem '{modem_id}' is not found. Available modems: {ids}"
+            ))
+            .with_endpoint(MODEMS_STATUS_QUERY)
+        })
+    }
+
+    fn parse_carrier_json(json: &serde_json::Value, role: CarrierRole) -> Option<ComponentCarrier> {
+        let band = json["band"]
+            .as_str()
+            .and_then(parse_band_number)
+            .or_else(|| get_teltonika_i64(&json["band"]))?;
+        Some(ComponentCarrier {
+            role,
+            band,
+            earfcn: get_teltonika_i64(&json["earfcn"]),
+            bandwidth: json["bandwidth"]
+                .as_str()
+                .and_then(parse_bandwidth_mhz)
+                .or_else(|| json["bandwidth"].as_f64()),
+            pci: get_teltonika_i64(&json["pcid"]).or_else(|| get_teltonika_i64(&json["pci"])),
+            rsrp: get_teltonika_i64(&json["rsrp"]),
+            rsrq: get_teltonika_i64(&json["rsrq"]),
+            sinr: get_teltonika_i64(&json["sinr"]),
+        })
+    }
+
+    fn parse_modem_json(modem: &serde_json::Value) -> Result<ModemStatus, ModemError> {
+        let Some(conntype) = modem["conntype"].as_str() else {
+            eprintln!("Modem status doesn't have required field 'conntype'");
+            return Err(ModemError::data_parsing("conntype").with_endpoint(MODEMS_STATUS_QUERY));
+        };
+        let mode = get_mode_by_conntype(conntype);
+
+        let rssi = get_teltonika_i64(&modem["rssi"]).unwrap_or(0);
+
+        let plmn_str = get_teltonika_i64(&modem["opernum"])
+            .map(|opernum| opernum.to_string())
+            .unwrap_or_default();
+        let plmn = PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string");
+
+        let cell_id = get_teltonika_i64(&modem["cellid"]).unwrap_or(0);
+        let pci = get_teltonika_i64(&modem["pci"]).unwrap_or(-1);
+
+        // Primary carrier is the serving cell, secondary carriers are reported separately
+        let mut carriers = Vec::new();
+        if let Some(primary_carrier) =
+            TeltonikaParser::parse_carrier_json(modem, CarrierRole::Primary)
+            && mode != NetworkMode::Wcdma
+            && mode != NetworkMode::Gsm
+        {
+            carriers.push(primary_carrier);
+            if let Some(ca_signal) = modem["ca_signal"].as_array() {
+                carriers.extend(ca_signal.iter().filter_map(|carrier| {
+                    TeltonikaParser::parse_carrier_json(carrier, CarrierRole::Secondary)
+                }));
+            }
+        }
+
+        let mut band = ['\0'; 20];
+        if let Some(band_str) = modem["band"].as_str() {
+            copy_string_to_array!(band, band_str);
+        }
+
+        // Signal of LTE or SA NR serving cell is reported in the same fields
+        let get_lte_signal_info = || -> Result<LteSignalInfo, ModemError> {
+            Ok(LteSignalInfo {
+                rsrq: required_teltonika_i64(modem, "rsrq")?,
+                rsrp: required_teltonika_i64(modem, "rsrp")?,
+                sinr: required_teltonika_i64(modem, "sinr")?,
+                ca_count: i64::try_from(carriers.len().saturating_sub(1)).unwrap_or(0),
+                enb: cell_id >> 8,
+                id: cell_id & 0xFF,
+                pci,
+            })
+        };
+
+        let signal_info = match mode {
+            NetworkMode::Nr => {
+                let lte_signal_info = get_lte_signal_info()?;
+                let (gnb, id) = NrSignalInfo::split_nci(cell_id);
+                SignalInfo::Nr(NrSignalInfo {
+                    rsrq: lte_signal_info.rsrq,
+                    rsrp: lte_signal_info.rsrp,
+                    sinr: lte_signal_info.sinr,
+                    band: carriers.first().map_or(0, |carrier| carrier.band),
+                    arfcn: get_teltonika_i64(&modem["earfcn"]).unwrap_or(0),
+                    gnb,
+                    id,
+                    pci,
+                })
+            }
+            NetworkMode::NrNsa => SignalInfo::NrNsa(
+                get_lte_signal_info()?,
+                NrSignalInfo {
+                    rsrq: required_teltonika_i64(modem, "nr_rsrq")?,
+                    rsrp: required_teltonika_i64(modem, "nr_rsrp")?,
+                    sinr: required_teltonika_i64(modem, "nr_sinr")?,
+                    band: 0,
+                    arfcn: 0,
+                    gnb: 0,
+                    id: 0,
+                    pci: -1,
+                },
+            ),
+            NetworkMode::Lte => SignalInfo::Lte(get_lte_signal_info()?),
+            NetworkMode::Wcdma => {
+                let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
+                let (nb, cc) = (id / 10, id % 10);
+                SignalInfo::Wcdma(WcdmaSignalInfo {
+                    rscp: required_teltonika_i64(modem, "rscp")?,
+                    ecio: required_teltonika_i64(modem, "ecio")?,
+                    nb,
+                    cc,
+                    rnc,
+                    psc: 0,
+                })
+            }
+            _ => SignalInfo::None,
+        };
+
+        let channel_info = carriers
+            .first()
+            .filter(|_| mode == NetworkMode::Lte || mode == NetworkMode::NrNsa)
+            .and_then(|carrier| carrier.earfcn.map(|earfcn| (earfcn, carrier.bandwidth)))
+            .map(|(earfcn, bandwidth)| {
+                let (dl_frequency, ul_frequency) = match get_lte_frequencies(earfcn) {
+                    Some((dl, ul)) => (Some(dl), ul),
+                    None => (None, None),
+                };
+                ChannelInfo {
+                    earfcn: Some(earfcn),
+                    dl_frequency,
+                    ul_frequency,
+                    dl_bandwidth: bandwidth,
+                    ul_bandwidth: None,
+                }
+            });
+
+        // Details of carriers are shown only for carrier aggregation
+        if carriers.len() < 2 {
+            carriers.clear();
+        }
+
+        let device_temp =
+            get_teltonika_i64(&modem["temperature"]).map(|device_temp| DeviceTemperature {
+                device_temp,
+                battery_temp: None,
+            });
+
+        let manufacturer_str = modem["manufacturer"].as_str().unwrap_or("Teltonika");
+        let model_str = modem["model"].as_str().unwrap_or_default();
+
+        Ok(ModemStatus {
+            mode,
+            plmn,
+            rssi,
+            cell_id,
+            signal_info,
+            band,
+            carriers,
+            channel_info,
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_info: DeviceInformation::from(manufacturer_str, model_str),
+            battery_status: None,
+            device_temp,
+            traffic_statistics: None,
+            traffic_mode: TrafficMode::Absolute,
+        })
+    }
+}
+
+impl ModemInfoParser for TeltonikaParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        let status = self
+            .get_json(host, MODEMS_STATUS_QUERY)
+            .map_err(|e| e.context(&format!("Cannot get modem status from host {host}")))?;
+
+        let Some(modems) = status["data"].as_array() else {
+            eprintln!("Modem status doesn't have required field 'data'");
+            return Err(ModemError::data_parsing("data").with_endpoint(MODEMS_STATUS_QUERY));
+        };
+        let modem = TeltonikaParser::select_modem(modems, &self.options.modem_id)?;
+
+        TeltonikaParser::parse_modem_json(modem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_utils::ModemErrorKind;
+
+    /// Response of `/api/modems/status` of RUTX50 with internal LTE-A modem and 5G modem
+    const MODEMS_STATUS_JSON: &str = r#"{
+        "success": true,
+        "data": [
+            {
+                "id": "1-1", "name": "Internal modem", "manufacturer": "Quectel",
+                "model": "EG06", "conntype": "LTE", "rssi": -64, "rsrp": -95, "rsrq": -10,
+                "sinr": 12, "opernum": 24602, "operator": "Telia", "cellid": 27436305,
+                "pci": "293", "band": "LTE B20", "earfcn": 6300, "bandwidth": "10 MHz",
+                "temperature": 41,
+                "ca_signal": [
+                    {"band": "LTE B3", "earfcn": 1300, "bandwidth": "20 MHz", "pcid": 12,
+                     "rsrp": -101, "rsrq": -12, "sinr": 8}
+                ]
+            },
+            {
+                "id": "2-1", "name": "5G modem", "manufacturer": "Quectel", "model": "RG501Q",
+                "conntype": "5G-NSA", "rssi": -58, "rsrp": -88, "rsrq": -9, "sinr": 15,
+                "nr_rsrp": -92, "nr_rsrq": -11, "nr_sinr": "7", "opernum": 24602,
+                "cellid": 27436305, "band": "LTE B3", "earfcn": 1300
+            }
+        ]
+    }"#;
+
+    fn get_modems() -> Vec<serde_json::Value> {
+        let status: serde_json::Value = serde_json::from_str(MODEMS_STATUS_JSON).unwrap();
+        status["data"].as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn lte_modem() {
+        let modems = get_modems();
+        let modem = TeltonikaParser::select_modem(&modems, "").unwrap();
+        let modem_status = TeltonikaParser::parse_modem_json(modem).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE-A");
+        assert_eq!(modem_status.get_plmn().trim_end_matches('\0'), "24602");
+        assert_eq!(modem_status.get_band(), "LTE B20+B3");
+        assert_eq!(
+            modem_status.get_frequencies().as_deref(),
+            Some("806.0/847.0 MHz")
+        );
+        assert_eq!(modem_status.get_bandwidths().as_deref(), Some("10 MHz"));
+        assert_eq!(
+            modem_status
+                .device_temp
+                .map(|device_temp| device_temp.device_temp),
+            Some(41)
+        );
+
+        let SignalInfo::Lte(lte_info) = modem_status.signal_info else {
+            panic!("Signal info is not LTE");
+        };
+        assert_eq!(lte_info.rsrp, -95);
+        assert_eq!(lte_info.rsrq, -10);
+        assert_eq!(lte_info.sinr, 12);
+        assert_eq!(lte_info.pci, 293);
+        assert_eq!(lte_info.ca_count, 1);
+
+        assert_eq!(modem_status.carriers.len(), 2);
+        let secondary = &modem_status.carriers[1];
+        assert!(secondary.role == CarrierRole::Secondary);
+        assert_eq!(secondary.band, 3);
+        assert_eq!(secondary.pci, Some(12));
+        assert_eq!(secondary.rsrp, Some(-101));
+    }
+
+    #[test]
+    fn nsa_modem() {
+        let modems = get_modems();
+        let modem = TeltonikaParser::select_modem(&modems, "2-1").unwrap();
+        let modem_status = TeltonikaParser::parse_modem_json(modem).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE+NR");
+        assert!(modem_status.carriers.is_empty());
+
+        let SignalInfo::NrNsa(lte_info, nr_info) = modem_status.signal_info else {
+            panic!("Signal info is not NSA");
+        };
+        assert_eq!(lte_info.rsrp, -88);
+        assert_eq!(nr_info.rsrp, -92);
+        assert_eq!(nr_info.rsrq, -11);
+        assert_eq!(nr_info.sinr, 7);
+    }
+
+    #[test]
+    fn unknown_modem_id() {
+        let modems = get_modems();
+        let Err(e) = TeltonikaParser::select_modem(&modems, "3-1") else {
+            panic!("Unknown modem is selected");
+        };
+        assert_eq!(e.kind, ModemErrorKind::Access);
+        assert!(e.summary().contains("1-1, 2-1"));
+    }
+
+    #[test]
+    fn missing_signal_value() {
+        let mut modem = get_modems()[0].clone();
+        modem["sinr"] = serde_json::Value::Null;
+        let Err(e) = TeltonikaParser::parse_modem_json(&modem) else {
+            panic!("Missing SINR is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+
+    #[test]
+    fn connection_types() {
+        assert!(get_mode_by_conntype("5G-NSA") == NetworkMode::NrNsa);
+        assert!(get_mode_by_conntype("5G-SA") == NetworkMode::Nr);
+        assert!(get_mode_by_conntype("LTE") == NetworkMode::Lte);
+        assert!(get_mode_by_conntype("3G") == NetworkMode::Wcdma);
+        assert!(get_mode_by_conntype("EDGE") == NetworkMode::Gsm);
+    }
+}
@@ -0,0 +1,591 @@
+#![allow(clippy::similar_names)]
+
+use std::str::FromStr;
+
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, block_padding::Pkcs7};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use rand::Rng;
+use rsa::{BigUint, Pkcs1v15Encrypt, RsaPublicKey};
+use serde_json::json;
+
+use crate::bandwidth_utils::{TrafficMode, TrafficStatistics};
+use crate::earfcn_utils::get_lte_frequencies;
+use crate::modem_utils::{
+    BatteryStatus, ChannelInfo, Credentials, DeviceInformation, LteSignalInfo, ModemError,
+    ModemInfoParser, ModemStatus, NetworkMode, PlmnStatus, SignalInfo, VendorError,
+    WcdmaSignalInfo,
+};
+use crate::network_utils::{new_session_agent, post_url_json_with_agent};
+use crate::utils::{
+    copy_string_to_array, from_hex_string, md5_hex, parse_band_number, to_hex_string,
+};
+
+type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+const AUTH_QUERY: &str = "/cgi-bin/auth_cgi";
+const WEB_QUERY: &str = "/cgi-bin/web_cgi";
+
+/// Web UI of MiFi devices has the only user
+const TPLINK_USERNAME: &str = "admin";
+
+/// Size of chunk of signature for RSA with PKCS#1 v1.5 padding and 512 bit key
+const RSA_CHUNK_SIZE: usize = 53;
+
+fn get_mode_by_network_type(network_type: i64) -> NetworkMode {
+    match network_type {
+        1 => NetworkMode::Gsm,
+        2 => NetworkMode::Wcdma,
+        3 => NetworkMode::Lte,
+        _ => NetworkMode::Unknown,
+    }
+}
+
+/// Random string of decimal digits that is used as AES key and IV
+fn random_digits() -> String {
+    let mut rng = rand::thread_rng();
+    (0..16)
+        .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+        .collect()
+}
+
+fn missing_field(name: &str) -> ModemError {
+    eprintln!("WAN status doesn't have required field '{name}'");
+    ModemError::data_parsing(name).with_endpoint(WEB_QUERY)
+}
+
+/// Get required numeric field of WAN status
+fn required_i64(wan: &serde_json::Value, name: &str) -> Result<i64, ModemError> {
+    wan[name].as_i64().ok_or_else(|| missing_field(name))
+}
+
+/*
+ * Encryption of web UI requests on newer firmwares
+ */
+
+/// Request data is encrypted with AES-128-CBC using random key. The key and hash of
+/// credentials are sent in the signature encrypted with RSA key of the device
+struct TpLinkCipher {
+    key: String,
+    iv: String,
+    rsa_key: RsaPublicKey,
+    /// Sequence number from authenticator
+    seq: i64,
+    /// Hash of username and password
+    hash: String,
+}
+
+impl TpLinkCipher {
+    fn new(rsa_modulus: &str, rsa_exponent: &str, seq: i64, password: &str) -> Option<Self> {
+        let to_biguint = |s: &str| {
+            let s = if s.len().is_multiple_of(2) {
+                s.to_string()
+            } else {
+                format!("0{s}")
+            };
+            from_hex_string(&s).map(|bytes| BigUint::from_bytes_be(&bytes))
+        };
+
+        let rsa_key = RsaPublicKey::new(to_biguint(rsa_modulus)?, to_biguint(rsa_exponent)?)
+            .inspect_err(|e| eprintln!("Invalid RSA key: {e}"))
+            .ok()?;
+
+        Some(Self {
+            key: random_digits(),
+            iv: random_digits(),
+            rsa_key,
+            seq,
+            hash: md5_hex(&format!("{TPLINK_USERNAME}{password}")),
+        })
+    }
+    fn encrypt(&self, data: &str) -> String {
+        let encrypted = Aes128CbcEnc::new(self.key.as_bytes().into(), self.iv.as_bytes().into())
+            .encrypt_padded_vec_mut::<Pkcs7>(data.as_bytes());
+        BASE64.encode(encrypted)
+    }
+    fn decrypt(&self, data: &str) -> Option<String> {
+        let encrypted = BASE64.decode(data).ok()?;
+        let decrypted = Aes128CbcDec::new(self.key.as_bytes().into(), self.iv.as_bytes().into())
+            .decrypt_padded_vec_mut::<Pkcs7>(&encrypted)
+            .ok()?;
+        String::from_utf8(decrypted).ok()
+    }
+    /// Signature of request. AES key is sent only with login request
+    fn sign(&self, data_len: usize, is_login: bool) -> String {
+        let seq = self.seq + i64::try_from(data_len).unwrap_or_default();
+        let sign_data = if is_login {
+            format!("key={}&iv={}&h={}&s={seq}", self.key, self.iv, self.hash)
+        } else {
+            format!("h={}&s={seq}", self.hash)
+        };
+
+        let mut rng = rand::thread_rng();
+        sign_data
+            .as_bytes()
+            .chunks(RSA_CHUNK_SIZE)
+            .filter_map(|chunk| self.rsa_key.encrypt(&mut rng, Pkcs1v15Encrypt, chunk).ok())
+            .map(|encrypted| to_hex_string(&encrypted))
+            .collect()
+    }
+}
+
+/// Session of web UI that is kept between polls
+struct TpLinkSession {
+    token: String,
+    /// Older firmwares don't encrypt requests
+    cipher: Option<TpLinkCipher>,
+}
+
+/*
+ * Utils for TP-Link
+ */
+
+/// Parser of MiFi web UI (M7350, M7650). MR-series routers have other API
+pub struct TpLinkParser {
+    credentials: Credentials,
+    agent: ureq::Agent,
+    session: Option<TpLinkSession>,
+}
+
+impl TpLinkParser {
+    pub fn new(credentials: &Credentials) -> Self {
+        Self {
+            credentials: credentials.clone(),
+            agent: new_session_agent(),
+            session: None,
+        }
+    }
+
+    /// Send request to web UI, encrypt it and decrypt response when session is encrypted
+    fn post_request(
+        agent: &ureq::Agent,
+        host: &str,
+        cipher: Option<&TpLinkCipher>,
+        query: &str,
+        request: &serde_json::Value,
+        is_login: bool,
+    ) -> Result<serde_json::Value, ModemError> {
+        let Some(cipher) = cipher else {
+            return post_url_json_with_agent(agent, host, query, &[], request);
+        };
+
+        let data = cipher.encrypt(&request.to_string());
+        let sign = cipher.sign(data.len(), is_login);
+
+        let response = post_url_json_with_agent(
+            agent,
+            host,
+            query,
+            &[],
+            &json!({"data": data, "sign": sign}),
+        )?;
+
+        response["data"]
+            .as_str()
+            .and_then(|data| cipher.decrypt(data))
+            .and_then(|data| serde_json::from_str::<serde_json::Value>(&data).ok())
+            .ok_or_else(|| {
+                eprintln!("Cannot decrypt response of '{query}'");
+                ModemError::data_parsing("encrypted response").with_endpoint(query)
+            })
+    }
+
+    /// Get authenticator parameters: nonce for password digest and RSA key on newer firmwares
+    fn get_authenticator(agent: &ureq::Agent, host: &str) -> Result<serde_json::Value, ModemError> {
+        post_url_json_with_agent(
+            agent,
+            host,
+            AUTH_QUERY,
+            &[],
+            &json!({"module": "authenticator", "action": 0}),
+        )
+    }
+
+    fn login(
+        agent: &ureq::Agent,
+        host: &str,
+        credentials: &Credentials,
+    ) -> Result<TpLinkSession, ModemError> {
+        if credentials.password.is_empty() {
+            eprintln!("Login is required but password is not set");
+            return Err(ModemError::access(
+                "Login is required but password is not set",
+            ));
+        }
+
+        let authenticator = TpLinkParser::get_authenticator(agent, host)?;
+
+        let Some(nonce) = authenticator["nonce"].as_str() else {
+            eprintln!("Authenticator doesn't have nonce");
+            return Err(ModemError::data_parsing("nonce").with_endpoint(AUTH_QUERY));
+        };
+
+        let cipher = match (
+            authenticator["rsaMod"].as_str(),
+            authenticator["rsaPubKey"].as_str(),
+        ) {
+            (Some(rsa_modulus), Some(rsa_exponent)) => Some(
+                TpLinkCipher::new(
+                    rsa_modulus,
+                    rsa_exponent,
+                    authenticator["seqNum"].as_i64().unwrap_or(0),
+                    &credentials.password,
+                )
+                .ok_or_else(|| ModemError::data_parsing("rsaMod").with_endpoint(AUTH_QUERY))?,
+            ),
+            _ => None,
+        };
+
+        let digest = md5_hex(&format!("{}:{nonce}", credentials.password));
+        let response = TpLinkParser::post_request(
+            agent,
+            host,
+            cipher.as_ref(),
+            AUTH_QUERY,
+            &json!({"module": "authenticator", "action": 1, "digest": digest}),
+            true,
+        )?;
+
+        match (response["result"].as_i64(), response["token"].as_str()) {
+            (Some(0), Some(token)) => Ok(TpLinkSession {
+                token: token.to_string(),
+                cipher,
+            }),
+            (result, _) => {
+                eprintln!("Login error: result={result:?}");
+                // Non-zero result is wrong password. Repeated login uses up remaining attempts
+                let error = match result {
+                    Some(result) if result != 0 => {
+                        ModemError::authentication("Password is rejected by web UI")
+                    }
+                    _ => ModemError::access("Login to web UI failed"),
+                };
+                Err(error
+                    .with_endpoint(AUTH_QUERY)
+                    .with_vendor_error(VendorError {
+                        code: result.map(|result| result.to_string()),
+                        message: response["remainAttempts"]
+                            .as_i64()
+                            .map(|attempts| format!("Remaining attempts: {attempts}")),
+                        explanation: None,
+                    }))
+            }
+        }
+    }
+
+    fn get_status_json(
+        agent: &ureq::Agent,
+        host: &str,
+        session: &TpLinkSession,
+    ) -> Result<serde_json::Value, ModemError> {
+        TpLinkParser::post_request(
+            agent,
+            host,
+            session.cipher.as_ref(),
+            WEB_QUERY,
+            &json!({"token": session.token, "module": "status", "action": 0}),
+            false,
+        )
+    }
+
+    fn parse_battery_status_json(json: &serde_json::Value) -> Option<BatteryStatus> {
+        // Routers without battery don't report it
+        if json["battery"]["isConnected"].as_bool() == Some(false) {
+            return None;
+        }
+        let battery_percent = json["battery"]["voltage"].as_i64()?;
+
+        let battery_status_str = if json["battery"]["charging"].as_bool() == Some(true) {
+            "Charging"
+        } else {
+            "No Charge"
+        };
+
+        let mut battery_status = ['\0'; 20];
+        copy_string_to_array!(battery_status, battery_status_str);
+
+        Some(BatteryStatus {
+            percent: battery_percent,
+            status: battery_status,
+        })
+    }
+
+    fn parse_device_info_json(json: &serde_json::Value) -> DeviceInformation {
+        let model_str = json["deviceInfo"]["model"].as_str().unwrap_or_default();
+        DeviceInformation::from("TP-Link", model_str)
+    }
+
+    /// Check if host has TP-Link web UI. Model is available only after login
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let agent = new_session_agent();
+        let authenticator = TpLinkParser::get_authenticator(&agent, host).ok()?;
+        if authenticator["nonce"].is_string() {
+            Some(DeviceInformation::from("TP-Link", ""))
+        } else {
+            None
+        }
+    }
+
+    fn parse_info_json(json: &serde_json::Value) -> Result<ModemStatus, ModemError> {
+        let wan = &json["wan"];
+
+        let mode = get_mode_by_network_type(wan["networkType"].as_i64().unwrap_or(-1));
+
+        let rssi = wan["rssi"].as_i64().unwrap_or(0);
+
+        let plmn_str = format!(
+            "{}{}",
+            wan["mcc"].as_str().unwrap_or("000"),
+            wan["mnc"].as_str().unwrap_or("00")
+        );
+        let plmn = PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string");
+
+        let band_number = wan["band"].as_str().and_then(parse_band_number);
+        let mut band = ['\0'; 20];
+        if let Some(band_number) = band_number {
+            let band_str = if mode == NetworkMode::Lte {
+                format!("LTE B{band_number}")
+            } else {
+                format!("B{band_number}")
+            };
+            copy_string_to_array!(band, band_str);
+        }
+
+        let cell_id = wan["cellId"].as_i64().unwrap_or(0);
+
+        let signal_info = match mode {
+            NetworkMode::Wcdma => {
+                let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
+
+                let (nb, cc) = (id / 10, id % 10);
+
+                SignalInfo::Wcdma(WcdmaSignalInfo {
+                    rscp: required_i64(wan, "rscp")?,
+                    ecio: required_i64(wan, "ecio")?,
+                    nb,
+                    cc,
+                    rnc,
+                    psc: 0,
+                })
+            }
+            NetworkMode::Lte => {
+                let (enb, id) = (cell_id >> 8, cell_id & 0xFF);
+
+                SignalInfo::Lte(LteSignalInfo {
+                    rsrq: required_i64(wan, "rsrq")?,
+                    rsrp: required_i64(wan, "rsrp")?,
+                    sinr: required_i64(wan, "snr")?,
+                    ca_count: 0,
+                    enb,
+                    id,
+                    pci: wan["pci"].as_i64().unwrap_or(-1),
+                })
+            }
+            _ => SignalInfo::None,
+        };
+
+        let channel_info = wan["earfcn"]
+            .as_i64()
+            .filter(|_| mode == NetworkMode::Lte)
+            .map(|earfcn| {
+                let (dl_frequency, ul_frequency) = match get_lte_frequencies(earfcn) {
+                    Some((dl, ul)) => (Some(dl), ul),
+                    None => (None, None),
+                };
+                ChannelInfo {
+                    earfcn: Some(earfcn),
+                    dl_frequency,
+                    ul_frequency,
+                    dl_bandwidth: None,
+                    ul_bandwidth: None,
+                }
+            });
+
+        // Current speed in bytes per second
+        let traffic_statistics = match (wan["rxSpeed"].as_i64(), wan["txSpeed"].as_i64()) {
+            (None, None) => None,
+            (dl, ul) => Some(TrafficStatistics {
+                dl: dl.unwrap_or(0) * 8,
+                ul: ul.unwrap_or(0) * 8,
+            }),
+        };
+
+        Ok(ModemStatus {
+            mode,
+            plmn,
+            rssi,
+            cell_id,
+            signal_info,
+            band,
+            carriers: Vec::new(),
+            channel_info,
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_info: TpLinkParser::parse_device_info_json(json),
+            battery_status: TpLinkParser::parse_battery_status_json(json),
+            device_temp: None,
+            traffic_statistics,
+            traffic_mode: TrafficMode::Absolute,
+        })
+    }
+}
+
+impl ModemInfoParser for TpLinkParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        let agent = &self.agent;
+
+        // Token is reused between polls. Login again when it expires
+        let mut json = None;
+        if let Some(session) = &self.session {
+            json = TpLinkParser::get_status_json(agent, host, session)
+                .ok()
+                .filter(|json| json["result"].as_i64() == Some(0));
+        }
+
+        let json = match json {
+            Some(json) => json,
+            None => {
+                let session = TpLinkParser::login(agent, host, &self.credentials)?;
+                let session = self.session.insert(session);
+
+                let json = TpLinkParser::get_status_json(agent, host, session)
+                    .map_err(|e| e.context(&format!("Cannot get status from host {host}")))?;
+                if json["result"].as_i64() != Some(0) {
+                    eprintln!("Status error: result={}", json["result"]);
+                    return Err(ModemError::access("Status is not available after login")
+                        .with_endpoint(WEB_QUERY)
+                        .with_vendor_error(VendorError {
+                            code: json["result"].as_i64().map(|result| result.to_string()),
+                            message: None,
+                            explanation: None,
+                        }));
+                }
+                json
+            }
+        };
+
+        if json["wan"].is_null() {
+            eprintln!("Status doesn't have WAN info");
+            return Err(ModemError::data_parsing("wan").with_endpoint(WEB_QUERY));
+        }
+
+        TpLinkParser::parse_info_json(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_utils::ModemErrorKind;
+    use rsa::RsaPrivateKey;
+    use rsa::traits::PublicKeyParts;
+
+    /// Decrypted status of M7350 in LTE mode
+    const STATUS_JSON: &str = r#"{
+        "result": 0,
+        "deviceInfo": {"model": "M7350", "firmwareVer": "1.0.10 Build 190312 Rel.1070n"},
+        "battery": {"isConnected": true, "voltage": 76, "charging": false},
+        "wan": {
+            "networkType": 3, "simStatus": 3, "connectStatus": 4, "rssi": -67,
+            "mcc": "262", "mnc": "02", "band": "LTE BAND 20", "cellId": 27436305,
+            "pci": 293, "earfcn": 6300, "rsrp": -98, "rsrq": -12, "snr": 7,
+            "rxSpeed": 2500, "txSpeed": 400
+        }
+    }"#;
+
+    fn parse_json(json: &str) -> Result<ModemStatus, ModemError> {
+        TpLinkParser::parse_info_json(&serde_json::from_str(json).unwrap())
+    }
+
+    #[test]
+    fn lte_status() {
+        let modem_status = parse_json(STATUS_JSON).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE");
+        assert_eq!(modem_status.get_plmn().trim_end_matches('\0'), "26202");
+        assert_eq!(modem_status.get_band(), "LTE B20");
+        assert_eq!(modem_status.rssi, -67);
+        assert_eq!(
+            modem_status.get_frequencies().as_deref(),
+            Some("806.0/847.0 MHz")
+        );
+
+        let SignalInfo::Lte(lte_info) = modem_status.signal_info else {
+            panic!("Signal info is not LTE");
+        };
+        assert_eq!(lte_info.rsrp, -98);
+        assert_eq!(lte_info.rsrq, -12);
+        assert_eq!(lte_info.sinr, 7);
+        assert_eq!(lte_info.enb, 107_173);
+        assert_eq!(lte_info.id, 17);
+        assert_eq!(lte_info.pci, 293);
+
+        let traffic_statistics = modem_status.traffic_statistics.unwrap();
+        assert_eq!(traffic_statistics.dl, 20_000);
+        assert_eq!(traffic_statistics.ul, 3200);
+
+        let (percent, status) = modem_status.get_battery_percent_and_status().unwrap();
+        assert_eq!(percent, 76);
+        assert_eq!(status.trim_end_matches('\0'), "No Charge");
+
+        let (manufacturer, model) = modem_status.device_info.get_manufacturer_and_model();
+        assert_eq!(manufacturer, "TP-Link");
+        assert_eq!(model, "M7350");
+    }
+
+    #[test]
+    fn missing_signal_value() {
+        let json = STATUS_JSON.replace(r#""rsrq": -12, "#, "");
+        let Err(e) = parse_json(&json) else {
+            panic!("Missing RSRQ is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+
+    #[test]
+    fn cipher() {
+        let mut rng = rand::thread_rng();
+        let private_key = RsaPrivateKey::new(&mut rng, 512).unwrap();
+        let public_key = private_key.to_public_key();
+
+        let cipher = TpLinkCipher::new(
+            &to_hex_string(&public_key.n().to_bytes_be()),
+            &to_hex_string(&public_key.e().to_bytes_be()),
+            1000,
+            "secret",
+        )
+        .unwrap();
+
+        let request = r#"{"module":"status","action":0}"#;
+        let data = cipher.encrypt(request);
+        assert_eq!(cipher.decrypt(&data).as_deref(), Some(request));
+
+        // Signature is hex string of RSA chunks of 512 bit key
+        let decrypt_sign = |sign: &str| {
+            assert_eq!(sign.len() % 128, 0);
+            (0..sign.len())
+                .step_by(128)
+                .map(|i| {
+                    let chunk = from_hex_string(&sign[i..i + 128]).unwrap();
+                    let decrypted = private_key.decrypt(Pkcs1v15Encrypt, &chunk).unwrap();
+                    String::from_utf8(decrypted).unwrap()
+                })
+                .collect::<String>()
+        };
+
+        let hash = md5_hex("adminsecret");
+        assert_eq!(
+            decrypt_sign(&cipher.sign(data.len(), true)),
+            format!(
+                "key={}&iv={}&h={hash}&s={}",
+                cipher.key,
+                cipher.iv,
+                1000 + data.len()
+            )
+        );
+        assert_eq!(
+            decrypt_sign(&cipher.sign(10, false)),
+            format!("h={hash}&s=1010")
+        );
+    }
+}
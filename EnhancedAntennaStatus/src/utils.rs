@@ -1,3 +1,5 @@
+use md5::Md5;
+use sha2::{Digest, Sha256};
 use std::fmt::Debug;
 use std::str::FromStr;
 
@@ -39,6 +41,12 @@ pub fn json_str_as_type<T: FromStr>(val: &serde_json::Value) -> Option<T> {
     val.as_str().and_then(|val| val.parse::<T>().ok())
 }
 
+/// Get JSON value by path in format 'wwan.signalStrength.rssi'.
+/// Returns `Null` if any part of the path is missing
+pub fn get_json_value<'a>(json: &'a serde_json::Value, path: &str) -> &'a serde_json::Value {
+    path.split('.').fold(json, |val, key| &val[key])
+}
+
 /// Truncate unit at the end of the string
 fn truncate_unit(s: &mut String) {
     const UNITS: [&str; 2] = ["dB", "dBm"];
@@ -112,3 +120,78 @@ pub fn xml_contains_required_parameters(xml: &xmltree::Element, parameters: &[&s
     }
     true
 }
+
+/// Escape special characters of XML text, e.g. in username of login request
+pub fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Parse number of the band from its name (e.g. 'LTE B3', 'n78' or just '78')
+pub fn parse_band_number(s: &str) -> Option<i64> {
+    let mut digits = s
+        .trim_end()
+        .chars()
+        .rev()
+        .take_while(char::is_ascii_digit)
+        .collect::<Vec<char>>();
+    digits.reverse();
+    digits.iter().collect::<String>().parse::<i64>().ok()
+}
+
+/// Parse channel bandwidth in MHz (e.g. '20MHz', '1.4 MHz' or just '20')
+pub fn parse_bandwidth_mhz(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let s = s
+        .strip_suffix("MHz")
+        .or_else(|| s.strip_suffix("Mhz"))
+        .unwrap_or(s);
+    s.trim().parse::<f64>().ok()
+}
+
+/// Format bytes as lowercase hex string
+pub fn to_hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// SHA256 digest of string as lowercase hex string
+pub fn sha256_hex(s: &str) -> String {
+    to_hex_string(&Sha256::digest(s.as_bytes()))
+}
+
+/// MD5 digest of string as lowercase hex string
+pub fn md5_hex(s: &str) -> String {
+    to_hex_string(&Md5::digest(s.as_bytes()))
+}
+
+/// Parse hex string as bytes
+pub fn from_hex_string(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_text() {
+        assert_eq!(escape_xml("admin"), "admin");
+        assert_eq!(escape_xml("a<b>&\"c'"), "a&lt;b&gt;&amp;&quot;c&apos;");
+    }
+}
@@ -0,0 +1,543 @@
+#![allow(clippy::similar_names)]
+
+use std::str::FromStr;
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
+use crate::bandwidth_utils::{TrafficMode, TrafficStatistics};
+use crate::earfcn_utils::get_lte_frequencies;
+use crate::modem_utils::{
+    BatteryStatus, ChannelInfo, Credentials, DeviceInformation, LteSignalInfo, ModemError,
+    ModemInfoParser, ModemStatus, NetworkMode, NrSignalInfo, PlmnStatus, SignalInfo, VendorError,
+    WcdmaSignalInfo,
+};
+use crate::network_utils::{get_url_json_with_agent, new_session_agent, post_url_form_with_agent};
+use crate::utils::{copy_string_to_array, json_str_as_type, parse_band_number, sha256_hex};
+
+const GET_CMD_QUERY: &str = "/goform/goform_get_cmd_process";
+const SET_CMD_QUERY: &str = "/goform/goform_set_cmd_process";
+
+/// Parameters that are requested from web UI on every poll
+const STATUS_FIELDS: [&str; 26] = [
+    "loginfo",
+    "network_type",
+    "rssi",
+    "rscp",
+    "ecio",
+    "lte_rsrp",
+    "lte_rsrq",
+    "lte_snr",
+    "cell_id",
+    "lte_pci",
+    "wan_active_band",
+    "wan_active_channel",
+    "rmcc",
+    "rmnc",
+    "Z5g_rsrp",
+    "Z5g_rsrq",
+    "Z5g_SINR",
+    "nr5g_action_band",
+    "nr5g_action_channel",
+    "nr5g_pci",
+    "nr5g_cell_id",
+    "realtime_tx_thrpt",
+    "realtime_rx_thrpt",
+    "battery_charging",
+    "battery_vol_percent",
+    "model_name",
+];
+
+fn get_mode_by_network_type(s: &str) -> NetworkMode {
+    match s.to_uppercase().as_str() {
+        "GSM" | "GPRS" | "EDGE" | "2G" => NetworkMode::Gsm,
+        "UMTS" | "WCDMA" | "HSDPA" | "HSUPA" | "HSPA" | "HSPA+" | "DC-HSPA+" | "3G" => {
+            NetworkMode::Wcdma
+        }
+        "LTE" | "LTE-CA" | "LTE+" | "4G" => NetworkMode::Lte,
+        "ENDC" | "EN-DC" | "LTE_NSA" | "NR5G_NSA" => NetworkMode::NrNsa,
+        "SA" | "NR" | "NR5G" | "NR5G_SA" | "5G" => NetworkMode::Nr,
+        _ => NetworkMode::Unknown,
+    }
+}
+
+/// Parse numeric parameter that web UI reports as string. Decimal values (e.g. SNR) are rounded
+fn get_zte_i64(json: &serde_json::Value, name: &str) -> Option<i64> {
+    json_str_as_type::<f64>(&json[name]).map(|val| val.round() as i64)
+}
+
+/// Parse identifier that web UI reports as hex string (cell ID, PCI)
+fn get_zte_hex(json: &serde_json::Value, name: &str) -> Option<i64> {
+    json[name]
+        .as_str()
+        .and_then(|val| i64::from_str_radix(val, 16).ok())
+}
+
+fn missing_field(name: &str) -> ModemError {
+    eprintln!("Status JSON doesn't have required field '{name}'");
+    ModemError::data_parsing(name).with_endpoint(GET_CMD_QUERY)
+}
+
+/// Get required numeric field of status JSON
+fn required_i64(json: &serde_json::Value, name: &str) -> Result<i64, ModemError> {
+    get_zte_i64(json, name).ok_or_else(|| missing_field(name))
+}
+
+/// Human-readable meaning of result codes of login request
+fn explain_zte_login_result(code: &str) -> Option<&'static str> {
+    match code {
+        "1" => Some("Login failed"),
+        "2" => Some("Another user is already logged in"),
+        "3" => Some("Wrong password"),
+        _ => None,
+    }
+}
+
+/// Check if login result means that credentials are rejected
+fn is_zte_credentials_error(code: &str) -> bool {
+    code == "3"
+}
+
+/*
+ * Utils for ZTE
+ */
+
+pub struct ZteParser {
+    credentials: Credentials,
+    /// HTTP agent with session cookie that is reused between polls
+    agent: ureq::Agent,
+}
+
+impl ZteParser {
+    pub fn new(credentials: &Credentials) -> Self {
+        Self {
+            credentials: credentials.clone(),
+            agent: new_session_agent(),
+        }
+    }
+
+    fn get_cmd_json(
+        agent: &ureq::Agent,
+        host: &str,
+        fields: &[&str],
+    ) -> Result<serde_json::Value, ModemError> {
+        let query = format!(
+            "{GET_CMD_QUERY}?isTest=false&multi_data=1&cmd={}",
+            fields.join(",")
+        );
+        get_url_json_with_agent(agent, host, &query)
+    }
+
+    /// Signal parameters are empty when firmware hides them from anonymous users
+    fn is_login_required(json: &serde_json::Value) -> bool {
+        json["loginfo"]
+            .as_str()
+            .is_none_or(|loginfo| loginfo != "ok")
+            && json["network_type"]
+                .as_str()
+                .is_none_or(|network_type| network_type.is_empty())
+    }
+
+    /// Encode password for login request. Newer firmwares salt SHA256 hash with one-time 'LD'
+    /// value, older ones expect base64
+    fn encode_password(agent: &ureq::Agent, host: &str, password: &str) -> String {
+        let ld = ZteParser::get_cmd_json(agent, host, &["LD"])
+            .ok()
+            .and_then(|json| json["LD"].as_str().map(std::string::ToString::to_string))
+            .filter(|ld| !ld.is_empty());
+
+        match ld {
+            Some(ld) => ZteParser::hash_password(password, &ld),
+            None => BASE64.encode(password),
+        }
+    }
+
+    /// Uppercase SHA256 of uppercase SHA256 of password salted with 'LD' value
+    fn hash_password(password: &str, ld: &str) -> String {
+        sha256_hex(&format!("{}{ld}", sha256_hex(password).to_uppercase())).to_uppercase()
+    }
+
+    /// Login to web UI. Session cookie is kept by the agent
+    fn login(agent: &ureq::Agent, host: &str, credentials: &Credentials) -> Result<(), ModemError> {
+        if credentials.password.is_empty() {
+            eprintln!("Login is required but password is not set");
+            return Err(ModemError::access(
+                "Login is required but password is not set",
+            ));
+        }
+
+        let password = ZteParser::encode_password(agent, host, &credentials.password);
+
+        let response = post_url_form_with_agent(
+            agent,
+            host,
+            SET_CMD_QUERY,
+            &[
+                ("isTest", "false"),
+                ("goformId", "LOGIN"),
+                ("password", password.as_str()),
+            ],
+        )
+        .map_err(|e| e.context("Login to web UI failed"))?;
+
+        let result = serde_json::from_str::<serde_json::Value>(&response)
+            .ok()
+            .and_then(|json| {
+                json["result"]
+                    .as_str()
+                    .map(std::string::ToString::to_string)
+            })
+            .unwrap_or_default();
+
+        if result == "0" {
+            Ok(())
+        } else {
+            eprintln!("Login error: result='{result}'");
+            // Repeated login with wrong password runs down lockout counter of the modem
+            let error = if is_zte_credentials_error(&result) {
+                ModemError::authentication("Password is rejected by web UI")
+            } else {
+                ModemError::access("Login to web UI failed")
+            };
+            Err(error
+                .with_endpoint(SET_CMD_QUERY)
+                .with_vendor_error(VendorError {
+                    code: Some(result.clone()),
+                    message: None,
+                    explanation: explain_zte_login_result(&result)
+                        .map(std::string::ToString::to_string),
+                }))
+        }
+    }
+
+    fn parse_nr_signal_json(json: &serde_json::Value) -> Option<NrSignalInfo> {
+        let rsrp = get_zte_i64(json, "Z5g_rsrp")?;
+
+        let (gnb, id) = NrSignalInfo::split_nci(get_zte_hex(json, "nr5g_cell_id").unwrap_or(0));
+
+        Some(NrSignalInfo {
+            rsrq: get_zte_i64(json, "Z5g_rsrq").unwrap_or(0),
+            rsrp,
+            sinr: get_zte_i64(json, "Z5g_SINR").unwrap_or(0),
+            band: json["nr5g_action_band"]
+                .as_str()
+                .and_then(parse_band_number)
+                .unwrap_or(0),
+            arfcn: get_zte_i64(json, "nr5g_action_channel").unwrap_or(0),
+            gnb,
+            id,
+            pci: get_zte_hex(json, "nr5g_pci").unwrap_or(-1),
+        })
+    }
+
+    fn parse_channel_info_json(json: &serde_json::Value) -> Option<ChannelInfo> {
+        let earfcn = get_zte_i64(json, "wan_active_channel")?;
+        let (dl_frequency, ul_frequency) = match get_lte_frequencies(earfcn) {
+            Some((dl, ul)) => (Some(dl), ul),
+            None => (None, None),
+        };
+
+        Some(ChannelInfo {
+            earfcn: Some(earfcn),
+            dl_frequency,
+            ul_frequency,
+            dl_bandwidth: None,
+            ul_bandwidth: None,
+        })
+    }
+
+    fn parse_battery_status_json(json: &serde_json::Value) -> Option<BatteryStatus> {
+        let battery_percent = get_zte_i64(json, "battery_vol_percent")?;
+
+        let battery_status_str = if json["battery_charging"].as_str() == Some("1") {
+            "Charging"
+        } else {
+            "No Charge"
+        };
+
+        let mut battery_status = ['\0'; 20];
+        copy_string_to_array!(battery_status, battery_status_str);
+
+        Some(BatteryStatus {
+            percent: battery_percent,
+            status: battery_status,
+        })
+    }
+
+    fn parse_traffic_statistics_json(json: &serde_json::Value) -> Option<TrafficStatistics> {
+        // Current throughput in bytes per second
+        let dl = get_zte_i64(json, "realtime_rx_thrpt");
+        let ul = get_zte_i64(json, "realtime_tx_thrpt");
+        if dl.is_none() && ul.is_none() {
+            None
+        } else {
+            Some(TrafficStatistics {
+                dl: dl.unwrap_or(0) * 8,
+                ul: ul.unwrap_or(0) * 8,
+            })
+        }
+    }
+
+    fn parse_device_info_json(json: &serde_json::Value) -> DeviceInformation {
+        let model_str = json["model_name"].as_str().unwrap_or_default();
+        DeviceInformation::from("ZTE", model_str)
+    }
+
+    /// Check if host has ZTE web UI
+    pub fn detect(host: &str) -> Option<DeviceInformation> {
+        let agent = new_session_agent();
+        let json = ZteParser::get_cmd_json(&agent, host, &["loginfo", "model_name"]).ok()?;
+        if json.get("loginfo").is_some() {
+            Some(ZteParser::parse_device_info_json(&json))
+        } else {
+            None
+        }
+    }
+
+    fn parse_info_json(json: &serde_json::Value) -> Result<ModemStatus, ModemError> {
+        let mode = get_mode_by_network_type(
+            json["network_type"]
+                .as_str()
+                .ok_or_else(|| missing_field("network_type"))?,
+        );
+
+        let rssi = get_zte_i64(json, "rssi").unwrap_or(0);
+
+        let plmn_str = format!(
+            "{}{:0>2}",
+            json["rmcc"].as_str().unwrap_or("000"),
+            json["rmnc"].as_str().unwrap_or("00")
+        );
+        let plmn = PlmnStatus::from_str(&plmn_str).expect("Unable to convert PLMN from string");
+
+        let band_str = json["wan_active_band"].as_str().unwrap_or_default();
+        let band_str = match (mode, parse_band_number(band_str)) {
+            (NetworkMode::Lte | NetworkMode::NrNsa, Some(band_number)) => {
+                format!("LTE B{band_number}")
+            }
+            _ => band_str.to_string(),
+        };
+        let mut band = ['\0'; 20];
+        copy_string_to_array!(band, band_str);
+
+        let mut cell_id = get_zte_hex(json, "cell_id").unwrap_or(0);
+
+        let signal_info = match mode {
+            NetworkMode::Wcdma => {
+                let rscp = required_i64(json, "rscp")?;
+                let ecio = required_i64(json, "ecio")?;
+
+                let (rnc, id) = (cell_id >> 16, cell_id & 0xFFFF);
+
+                let (nb, cc) = (id / 10, id % 10);
+
+                SignalInfo::Wcdma(WcdmaSignalInfo {
+                    rscp,
+                    ecio,
+                    nb,
+                    cc,
+                    rnc,
+                    psc: 0,
+                })
+            }
+            NetworkMode::Lte | NetworkMode::NrNsa => {
+                let (enb, id) = (cell_id >> 8, cell_id & 0xFF);
+
+                let lte_info = LteSignalInfo {
+                    rsrq: required_i64(json, "lte_rsrq")?,
+                    rsrp: required_i64(json, "lte_rsrp")?,
+                    sinr: required_i64(json, "lte_snr")?,
+                    ca_count: 0,
+                    enb,
+                    id,
+                    pci: get_zte_hex(json, "lte_pci").unwrap_or(-1),
+                };
+
+                match ZteParser::parse_nr_signal_json(json) {
+                    Some(nr_info) if mode == NetworkMode::NrNsa => {
+                        SignalInfo::NrNsa(lte_info, nr_info)
+                    }
+                    _ => SignalInfo::Lte(lte_info),
+                }
+            }
+            NetworkMode::Nr => {
+                cell_id = get_zte_hex(json, "nr5g_cell_id").unwrap_or(cell_id);
+                SignalInfo::Nr(
+                    ZteParser::parse_nr_signal_json(json)
+                        .ok_or_else(|| missing_field("Z5g_rsrp"))?,
+                )
+            }
+            _ => SignalInfo::None,
+        };
+
+        // NSA mode without NR leg info is shown as LTE
+        let mode = match signal_info {
+            SignalInfo::Lte(_) => NetworkMode::Lte,
+            _ => mode,
+        };
+
+        let channel_info = match mode {
+            NetworkMode::Lte | NetworkMode::NrNsa => ZteParser::parse_channel_info_json(json),
+            _ => None,
+        };
+
+        Ok(ModemStatus {
+            mode,
+            plmn,
+            rssi,
+            cell_id,
+            signal_info,
+            band,
+            carriers: Vec::new(),
+            channel_info,
+            tac: None,
+            antenna_rsrp: Vec::new(),
+            device_info: ZteParser::parse_device_info_json(json),
+            battery_status: ZteParser::parse_battery_status_json(json),
+            device_temp: None,
+            traffic_statistics: ZteParser::parse_traffic_statistics_json(json),
+            traffic_mode: TrafficMode::Absolute,
+        })
+    }
+}
+
+impl ModemInfoParser for ZteParser {
+    fn get_info(&mut self, host: &str) -> Result<ModemStatus, ModemError> {
+        // Session cookie is kept by the agent so login is needed only when it expires
+        let agent = &self.agent;
+
+        let mut json = ZteParser::get_cmd_json(agent, host, &STATUS_FIELDS)
+            .map_err(|e| e.context(&format!("Cannot access status JSON from host {host}")))?;
+
+        // Some firmwares (e.g. MF286) require login to show signal info
+        if ZteParser::is_login_required(&json) {
+            ZteParser::login(agent, host, &self.credentials)?;
+
+            json = ZteParser::get_cmd_json(agent, host, &STATUS_FIELDS)?;
+            if ZteParser::is_login_required(&json) {
+                eprintln!("Status JSON is not available after login");
+                return Err(ModemError::access(
+                    "Signal info is hidden after login, check the password",
+                )
+                .with_endpoint(GET_CMD_QUERY));
+            }
+        }
+
+        ZteParser::parse_info_json(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_utils::ModemErrorKind;
+
+    /// Status of MF286D in LTE mode. Unknown parameters are empty strings
+    const LTE_STATUS_JSON: &str = r#"{
+        "loginfo": "ok", "network_type": "LTE", "rssi": "-67", "rscp": "", "ecio": "",
+        "lte_rsrp": "-97", "lte_rsrq": "-11", "lte_snr": "12.4", "cell_id": "1A2B301",
+        "lte_pci": "BB", "wan_active_band": "LTE BAND 3", "wan_active_channel": "1300",
+        "rmcc": "262", "rmnc": "3", "Z5g_rsrp": "", "Z5g_rsrq": "", "Z5g_SINR": "",
+        "nr5g_action_band": "", "nr5g_action_channel": "", "nr5g_pci": "", "nr5g_cell_id": "",
+        "realtime_tx_thrpt": "1250", "realtime_rx_thrpt": "50000", "battery_charging": "",
+        "battery_vol_percent": "", "model_name": "MF286D"
+    }"#;
+
+    /// Status of MC801A in EN-DC mode
+    const NSA_STATUS_JSON: &str = r#"{
+        "loginfo": "ok", "network_type": "ENDC", "rssi": "-61", "rscp": "", "ecio": "",
+        "lte_rsrp": "-88", "lte_rsrq": "-9", "lte_snr": "15", "cell_id": "2F1C00A",
+        "lte_pci": "1C", "wan_active_band": "LTE BAND 20", "wan_active_channel": "6300",
+        "rmcc": "262", "rmnc": "01", "Z5g_rsrp": "-92", "Z5g_rsrq": "-11", "Z5g_SINR": "8.6",
+        "nr5g_action_band": "n78", "nr5g_action_channel": "636666", "nr5g_pci": "1F4",
+        "nr5g_cell_id": "", "realtime_tx_thrpt": "", "realtime_rx_thrpt": "",
+        "battery_charging": "", "battery_vol_percent": "", "model_name": "MC801A"
+    }"#;
+
+    fn parse_json(json: &str) -> Result<ModemStatus, ModemError> {
+        ZteParser::parse_info_json(&serde_json::from_str(json).unwrap())
+    }
+
+    #[test]
+    fn lte_status() {
+        let modem_status = parse_json(LTE_STATUS_JSON).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE");
+        assert_eq!(modem_status.get_plmn().trim_end_matches('\0'), "26203");
+        assert_eq!(modem_status.get_band(), "LTE B3");
+        assert_eq!(modem_status.rssi, -67);
+        assert_eq!(modem_status.cell_id, 0x01A2_B301);
+        assert_eq!(
+            modem_status.get_frequencies().as_deref(),
+            Some("1815.0/1720.0 MHz")
+        );
+
+        let SignalInfo::Lte(lte_info) = modem_status.signal_info else {
+            panic!("Signal info is not LTE");
+        };
+        assert_eq!(lte_info.rsrp, -97);
+        assert_eq!(lte_info.rsrq, -11);
+        assert_eq!(lte_info.sinr, 12);
+        assert_eq!(lte_info.enb, 0x0001_A2B3);
+        assert_eq!(lte_info.id, 1);
+        assert_eq!(lte_info.pci, 187);
+
+        // Throughput in bytes per second is shown in bits per second
+        let traffic_statistics = modem_status.traffic_statistics.unwrap();
+        assert_eq!(traffic_statistics.dl, 400_000);
+        assert_eq!(traffic_statistics.ul, 10_000);
+        assert!(modem_status.battery_status.is_none());
+    }
+
+    #[test]
+    fn nsa_status() {
+        let modem_status = parse_json(NSA_STATUS_JSON).unwrap();
+
+        assert_eq!(modem_status.get_mode(), "LTE+NR");
+        assert_eq!(modem_status.get_band(), "LTE B20");
+
+        let SignalInfo::NrNsa(lte_info, nr_info) = modem_status.signal_info else {
+            panic!("Signal info is not NSA");
+        };
+        assert_eq!(lte_info.rsrp, -88);
+        assert_eq!(nr_info.rsrp, -92);
+        assert_eq!(nr_info.rsrq, -11);
+        assert_eq!(nr_info.sinr, 9);
+        assert_eq!(nr_info.band, 78);
+        assert_eq!(nr_info.arfcn, 636_666);
+        assert_eq!(nr_info.pci, 500);
+        assert!(modem_status.traffic_statistics.is_none());
+    }
+
+    #[test]
+    fn missing_signal_value() {
+        let json = LTE_STATUS_JSON.replace(r#""lte_rsrq": "-11""#, r#""lte_rsrq": """#);
+        let Err(e) = parse_json(&json) else {
+            panic!("Empty RSRQ is accepted");
+        };
+        assert_eq!(e.kind, ModemErrorKind::DataParsing);
+    }
+
+    #[test]
+    fn login_required() {
+        let json = serde_json::json!({"loginfo": "", "network_type": ""});
+        assert!(ZteParser::is_login_required(&json));
+
+        let json = serde_json::from_str(LTE_STATUS_JSON).unwrap();
+        assert!(!ZteParser::is_login_required(&json));
+    }
+
+    #[test]
+    fn password_hash() {
+        assert_eq!(
+            ZteParser::hash_password("admin", "2D1F7A52C3E94B08"),
+            "6AB44A364147110B7D4059C705D3AF5E3714078F2490099827EAC7FBF0789F71"
+        );
+    }
+
+    #[test]
+    fn credentials_errors() {
+        assert!(is_zte_credentials_error("3"));
+        assert!(!is_zte_credentials_error("1"));
+        assert!(!is_zte_credentials_error("2"));
+    }
+}
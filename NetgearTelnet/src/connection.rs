@@ -0,0 +1,294 @@
+use std::fmt;
+use std::io;
+use std::net::ToSocketAddrs;
+use std::time::Duration;
+
+use telnet::{Event, Telnet};
+
+use crate::gstatus::GstatusInfo;
+use crate::lteinfo::LteInfo;
+
+/// Default port of AT command interface. Enabled with `AT!TELEN=1`
+pub const NETGEAR_TELNET_PORT: u16 = 5510;
+
+const BUF_SIZE: usize = 128;
+
+#[derive(Debug)]
+pub enum ConnectionError {
+    /// Host is not resolved or doesn't accept connections on the port
+    Connect(io::Error),
+    Write(io::Error),
+    Read(io::Error),
+    /// Final result code is not received in time
+    Timeout,
+    /// Response of the previous timed out command is still not completed
+    Busy,
+    /// Connection is closed by the modem
+    Closed,
+    /// Telnet protocol error
+    Protocol(telnet::TelnetError),
+    /// Modem returned `ERROR`, `+CME ERROR: ...` or `+CMS ERROR: ...`
+    Command(String),
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConnectionError::Connect(e) => write!(f, "Cannot connect to host: {e}"),
+            ConnectionError::Write(e) => write!(f, "Telnet write error: {e}"),
+            ConnectionError::Read(e) => write!(f, "Telnet read error: {e}"),
+            ConnectionError::Timeout => write!(f, "No response from modem"),
+            ConnectionError::Busy => write!(f, "Modem hasn't completed previous command"),
+            ConnectionError::Closed => write!(f, "Connection is closed by modem"),
+            ConnectionError::Protocol(e) => write!(f, "Telnet protocol error: {e}"),
+            ConnectionError::Command(result) => write!(f, "Command failed: {result}"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConnectionError::Connect(e) | ConnectionError::Write(e) | ConnectionError::Read(e) => {
+                Some(e)
+            }
+            ConnectionError::Protocol(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ConnectionOptions {
+    pub port: u16,
+    pub connect_timeout: Duration,
+    /// Maximal time of waiting for the next part of response. `AT!GSTATUS?` and
+    /// network scans take several seconds to complete
+    pub read_timeout: Duration,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            port: NETGEAR_TELNET_PORT,
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ModemInfo {
+    pub manufacturer: String,
+    pub model: String,
+    pub revision: String,
+}
+
+/// Check if response ends with final result code. Returns error result code if the
+/// command failed
+fn get_final_result(response: &str) -> Option<Result<(), String>> {
+    let response = response.strip_suffix("\r\n")?;
+    let last_line = response.rsplit("\r\n").next().unwrap_or(response).trim();
+
+    if last_line == "OK" {
+        Some(Ok(()))
+    } else if last_line == "ERROR"
+        || last_line.starts_with("+CME ERROR:")
+        || last_line.starts_with("+CMS ERROR:")
+    {
+        Some(Err(last_line.to_string()))
+    } else {
+        None
+    }
+}
+
+pub struct Connection {
+    telnet: Telnet,
+    options: ConnectionOptions,
+    /// Response of timed out command may still arrive
+    pending_response: bool,
+}
+
+impl Connection {
+    pub fn connect(host: &str, options: &ConnectionOptions) -> Result<Self, ConnectionError> {
+        let addresses = (host, options.port)
+            .to_socket_addrs()
+            .map_err(ConnectionError::Connect)?;
+
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "Host is not resolved");
+        for address in addresses {
+            match Telnet::connect_timeout(&address, BUF_SIZE, options.connect_timeout) {
+                Ok(telnet) => {
+                    return Ok(Self {
+                        telnet,
+                        options: *options,
+                        pending_response: false,
+                    });
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(ConnectionError::Connect(last_error))
+    }
+
+    /// Read response until final result code
+    fn read_response(&mut self) -> Result<(String, Result<(), String>), ConnectionError> {
+        let mut response = String::default();
+        loop {
+            let event = self
+                .telnet
+                .read_timeout(self.options.read_timeout)
+                .map_err(ConnectionError::Read)?;
+
+            match event {
+                Event::Data(buffer) => {
+                    response.push_str(&String::from_utf8_lossy(&buffer));
+
+                    if let Some(result) = get_final_result(&response) {
+                        return Ok((response, result));
+                    }
+                }
+                Event::TimedOut => return Err(ConnectionError::Timeout),
+                Event::Error(telnet::TelnetError::InternalQueueErr) => {
+                    // Nothing is read from closed stream
+                    return Err(ConnectionError::Closed);
+                }
+                Event::Error(e) => return Err(ConnectionError::Protocol(e)),
+                // Telnet options are not negotiated
+                _ => {}
+            }
+        }
+    }
+
+    /// Skip late response of timed out command so it isn't taken as response of
+    /// the next one
+    fn drain_response(&mut self) -> Result<(), ConnectionError> {
+        match self.read_response() {
+            Ok(_) => {
+                self.pending_response = false;
+                Ok(())
+            }
+            Err(ConnectionError::Timeout) => Err(ConnectionError::Busy),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Send command and wait for final result code. Returns non-empty lines of
+    /// the response without the result code
+    pub fn send(&mut self, cmd: &str) -> Result<Vec<String>, ConnectionError> {
+        if self.pending_response {
+            self.drain_response()?;
+        }
+
+        let command_str = format!("{cmd}\r");
+        self.telnet
+            .write(command_str.as_bytes())
+            .map_err(ConnectionError::Write)?;
+
+        let (response, result) = self.read_response().inspect_err(|e| {
+            if matches!(e, ConnectionError::Timeout) {
+                self.pending_response = true;
+            }
+        })?;
+        result.map_err(ConnectionError::Command)?;
+
+        let mut lines: Vec<String> = response
+            .split("\r\n")
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(std::string::ToString::to_string)
+            .collect();
+        // Remove final result code
+        lines.pop();
+        Ok(lines)
+    }
+
+    pub fn ati(&mut self) -> Result<ModemInfo, ConnectionError> {
+        let lines = self.send("ATI")?;
+        let mut data = ModemInfo::default();
+
+        for line in lines {
+            let value = line
+                .split_once(':')
+                .unwrap_or_default()
+                .1
+                .trim()
+                .to_string();
+            if line.contains("Revision") {
+                data.revision = value;
+            } else if line.contains("Model") {
+                data.model = value;
+            } else if line.contains("Manufacturer") {
+                data.manufacturer = value;
+            }
+        }
+
+        Ok(data)
+    }
+
+    pub fn gstatus(&mut self) -> Result<GstatusInfo, ConnectionError> {
+        self.send("AT !GSTATUS?")
+            .map(|lines| GstatusInfo::parse(&lines))
+    }
+
+    pub fn lteinfo(&mut self) -> Result<LteInfo, ConnectionError> {
+        self.send("AT!LTEINFO?").map(|lines| LteInfo::parse(&lines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn final_result() {
+        assert_eq!(get_final_result("\r\nOK\r\n"), Some(Ok(())));
+        assert_eq!(
+            get_final_result("\r\n+CME ERROR: 10\r\n"),
+            Some(Err("+CME ERROR: 10".to_string()))
+        );
+        assert_eq!(get_final_result("\r\n!GSTATUS: \r\n"), None);
+        assert_eq!(get_final_result("\r\nOK"), None);
+    }
+
+    /// Response of timed out command arrives before response of the next one
+    #[test]
+    fn late_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let modem = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut stream = stream;
+            let mut command = Vec::new();
+
+            reader.read_until(b'\r', &mut command).unwrap();
+            thread::sleep(Duration::from_millis(400));
+            stream.write_all(b"\r\nSLOW\r\n\r\nOK\r\n").unwrap();
+
+            command.clear();
+            reader.read_until(b'\r', &mut command).unwrap();
+            stream.write_all(b"\r\nFAST\r\n\r\nOK\r\n").unwrap();
+        });
+
+        let options = ConnectionOptions {
+            port,
+            read_timeout: Duration::from_millis(250),
+            ..ConnectionOptions::default()
+        };
+        let mut connection = Connection::connect("127.0.0.1", &options).unwrap();
+
+        assert!(matches!(
+            connection.send("AT!SLOW"),
+            Err(ConnectionError::Timeout)
+        ));
+        assert_eq!(connection.send("AT!FAST").unwrap(), vec!["FAST"]);
+
+        modem.join().unwrap();
+    }
+}
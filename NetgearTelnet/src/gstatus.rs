@@ -0,0 +1,410 @@
+use std::fmt;
+
+/// Signal of one receive chain, e.g. 'PCC RxM' (main antenna of primary carrier)
+#[derive(Clone, Debug, Default)]
+pub struct RxChain {
+    pub name: String,
+    pub rssi: Option<f64>,
+    pub rsrp: Option<f64>,
+}
+
+/// Secondary component carrier. Single carrier is reported as 'LTE Scell ...',
+/// several carriers as 'LTE SCC1 ...' ('LTE SSC1 ...' on some firmwares)
+#[derive(Clone, Debug, Default)]
+pub struct SecondaryCell {
+    pub index: u32,
+    pub state: String,
+    pub band: String,
+    pub bandwidth: String,
+    pub rx_channel: Option<i64>,
+}
+
+/// Modem status reported by `AT!GSTATUS?` of Sierra Wireless modules
+#[derive(Clone, Debug, Default)]
+pub struct GstatusInfo {
+    pub system_mode: String,
+    pub ps_state: String,
+    pub lte_band: String,
+    pub lte_bandwidth: String,
+    pub lte_rx_channel: Option<i64>,
+    pub lte_tx_channel: Option<i64>,
+    pub rx_chains: Vec<RxChain>,
+    pub rsrq: Option<f64>,
+    pub sinr: Option<f64>,
+    pub tac: Option<i64>,
+    pub cell_id: Option<i64>,
+    pub temperature: Option<i64>,
+    pub emm_state: String,
+    pub rrc_state: String,
+    pub secondary_cells: Vec<SecondaryCell>,
+}
+
+/// Split lines into key-value pairs. Columns are separated with tabs, column without
+/// key (e.g. 'Normal Service' after EMM state) continues value of the previous one
+fn get_gstatus_pairs(lines: &[String]) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for column in lines.iter().flat_map(|line| line.split('\t')) {
+        let column = column.trim();
+        if column.is_empty() {
+            continue;
+        }
+
+        match column.split_once(':') {
+            Some((key, value)) => pairs.push((key.trim().to_string(), value.trim().to_string())),
+            None => {
+                if let Some((_, value)) = pairs.last_mut() {
+                    if !value.is_empty() {
+                        value.push(' ');
+                    }
+                    value.push_str(column);
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// Parse number with optional unit, e.g. '-10.9' or '38 C'. Not available values are '--'
+fn parse_gstatus_f64(value: &str) -> Option<f64> {
+    value
+        .split_whitespace()
+        .next()
+        .and_then(|val| val.parse::<f64>().ok())
+}
+
+fn parse_gstatus_i64(value: &str) -> Option<i64> {
+    value
+        .split_whitespace()
+        .next()
+        .and_then(|val| val.parse::<i64>().ok())
+}
+
+/// Parse hex value with decimal in brackets, e.g. '0012AB34 (1223476)'
+fn parse_gstatus_hex(value: &str) -> Option<i64> {
+    value
+        .split_whitespace()
+        .next()
+        .and_then(|val| i64::from_str_radix(val, 16).ok())
+}
+
+/// Get index and field name of secondary cell key without 'LTE ' prefix,
+/// e.g. 'Scell band' -> (1, "band"), 'SCC2 chan' -> (2, "chan")
+fn parse_secondary_cell_key(key: &str) -> Option<(u32, &str)> {
+    if let Some(field) = key.strip_prefix("Scell ") {
+        return Some((1, field.trim()));
+    }
+
+    let key = key
+        .strip_prefix("SCC")
+        .or_else(|| key.strip_prefix("SSC"))?;
+    let (index, field) = key.split_once(' ')?;
+    Some((index.parse::<u32>().ok()?, field.trim()))
+}
+
+impl GstatusInfo {
+    /// Parse lines of `AT!GSTATUS?` response
+    pub fn parse(lines: &[String]) -> Self {
+        let mut info = GstatusInfo::default();
+        // State of the only secondary cell is reported as state of carrier aggregation
+        let mut ca_state = String::new();
+
+        for (key, value) in get_gstatus_pairs(lines) {
+            match key.as_str() {
+                "System mode" => info.system_mode = value,
+                "PS state" => info.ps_state = value,
+                "LTE band" => info.lte_band = value,
+                "LTE bw" => info.lte_bandwidth = value,
+                "LTE Rx chan" => info.lte_rx_channel = parse_gstatus_i64(&value),
+                "LTE Tx chan" => info.lte_tx_channel = parse_gstatus_i64(&value),
+                "LTE CA state" => ca_state = value,
+                "RSRQ (dB)" => info.rsrq = parse_gstatus_f64(&value),
+                "SINR (dB)" => info.sinr = parse_gstatus_f64(&value),
+                "TAC" => info.tac = parse_gstatus_hex(&value),
+                "Cell ID" => info.cell_id = parse_gstatus_hex(&value),
+                "Temperature" => info.temperature = parse_gstatus_i64(&value),
+                "EMM state" => info.emm_state = value,
+                "RRC state" => info.rrc_state = value,
+                // RSRP of the chain from the previous column
+                "RSRP (dBm)" => {
+                    if let Some(rx_chain) = info.rx_chains.last_mut() {
+                        rx_chain.rsrp = parse_gstatus_f64(&value);
+                    }
+                }
+                _ => info.parse_other_pair(&key, &value),
+            }
+        }
+
+        if let Some(secondary_cell) = info
+            .secondary_cells
+            .iter_mut()
+            .find(|cell| cell.index == 1 && cell.state.is_empty())
+        {
+            secondary_cell.state = ca_state;
+        }
+
+        info
+    }
+
+    /// Parse signal of receive chains and parameters of secondary cells
+    fn parse_other_pair(&mut self, key: &str, value: &str) {
+        if key.starts_with("PCC Rx") || key.starts_with("SCC Rx") {
+            if let Some(name) = key.strip_suffix(" RSSI") {
+                self.rx_chains.push(RxChain {
+                    name: name.to_string(),
+                    rssi: parse_gstatus_f64(value),
+                    rsrp: None,
+                });
+            } else if let Some(name) = key.strip_suffix(" RSRP")
+                && let Some(rx_chain) = self.rx_chains.iter_mut().find(|c| c.name == name)
+            {
+                rx_chain.rsrp = parse_gstatus_f64(value);
+            }
+            return;
+        }
+
+        let Some((index, field)) = key.strip_prefix("LTE ").and_then(parse_secondary_cell_key)
+        else {
+            return;
+        };
+
+        let position = match self
+            .secondary_cells
+            .iter()
+            .position(|cell| cell.index == index)
+        {
+            Some(position) => position,
+            None => {
+                self.secondary_cells.push(SecondaryCell {
+                    index,
+                    ..SecondaryCell::default()
+                });
+                self.secondary_cells.len() - 1
+            }
+        };
+        let secondary_cell = &mut self.secondary_cells[position];
+
+        match field {
+            "state" => secondary_cell.state = value.to_string(),
+            "band" => secondary_cell.band = value.to_string(),
+            "bw" => secondary_cell.bandwidth = value.to_string(),
+            "chan" => secondary_cell.rx_channel = parse_gstatus_i64(value),
+            _ => {}
+        }
+    }
+}
+
+fn fmt_optional<T: fmt::Display>(value: Option<T>, unit: &str) -> String {
+    value.map_or_else(|| "-".to_string(), |value| format!("{value}{unit}"))
+}
+
+impl fmt::Display for GstatusInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "System mode: {}", self.system_mode)?;
+        writeln!(f, "PS state: {}", self.ps_state)?;
+        writeln!(f, "EMM state: {}", self.emm_state)?;
+        writeln!(f, "RRC state: {}", self.rrc_state)?;
+        writeln!(f, "LTE band: {}", self.lte_band)?;
+        writeln!(f, "LTE bandwidth: {}", self.lte_bandwidth)?;
+        writeln!(
+            f,
+            "LTE Rx/Tx channel: {}/{}",
+            fmt_optional(self.lte_rx_channel, ""),
+            fmt_optional(self.lte_tx_channel, "")
+        )?;
+        writeln!(
+            f,
+            "Cell ID: {}",
+            self.cell_id
+                .map_or_else(|| "-".to_string(), |id| format!("{id:X} ({id})"))
+        )?;
+        writeln!(
+            f,
+            "TAC: {}",
+            self.tac
+                .map_or_else(|| "-".to_string(), |tac| format!("{tac:X} ({tac})"))
+        )?;
+        writeln!(f, "RSRQ: {}", fmt_optional(self.rsrq, " dB"))?;
+        writeln!(f, "SINR: {}", fmt_optional(self.sinr, " dB"))?;
+        for rx_chain in &self.rx_chains {
+            writeln!(
+                f,
+                "{} RSSI/RSRP: {}/{}",
+                rx_chain.name,
+                fmt_optional(rx_chain.rssi, " dBm"),
+                fmt_optional(rx_chain.rsrp, " dBm")
+            )?;
+        }
+        for cell in &self.secondary_cells {
+            writeln!(
+                f,
+                "SCC{}: {} {} {} channel {}",
+                cell.index,
+                cell.state,
+                cell.band,
+                cell.bandwidth,
+                fmt_optional(cell.rx_channel, "")
+            )?;
+        }
+        write!(f, "Temperature: {}", fmt_optional(self.temperature, "°C"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(response: &str) -> Vec<String> {
+        response.lines().map(ToString::to_string).collect()
+    }
+
+    /// MR1100 with several secondary carriers
+    const MR1100_GSTATUS: &str = "!GSTATUS: 
+Current Time:  12345\t\tTemperature: 42
+Reset Counter: 2\t\tMode:        ONLINE         
+System mode:   LTE        \tPS state:    Attached     
+LTE band:      B3     \t\tLTE bw:      20 MHz  
+LTE Rx chan:   1300\t\tLTE Tx chan:   19300
+LTE SCC1 state:ACTIVE\t\tLTE SCC1 band:B7    
+LTE SCC1 bw  : 20 MHz  \t\tLTE SCC1 chan:3100
+LTE SCC2 state:INACTIVE\t\tLTE SCC2 band:B20   
+LTE SCC2 bw  : 10 MHz  \t\tLTE SCC2 chan:6300
+EMM state:     Registered     \tNormal Service 
+RRC state:     RRC Connected  
+IMS reg state: No Srv  \t\t
+
+PCC RxM RSSI:  -65\t\tPCC RxM RSRP:  -93
+PCC RxD RSSI:  -68\t\tPCC RxD RSRP:  -97
+Tx Power:      --\t\tTAC:         3F06 (16134)
+RSRQ (dB):     -9.3\t\tCell ID:     0145B20A (21344778)
+SINR (dB):     13.6";
+
+    /// AC785S with the only secondary carrier
+    const AC785S_GSTATUS: &str = "!GSTATUS: 
+Current Time:  4402\t\tTemperature: 38
+Bootup Time:   0\t\tMode:        ONLINE         
+System mode:   LTE        \tPS state:    Attached     
+LTE band:      B3     \t\tLTE bw:      20 MHz  
+LTE Rx chan:   1300\t\tLTE Tx chan:   19300
+LTE CA state:  ACTIVE \tLTE Scell band:B7    
+LTE Scell bw:  20 MHz  \t\tLTE Scell chan:3100
+EMM state:     Registered     \tNormal Service 
+RRC state:     RRC Connected  
+IMS reg state: No Srv  \t\t
+
+PCC RxM RSSI:  -69\t\tRSRP (dBm):  -98
+PCC RxD RSSI:  -72\t\tRSRP (dBm):  -101
+SCC RxM RSSI:  -73\t\tRSRP (dBm):  -103
+SCC RxD RSSI:  -77\t\tRSRP (dBm):  -107
+Tx Power:      --\t\tTAC:         01F2 (498)
+RSRQ (dB):     -10.9\t\tCell ID:     0012AB34 (1223476)
+SINR (dB):     10.2";
+
+    fn get_rx_chain<'a>(info: &'a GstatusInfo, name: &str) -> &'a RxChain {
+        info.rx_chains
+            .iter()
+            .find(|rx_chain| rx_chain.name == name)
+            .unwrap_or_else(|| panic!("{name} is not parsed"))
+    }
+
+    #[test]
+    fn mr1100() {
+        let info = GstatusInfo::parse(&lines(MR1100_GSTATUS));
+
+        assert_eq!(info.system_mode, "LTE");
+        assert_eq!(info.ps_state, "Attached");
+        assert_eq!(info.lte_band, "B3");
+        assert_eq!(info.lte_bandwidth, "20 MHz");
+        assert_eq!(info.lte_rx_channel, Some(1300));
+        assert_eq!(info.lte_tx_channel, Some(19300));
+        assert_eq!(info.temperature, Some(42));
+        assert_eq!(info.tac, Some(0x3F06));
+        assert_eq!(info.cell_id, Some(0x0145_B20A));
+        assert_eq!(info.rsrq, Some(-9.3));
+        assert_eq!(info.sinr, Some(13.6));
+
+        assert_eq!(info.rx_chains.len(), 2);
+        let main = get_rx_chain(&info, "PCC RxM");
+        assert_eq!((main.rssi, main.rsrp), (Some(-65.0), Some(-93.0)));
+        let diversity = get_rx_chain(&info, "PCC RxD");
+        assert_eq!((diversity.rssi, diversity.rsrp), (Some(-68.0), Some(-97.0)));
+
+        assert_eq!(info.secondary_cells.len(), 2);
+        let scc1 = &info.secondary_cells[0];
+        assert_eq!(scc1.index, 1);
+        assert_eq!(scc1.state, "ACTIVE");
+        assert_eq!(scc1.band, "B7");
+        assert_eq!(scc1.bandwidth, "20 MHz");
+        assert_eq!(scc1.rx_channel, Some(3100));
+        let scc2 = &info.secondary_cells[1];
+        assert_eq!(scc2.index, 2);
+        assert_eq!(scc2.state, "INACTIVE");
+        assert_eq!(scc2.band, "B20");
+        assert_eq!(scc2.rx_channel, Some(6300));
+    }
+
+    #[test]
+    fn ac785s() {
+        let info = GstatusInfo::parse(&lines(AC785S_GSTATUS));
+
+        assert_eq!(info.tac, Some(0x1F2));
+        assert_eq!(info.cell_id, Some(0x0012_AB34));
+        assert_eq!(info.rsrq, Some(-10.9));
+        assert_eq!(info.sinr, Some(10.2));
+
+        // RSRP is reported in the column next to RSSI of the same chain
+        assert_eq!(info.rx_chains.len(), 4);
+        let main = get_rx_chain(&info, "PCC RxM");
+        assert_eq!((main.rssi, main.rsrp), (Some(-69.0), Some(-98.0)));
+        let diversity = get_rx_chain(&info, "PCC RxD");
+        assert_eq!(
+            (diversity.rssi, diversity.rsrp),
+            (Some(-72.0), Some(-101.0))
+        );
+        let scc_diversity = get_rx_chain(&info, "SCC RxD");
+        assert_eq!(scc_diversity.rsrp, Some(-107.0));
+
+        // State of the only secondary cell is taken from carrier aggregation state
+        assert_eq!(info.secondary_cells.len(), 1);
+        let scell = &info.secondary_cells[0];
+        assert_eq!(scell.index, 1);
+        assert_eq!(scell.state, "ACTIVE");
+        assert_eq!(scell.band, "B7");
+        assert_eq!(scell.bandwidth, "20 MHz");
+        assert_eq!(scell.rx_channel, Some(3100));
+    }
+
+    #[test]
+    fn emm_and_rrc_state() {
+        let info = GstatusInfo::parse(&lines(AC785S_GSTATUS));
+        // Column without key continues value of EMM state
+        assert_eq!(info.emm_state, "Registered Normal Service");
+        assert_eq!(info.rrc_state, "RRC Connected");
+    }
+
+    #[test]
+    fn missing_fields() {
+        let response = "!GSTATUS: 
+Current Time:  77\t\tTemperature: 35
+System mode:   LTE        \tPS state:    Attached     
+LTE band:      B20    \t\tLTE bw:      10 MHz  
+EMM state:     Registered     \tNormal Service 
+RRC state:     RRC Idle       
+
+PCC RxM RSSI:  -80\t\tRSRP (dBm):  -110
+Tx Power:      --\t\tTAC:         --
+RSRQ (dB):     --\t\tCell ID:     0012AB34 (1223476)";
+        let info = GstatusInfo::parse(&lines(response));
+
+        assert_eq!(info.lte_band, "B20");
+        assert_eq!(info.lte_rx_channel, None);
+        assert_eq!(info.tac, None);
+        assert_eq!(info.rsrq, None);
+        assert_eq!(info.sinr, None);
+        assert_eq!(info.cell_id, Some(0x0012_AB34));
+        assert!(info.secondary_cells.is_empty());
+
+        assert_eq!(info.rx_chains.len(), 1);
+        let main = get_rx_chain(&info, "PCC RxM");
+        assert_eq!((main.rssi, main.rsrp), (Some(-80.0), Some(-110.0)));
+    }
+}
@@ -0,0 +1,11 @@
+//! Access to AT command port of Netgear modems (Sierra Wireless modules) over telnet
+
+mod connection;
+mod gstatus;
+mod lteinfo;
+
+pub use connection::{
+    Connection, ConnectionError, ConnectionOptions, ModemInfo, NETGEAR_TELNET_PORT,
+};
+pub use gstatus::{GstatusInfo, RxChain, SecondaryCell};
+pub use lteinfo::{CellKind, LteCell, LteInfo};
@@ -0,0 +1,250 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CellKind {
+    Serving,
+    IntraFrequency,
+    InterFrequency,
+}
+
+impl CellKind {
+    /// Label of the table in `AT!LTEINFO?` response
+    fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "Serving:" => Some(CellKind::Serving),
+            "IntraFreq:" => Some(CellKind::IntraFrequency),
+            "InterFreq:" => Some(CellKind::InterFrequency),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CellKind::Serving => "Serving",
+            CellKind::IntraFrequency => "Intra",
+            CellKind::InterFrequency => "Inter",
+        }
+    }
+}
+
+/// LTE cell from a table of `AT!LTEINFO?` response
+#[derive(Clone, Debug)]
+pub struct LteCell {
+    pub kind: CellKind,
+    pub earfcn: Option<i64>,
+    pub pci: Option<i64>,
+    pub rsrq: Option<f64>,
+    pub rsrp: Option<f64>,
+    pub rssi: Option<f64>,
+    pub rxlev: Option<i64>,
+}
+
+impl LteCell {
+    /// Parse row of the table by names of columns from the header
+    fn parse(kind: CellKind, columns: &[&str], values: &[&str]) -> Self {
+        let get_value = |column: &str| {
+            columns
+                .iter()
+                .position(|c| *c == column)
+                .and_then(|index| values.get(index))
+        };
+        let get_i64 = |column: &str| get_value(column).and_then(|val| val.parse::<i64>().ok());
+        let get_f64 = |column: &str| get_value(column).and_then(|val| val.parse::<f64>().ok());
+
+        Self {
+            kind,
+            earfcn: get_i64("EARFCN"),
+            pci: get_i64("PCI"),
+            rsrq: get_f64("RSRQ"),
+            rsrp: get_f64("RSRP"),
+            rssi: get_f64("RSSI"),
+            rxlev: get_i64("RXLV"),
+        }
+    }
+}
+
+/// Serving and neighbor cells reported by `AT!LTEINFO?` of Sierra Wireless modules
+#[derive(Debug, Default)]
+pub struct LteInfo {
+    pub serving: Option<LteCell>,
+    pub intra_frequency: Vec<LteCell>,
+    pub inter_frequency: Vec<LteCell>,
+}
+
+impl LteInfo {
+    /// Parse lines of `AT!LTEINFO?` response. Each table starts with header line like
+    /// 'InterFreq: EARFCN ThresholdLow ThresholdHi Priority PCI RSRQ RSRP RSSI RXLV'
+    /// followed by rows of values. Table ends at the header of the next one
+    pub fn parse(lines: &[String]) -> Self {
+        let mut info = LteInfo::default();
+
+        // Current table and its columns. Tables of other RATs (e.g. 'WCDMA:') are skipped
+        let mut table: Option<(CellKind, Vec<&str>)> = None;
+
+        for line in lines {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+
+            if let Some(label) = tokens.first().filter(|token| token.ends_with(':')) {
+                table = CellKind::from_label(label).map(|kind| (kind, tokens[1..].to_vec()));
+                continue;
+            }
+
+            let Some((kind, columns)) = &table else {
+                continue;
+            };
+            // Line that doesn't match the header is not a row of the table
+            if tokens.len() != columns.len() {
+                continue;
+            }
+
+            let cell = LteCell::parse(*kind, columns, &tokens);
+            match kind {
+                CellKind::Serving => info.serving = Some(cell),
+                CellKind::IntraFrequency => info.intra_frequency.push(cell),
+                CellKind::InterFrequency => info.inter_frequency.push(cell),
+            }
+        }
+
+        // Intra-frequency table includes the serving cell and doesn't have EARFCN column
+        let serving_pci = info.serving.as_ref().and_then(|cell| cell.pci);
+        if serving_pci.is_some() {
+            info.intra_frequency.retain(|cell| cell.pci != serving_pci);
+        }
+        let serving_earfcn = info.serving.as_ref().and_then(|cell| cell.earfcn);
+        for cell in &mut info.intra_frequency {
+            cell.earfcn = cell.earfcn.or(serving_earfcn);
+        }
+
+        info
+    }
+
+    /// All cells with the strongest RSRP first. Cells without RSRP are the last
+    pub fn get_cells_by_rsrp(&self) -> Vec<&LteCell> {
+        let mut cells: Vec<&LteCell> = self
+            .serving
+            .iter()
+            .chain(&self.intra_frequency)
+            .chain(&self.inter_frequency)
+            .collect();
+        cells.sort_by(|a, b| {
+            let a = a.rsrp.unwrap_or(f64::NEG_INFINITY);
+            let b = b.rsrp.unwrap_or(f64::NEG_INFINITY);
+            b.total_cmp(&a)
+        });
+        cells
+    }
+}
+
+fn fmt_optional<T: fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+fn fmt_optional_signal(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| format!("{value:.1}"))
+}
+
+impl fmt::Display for LteInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<8} {:>6} {:>4} {:>6} {:>7} {:>6} {:>5}",
+            "Cell", "EARFCN", "PCI", "RSRQ", "RSRP", "RSSI", "RxLev"
+        )?;
+        for cell in self.get_cells_by_rsrp() {
+            write!(
+                f,
+                "\n{:<8} {:>6} {:>4} {:>6} {:>7} {:>6} {:>5}",
+                cell.kind.as_str(),
+                fmt_optional(cell.earfcn),
+                fmt_optional(cell.pci),
+                fmt_optional_signal(cell.rsrq),
+                fmt_optional_signal(cell.rsrp),
+                fmt_optional_signal(cell.rssi),
+                fmt_optional(cell.rxlev)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(response: &str) -> Vec<String> {
+        response.lines().map(ToString::to_string).collect()
+    }
+
+    const LTEINFO_RESPONSE: &str = "!LTEINFO: 
+Serving:   EARFCN MCC MNC   TAC      CID Bd D U SNR PCI  RSRQ   RSRP   RSSI RXLV
+             1300 262  03 16134 21344778  3 5 5   9 187 -11.0  -95.0  -66.0 --
+IntraFreq:                                          PCI  RSRQ   RSRP   RSSI RXLV
+                                                    187 -11.0  -95.0  -66.0 --
+                                                    201 -15.5 -104.2  -75.1 --
+InterFreq: EARFCN ThresholdLow ThresholdHi Priority PCI  RSRQ   RSRP   RSSI RXLV
+             3100            0           0        4  54 -12.3  -88.7  -61.2 --
+             6300            0           0        2 311 -19.0     --     -- --
+WCDMA:     UARFCN ThreshL ThreshH Prio PSC   RSCP  ECN0 RSSI SRXLV
+            10588       0       0    1  12 -101.0 -12.5  -89    6";
+
+    #[test]
+    fn parse() {
+        let info = LteInfo::parse(&lines(LTEINFO_RESPONSE));
+
+        let serving = info.serving.as_ref().unwrap();
+        assert_eq!(serving.kind, CellKind::Serving);
+        assert_eq!(serving.earfcn, Some(1300));
+        assert_eq!(serving.pci, Some(187));
+        assert_eq!(serving.rsrq, Some(-11.0));
+        assert_eq!(serving.rsrp, Some(-95.0));
+        assert_eq!(serving.rssi, Some(-66.0));
+        assert_eq!(serving.rxlev, None);
+
+        // Serving cell is not repeated as intra-frequency neighbor
+        assert_eq!(info.intra_frequency.len(), 1);
+        assert_eq!(info.intra_frequency[0].pci, Some(201));
+        // Intra-frequency cells are on the channel of the serving cell
+        assert_eq!(info.intra_frequency[0].earfcn, Some(1300));
+        assert_eq!(info.intra_frequency[0].rsrp, Some(-104.2));
+
+        // WCDMA table ends inter-frequency table
+        assert_eq!(info.inter_frequency.len(), 2);
+        assert_eq!(info.inter_frequency[0].earfcn, Some(3100));
+        assert_eq!(info.inter_frequency[0].pci, Some(54));
+        assert_eq!(info.inter_frequency[1].rsrp, None);
+    }
+
+    #[test]
+    fn header_without_rows() {
+        let response = "!LTEINFO: 
+Serving:   EARFCN MCC MNC   TAC      CID Bd D U SNR PCI  RSRQ   RSRP   RSSI RXLV
+             1300 262  03 16134 21344778  3 5 5   9 187 -11.0  -95.0  -66.0 --
+IntraFreq:                                          PCI  RSRQ   RSRP   RSSI RXLV
+InterFreq: EARFCN ThresholdLow ThresholdHi Priority PCI  RSRQ   RSRP   RSSI RXLV";
+        let info = LteInfo::parse(&lines(response));
+
+        assert!(info.serving.is_some());
+        assert!(info.intra_frequency.is_empty());
+        assert!(info.inter_frequency.is_empty());
+    }
+
+    #[test]
+    fn cells_by_rsrp() {
+        let info = LteInfo::parse(&lines(LTEINFO_RESPONSE));
+        let cells = info
+            .get_cells_by_rsrp()
+            .iter()
+            .map(|cell| (cell.kind, cell.pci))
+            .collect::<Vec<(CellKind, Option<i64>)>>();
+
+        assert_eq!(
+            cells,
+            vec![
+                (CellKind::InterFrequency, Some(54)),
+                (CellKind::Serving, Some(187)),
+                (CellKind::IntraFrequency, Some(201)),
+                (CellKind::InterFrequency, Some(311)),
+            ]
+        );
+    }
+}
@@ -1,129 +1,15 @@
-use clap::{Parser, Subcommand};
-use telnet::{Event, Telnet};
-
-/*****************************************************************************
- * Telnet connection helper
- *****************************************************************************/
-
-const NETGEAR_TELNET_PORT: u16 = 5510;
-
-struct ModemInfo {
-    manufacturer: String,
-    model: String,
-    revision: String,
-}
-
-impl ModemInfo {
-    fn default() -> Self {
-        Self {
-            manufacturer: String::default(),
-            model: String::default(),
-            revision: String::default(),
-        }
-    }
-}
-
-struct Connection {
-    telnet: telnet::Telnet,
-}
-
-impl Connection {
-    fn new(host: &str) -> Self {
-        const BUF_SIZE: usize = 128;
-        let telnet =
-            Telnet::connect((host, NETGEAR_TELNET_PORT), BUF_SIZE).expect("Cannot connect to host");
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 
-        Self { telnet }
-    }
-    fn send(&mut self, cmd: &str) -> Option<Vec<String>> {
-        let command_str = format!("{cmd}\r");
-        let bytes = self
-            .telnet
-            .write(command_str.as_bytes())
-            .inspect_err(|e| eprintln!("Telnet write error: {e:?}"))
-            .ok()
-            .unwrap_or_default();
-
-        if bytes == 0 {
-            eprintln!("Unable to write command to telnet");
-            return None;
-        }
-
-        let mut str = String::default();
-        loop {
-            let event = self
-                .telnet
-                .read()
-                .inspect_err(|e| eprintln!("Telnet read error: {e:?}"))
-                .ok();
-
-            match event {
-                None => {
-                    break;
-                }
-                Some(event) => match event {
-                    Event::Data(buffer) => {
-                        let s = String::from_utf8_lossy(&buffer);
-                        str.push_str(&s);
-
-                        if s.ends_with("\r\nOK\r\n") {
-                            break;
-                        }
-                    }
-                    Event::NoData => {
-                        break;
-                    }
-                    _ => {
-                        println!("Unhandled during reading from telnet: {event:?}");
-                    }
-                },
-            }
-        }
+use clap::{Parser, Subcommand};
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
 
-        Some(
-            str.split("\r\n")
-                .map(std::string::ToString::to_string)
-                .collect(),
-        )
-    }
-    fn ati(&mut self) -> Option<ModemInfo> {
-        self.send("ATI").map(|lines| {
-            let mut data = ModemInfo::default();
-
-            for line in lines {
-                if line.contains("Revision") {
-                    data.revision = line
-                        .split_once(':')
-                        .unwrap_or_default()
-                        .1
-                        .trim()
-                        .to_string();
-                } else if line.contains("Model") {
-                    data.model = line
-                        .split_once(':')
-                        .unwrap_or_default()
-                        .1
-                        .trim()
-                        .to_string();
-                } else if line.contains("Manufacturer") {
-                    data.manufacturer = line
-                        .split_once(':')
-                        .unwrap_or_default()
-                        .1
-                        .trim()
-                        .to_string();
-                }
-            }
-
-            data
-        })
-    }
-    fn gstatus(&mut self) {
-        if let Some(lines) = self.send("AT !GSTATUS?") {
-            println!("{:?}", &lines);
-        }
-    }
-}
+use netgear_telnet::{Connection, ConnectionError, ConnectionOptions, NETGEAR_TELNET_PORT};
 
 /*****************************************************************************
  * Argument parser
@@ -135,6 +21,18 @@ impl Connection {
 struct Cli {
     #[command(subcommand)]
     command: CliCommands,
+
+    /// Telnet port of AT command interface
+    #[arg(long, global = true, default_value_t = NETGEAR_TELNET_PORT)]
+    port: u16,
+
+    /// Connection timeout in seconds
+    #[arg(long, global = true, default_value_t = 5)]
+    connect_timeout: u64,
+
+    /// Timeout of waiting for response in seconds
+    #[arg(long, global = true, default_value_t = 10)]
+    read_timeout: u64,
 }
 
 #[derive(Debug, Subcommand)]
@@ -143,44 +41,144 @@ enum CliCommands {
     ModemInfo { host: String },
     /// Connection status
     ConnectionStatus { host: String },
+    /// Serving and neighbor LTE cells sorted by RSRP
+    Neighbors { host: String },
+    /// Interactive console for AT commands
+    Console {
+        host: String,
+        /// Save commands and responses to the file
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
 }
 
 /*****************************************************************************
  * Commands implementation
  *****************************************************************************/
-fn modem_info(host: &str) {
-    let mut connection = Connection::new(host);
-    let info = connection.ati();
-    match info {
-        None => {
-            eprintln!("Cannot get ATI info");
-        }
-        Some(info) => {
-            println!("Manufacturer: {}", info.manufacturer);
-            println!("Model: {}", info.model);
-            println!("Revision: {}", info.revision);
+fn modem_info(host: &str, options: &ConnectionOptions) -> Result<(), Box<dyn Error>> {
+    let mut connection = Connection::connect(host, options)?;
+    let info = connection.ati()?;
+
+    println!("Manufacturer: {}", info.manufacturer);
+    println!("Model: {}", info.model);
+    println!("Revision: {}", info.revision);
+    Ok(())
+}
+
+fn connection_status(host: &str, options: &ConnectionOptions) -> Result<(), Box<dyn Error>> {
+    let mut connection = Connection::connect(host, options)?;
+    let info = connection.gstatus()?;
+
+    println!("{info}");
+    Ok(())
+}
+
+fn neighbors(host: &str, options: &ConnectionOptions) -> Result<(), Box<dyn Error>> {
+    let mut connection = Connection::connect(host, options)?;
+    let info = connection.lteinfo()?;
+
+    println!("{info}");
+    Ok(())
+}
+
+/// Format response with each tab-separated column (e.g. of `AT!GSTATUS?`) on its own line
+/// and aligned 'key: value' pairs
+fn format_response(result: &Result<Vec<String>, ConnectionError>) -> String {
+    match result {
+        Ok(lines) => {
+            let mut response = String::new();
+            for column in lines.iter().flat_map(|line| line.split('\t')) {
+                let column = match column.split_once(':') {
+                    Some((key, value)) => format!("{}: {}", key.trim(), value.trim())
+                        .trim_end()
+                        .to_string(),
+                    None => column.trim().to_string(),
+                };
+                if !column.is_empty() {
+                    response.push_str(&format!("  {column}\n"));
+                }
+            }
+            response.push_str("OK");
+            response
         }
+        Err(e) => e.to_string(),
     }
 }
 
-fn connection_status(host: &str) {
-    let mut connection = Connection::new(host);
-    connection.gstatus();
+fn console(
+    host: &str,
+    options: &ConnectionOptions,
+    log: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let mut connection = Connection::connect(host, options)?;
+    let mut transcript = log.map(File::create).transpose()?;
+    let mut editor = DefaultEditor::new()?;
+
+    println!(
+        "Connected to {host}:{}. Type 'exit' or press Ctrl-D to quit",
+        options.port
+    );
+
+    loop {
+        let cmd = match editor.readline("AT> ") {
+            Ok(line) => line.trim().to_string(),
+            // Ctrl-C clears current line
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if cmd.is_empty() {
+            continue;
+        }
+        if cmd == "exit" || cmd == "quit" {
+            break;
+        }
+        editor.add_history_entry(&cmd)?;
+
+        let result = connection.send(&cmd);
+        let response = format_response(&result);
+
+        if let Some(transcript) = transcript.as_mut() {
+            writeln!(transcript, "> {cmd}\n{response}")?;
+        }
+
+        // Modem still accepts commands after error result code or slow response
+        match result {
+            Ok(_)
+            | Err(ConnectionError::Command(_) | ConnectionError::Timeout | ConnectionError::Busy) =>
+            {
+                println!("{response}");
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
 }
 
 /*****************************************************************************
  * Main
  *****************************************************************************/
 
-fn main() {
+fn main() -> ExitCode {
     let args = Cli::parse();
 
-    match args.command {
-        CliCommands::ModemInfo { host } => {
-            modem_info(&host);
-        }
-        CliCommands::ConnectionStatus { host } => {
-            connection_status(&host);
-        }
+    let options = ConnectionOptions {
+        port: args.port,
+        connect_timeout: Duration::from_secs(args.connect_timeout),
+        read_timeout: Duration::from_secs(args.read_timeout),
+    };
+
+    let result = match args.command {
+        CliCommands::ModemInfo { host } => modem_info(&host, &options),
+        CliCommands::ConnectionStatus { host } => connection_status(&host, &options),
+        CliCommands::Neighbors { host } => neighbors(&host, &options),
+        CliCommands::Console { host, log } => console(&host, &options, log.as_deref()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{e}");
+        return ExitCode::FAILURE;
     }
+    ExitCode::SUCCESS
 }